@@ -0,0 +1,289 @@
+#![forbid(unsafe_code)]
+
+// Semantic COM API on top of a ComTransport.
+//
+// Application code calls `wlan_set_credentials`, `get_ipv4_conf` and friends, and never sees a
+// raw verb or a word count: each method picks the ComSpec, serializes its arguments with the
+// serdes structs, and decodes the response.
+
+use crate::eap::EapCredentials;
+use crate::payload::{response, Command};
+use crate::scan::ScanFetch;
+use crate::serdes::{
+    EapCommitStatus, FlashRange, Ipv4Conf, MacAddress, Mtu, ProtocolVersion, ScanResult, ScanResultPage, SerdesError,
+    TrafficStats, WpsPin, WpsPinStart,
+};
+use crate::transport::{ComTransport, TransportError};
+use crate::{verbs, ComState};
+
+/// Errors from ComClient
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ClientError<E> {
+    /// the exchange failed
+    Transport(TransportError<E>),
+    /// an argument could not be serialized
+    Serdes(SerdesError),
+}
+impl<E> From<TransportError<E>> for ClientError<E> {
+    fn from(e: TransportError<E>) -> Self {
+        ClientError::Transport(e)
+    }
+}
+impl<E> From<SerdesError> for ClientError<E> {
+    fn from(e: SerdesError) -> Self {
+        ClientError::Serdes(e)
+    }
+}
+
+/// High-level COM client
+pub struct ComClient<T> {
+    transport: T,
+}
+impl<T: ComTransport> ComClient<T> {
+    pub fn new(transport: T) -> Self {
+        ComClient { transport }
+    }
+    /// The underlying transport, for verbs without a semantic method
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+    pub fn into_inner(self) -> T {
+        self.transport
+    }
+
+    /// Issue any verb with a typed request and response, e.g.
+    /// `client.exchange::<verbs::IMU_WOM_CONFIG>(&config)`
+    pub fn exchange<C: Command>(&mut self, req: &C::Req) -> Result<C::Resp, ClientError<T::Error>> {
+        let tx = C::encode_req(req)?;
+        let mut rx = response::<C>();
+        self.transport.exchange(&C::SPEC, tx.as_ref(), rx.as_mut())?;
+        Ok(C::decode_resp(&rx)?)
+    }
+
+    /// Returns the inverted argument and the EC's ping counter
+    pub fn link_ping(&mut self, arg: u16) -> Result<(u16, u16), ClientError<T::Error>> {
+        self.exchange::<verbs::LINK_PING>(&arg)
+    }
+
+    pub fn protocol_version(&mut self) -> Result<ProtocolVersion, ClientError<T::Error>> {
+        self.exchange::<verbs::LINK_PROTOCOL_VERSION>(&())
+    }
+
+    pub fn wlan_on(&mut self) -> Result<(), ClientError<T::Error>> {
+        self.exchange::<verbs::WLAN_ON>(&())
+    }
+
+    pub fn wlan_off(&mut self) -> Result<(), ClientError<T::Error>> {
+        self.exchange::<verbs::WLAN_OFF>(&())
+    }
+
+    /// Set the SSID and passphrase used by the next `wlan_join`.
+    /// Both strings are checked before anything is sent.
+    pub fn wlan_set_credentials(&mut self, ssid: &str, pass: &str) -> Result<(), ClientError<T::Error>> {
+        let ssid_words = <verbs::WLAN_SET_SSID as Command>::encode_req(ssid)?;
+        let pass_words = <verbs::WLAN_SET_PASS as Command>::encode_req(pass)?;
+        self.transport.command(&ComState::WLAN_SET_SSID, &ssid_words)?;
+        self.transport.command(&ComState::WLAN_SET_PASS, &pass_words)?;
+        Ok(())
+    }
+
+    /// Pin the next `wlan_join` to the AP with this BSSID, or remove the pin with None
+    pub fn wlan_set_bssid(&mut self, bssid: Option<MacAddress>) -> Result<(), ClientError<T::Error>> {
+        match bssid {
+            Some(bssid) => self.exchange::<verbs::WLAN_SET_BSSID>(&bssid),
+            None => self.exchange::<verbs::WLAN_CLEAR_BSSID>(&()),
+        }
+    }
+
+    /// Upload WPA2-Enterprise credentials for a known-network slot. The slot keeps its previous
+    /// credentials unless the EC reports EapCommitStatus::Ok.
+    pub fn wlan_eap_provision(&mut self, creds: &EapCredentials) -> Result<EapCommitStatus, ClientError<T::Error>> {
+        self.exchange::<verbs::WLAN_EAP_CONFIG>(&creds.config()?)?;
+        for fragment in creds.fragments() {
+            self.exchange::<verbs::WLAN_EAP_FRAGMENT>(&fragment)?;
+        }
+        self.exchange::<verbs::WLAN_EAP_COMMIT>(&())
+    }
+
+    /// Start WPS enrollment into a known-network slot: with the PIN the user enters on the AP, or
+    /// in push-button mode if None. The outcome arrives with INT_WLAN_WPS.
+    pub fn wlan_wps_start(&mut self, slot: u8, pin: Option<WpsPin>) -> Result<(), ClientError<T::Error>> {
+        match pin {
+            Some(pin) => self.exchange::<verbs::WLAN_WPS_PIN_START>(&WpsPinStart { slot, pin }),
+            None => self.exchange::<verbs::WLAN_WPS_PBC_START>(&(slot as u16)),
+        }
+    }
+
+    /// Start joining the configured network. The result arrives with INT_WLAN_CONNECT_EVENT.
+    pub fn wlan_join(&mut self) -> Result<(), ClientError<T::Error>> {
+        self.exchange::<verbs::WLAN_JOIN>(&())
+    }
+
+    pub fn wlan_leave(&mut self) -> Result<(), ClientError<T::Error>> {
+        self.exchange::<verbs::WLAN_LEAVE>(&())
+    }
+
+    pub fn get_ipv4_conf(&mut self) -> Result<Ipv4Conf, ClientError<T::Error>> {
+        self.exchange::<verbs::WLAN_GET_IPV4_CONF>(&())
+    }
+
+    /// The MTU the EC is using, to size the network stack's interface with
+    pub fn net_mtu(&mut self) -> Result<Mtu, ClientError<T::Error>> {
+        self.exchange::<verbs::NET_GET_MTU>(&())
+    }
+
+    /// Ask the EC for an MTU, and return the one it settled on, which may be smaller
+    pub fn net_set_mtu(&mut self, mtu: Mtu) -> Result<Mtu, ClientError<T::Error>> {
+        self.exchange::<verbs::NET_SET_MTU>(&mtu)?;
+        self.net_mtu()
+    }
+
+    /// Traffic counters of the station link since WLAN_ON
+    pub fn net_traffic_stats(&mut self) -> Result<TrafficStats, ClientError<T::Error>> {
+        self.exchange::<verbs::NET_TRAFFIC_STATS>(&())
+    }
+
+    /// The scan records not yet fetched into `fetch`, read with SSID_SCAN_FETCH a page at a time
+    /// as the iterator advances. Use a fresh ScanFetch to read the whole list, or keep one across
+    /// INT_WLAN_SSID_UPDATE interrupts to read only what the EC appended since the last call.
+    /// Iteration ends when nothing new is left, when a fetch fails (the error is yielded once), or
+    /// when a new scan has replaced the list; `fetch` then restarts from the new list on the next call.
+    pub fn scan_results<'a>(&'a mut self, fetch: &'a mut ScanFetch) -> ScanResults<'a, T> {
+        ScanResults { client: self, fetch, page: None, pos: 0, done: false }
+    }
+
+    /// Erase `len` bytes of EC flash starting at `addr`
+    pub fn flash_erase(&mut self, addr: u32, len: u32) -> Result<(), ClientError<T::Error>> {
+        Ok(self.transport.command(&ComState::FLASH_ERASE, &FlashRange { addr, len }.encode_u16())?)
+    }
+}
+
+/// Iterator over scan results, from ComClient::scan_results
+pub struct ScanResults<'a, T> {
+    client: &'a mut ComClient<T>,
+    fetch: &'a mut ScanFetch,
+    page: Option<ScanResultPage>,
+    /// position in `page`
+    pos: usize,
+    done: bool,
+}
+impl<'a, T: ComTransport> Iterator for ScanResults<'a, T> {
+    type Item = Result<ScanResult, ClientError<T::Error>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            if let Some(result) = self.page.as_ref().and_then(|page| page.results().get(self.pos)) {
+                self.pos += 1;
+                return Some(Ok(*result));
+            }
+            let start = match self.fetch.begin_fetch() {
+                Some(start) => start,
+                None => break,
+            };
+            match self.client.exchange::<verbs::SSID_SCAN_FETCH>(&start) {
+                Ok(page) => {
+                    // an empty page means the EC has nothing after all
+                    self.done = !self.fetch.on_page(&page) || page.count == 0;
+                    self.page = Some(page);
+                    self.pos = 0;
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::serdes::{EapFragment, EapItem, EapMethod, Flags16, Rssi, Security, StringSer, STR_32_WORDS};
+    use crate::transport::mock::MockCom;
+    use std::vec::Vec;
+    use crate::DhcpState;
+
+    #[test]
+    fn semantic_calls_issue_the_right_verbs() {
+        let mut ec = MockCom::new();
+        let mut ssid = StringSer::<STR_32_WORDS>::new();
+        let conf = Ipv4Conf { dhcp: DhcpState::Bound, addr: [10, 0, 0, 2], ..Default::default() };
+        ec.expect(&ComState::WLAN_SET_SSID, ssid.encode("precursor").unwrap(), &[])
+            .expect_verb(&ComState::WLAN_SET_PASS, &[])
+            .expect(&ComState::WLAN_SET_BSSID, &[0x1102, 0x3322, 0x5544], &[])
+            .expect(&ComState::WLAN_JOIN, &[], &[])
+            .expect(&ComState::WLAN_CLEAR_BSSID, &[], &[])
+            .expect(&ComState::WLAN_WPS_PBC_START, &[3], &[])
+            .expect(&ComState::WLAN_WPS_PIN_START, &[3, 0x6146, 0x00BC], &[])
+            .expect(&ComState::WLAN_GET_IPV4_CONF, &[], &conf.encode_u16())
+            .expect(&ComState::NET_SET_MTU, &[2000], &[])
+            .expect(&ComState::NET_GET_MTU, &[], &[1800])
+            .expect(&ComState::NET_TRAFFIC_STATS, &[], &TrafficStats { rx_packets: 5, ..Default::default() }.encode_u16())
+            .expect(&ComState::FLASH_ERASE, &[0x0000, 0x0001, 0x1000, 0x0000], &[]);
+
+        let mut client = ComClient::new(ec);
+        client.wlan_set_credentials("precursor", "hunter2").unwrap();
+        client.wlan_set_bssid(Some(MacAddress([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]))).unwrap();
+        client.wlan_join().unwrap();
+        client.wlan_set_bssid(None).unwrap();
+        client.wlan_wps_start(3, None).unwrap();
+        client.wlan_wps_start(3, Some(WpsPin(12345670))).unwrap();
+        assert_eq!(client.get_ipv4_conf().unwrap().addr, [10, 0, 0, 2]);
+        assert_eq!(client.net_set_mtu(Mtu::new(2000).unwrap()).unwrap().get(), 1800);
+        assert_eq!(client.net_traffic_stats().unwrap().rx_packets, 5);
+        client.flash_erase(0x0001_0000, 0x1000).unwrap();
+        client.into_inner().finish();
+    }
+
+    #[test]
+    fn oversized_ssid_sends_nothing() {
+        let mut client = ComClient::new(MockCom::new());
+        let long = "0123456789abcdef0123456789abcdef!";
+        assert_eq!(client.wlan_set_credentials(long, ""), Err(ClientError::Serdes(SerdesError::StrLenTooBig)));
+        client.into_inner().finish();
+    }
+
+    #[test]
+    fn scan_results_are_fetched_by_page() {
+        let results: Vec<ScanResult> = (0..6u8)
+            .map(|i| {
+                ScanResult::new(b"precursor", [2, 0, 0, 0, 0, i], Rssi::from_dbm(-50 - i as i8), 1 + i, Security::Wpa2Psk)
+            })
+            .collect();
+        let mut ec = MockCom::new();
+        ec.expect(&ComState::SSID_SCAN_FETCH, &[0], &ScanResultPage::from_results(&results, 0).encode_u16())
+            .expect(&ComState::SSID_SCAN_FETCH, &[4], &ScanResultPage::from_results(&results, 4).encode_u16());
+
+        let mut client = ComClient::new(ec);
+        let mut fetch = ScanFetch::new();
+        let fetched: Result<Vec<ScanResult>, _> = client.scan_results(&mut fetch).collect();
+        assert_eq!(fetched.unwrap(), results);
+        assert!(fetch.is_complete());
+        client.into_inner().finish();
+    }
+
+    #[test]
+    fn eap_credentials_are_uploaded_in_fragments() {
+        let cert = [0x30u8; 200];
+        let creds = EapCredentials {
+            slot: 2,
+            method: EapMethod::Ttls,
+            flags: Flags16::empty(),
+            identity: "alice",
+            password: "hunter2",
+            ca_cert: &cert,
+        };
+        let mut ec = MockCom::new();
+        ec.expect(&ComState::WLAN_EAP_CONFIG, &[2, 1, 0, 5, 7, 200], &[])
+            .expect_verb(&ComState::WLAN_EAP_FRAGMENT, &[])
+            .expect_verb(&ComState::WLAN_EAP_FRAGMENT, &[])
+            .expect(&ComState::WLAN_EAP_FRAGMENT, &EapFragment::new(EapItem::CaCert, 0, &cert[..128]).encode_u16(), &[])
+            .expect(&ComState::WLAN_EAP_FRAGMENT, &EapFragment::new(EapItem::CaCert, 1, &cert[128..]).encode_u16(), &[])
+            .expect(&ComState::WLAN_EAP_COMMIT, &[], &[EapCommitStatus::Ok as u16]);
+
+        let mut client = ComClient::new(ec);
+        assert_eq!(client.wlan_eap_provision(&creds), Ok(EapCommitStatus::Ok));
+        client.into_inner().finish();
+    }
+}
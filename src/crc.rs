@@ -0,0 +1,232 @@
+#![forbid(unsafe_code)]
+
+// Optional CRC-16 framing for COM payloads.
+//
+// When framing is enabled with LINK_SET_CRC_MODE, one trailer word is appended to every
+// non-empty payload in both directions:
+// - SoC -> EC: after the `w_words` of a verb, the SoC sends crc16 over (verb, payload...)
+// - EC -> SoC: after the `r_words` of a response, the EC sends crc16 over (verb, response...)
+// Verbs with an empty payload in a given direction carry no trailer in that direction.
+// The LINK_* protocol overhead verbs are never framed, so that the mode can always be queried,
+// changed, or recovered with LINK_SYNC regardless of what the other side thinks the mode is.
+//
+// Independently of the link framing, a payload struct can carry its own integrity word by
+// implementing `WireChecksum`: `encode_with_checksum` appends the CRC of the struct's encoded
+// words, and `decode_with_checksum` refuses a payload whose word does not match. This protects
+// the payloads where silent corruption is worst (flash ranges, credentials) end to end, whatever
+// the framing mode. A verb using it must count the extra word in its w_words/r_words.
+//
+// The CRC is CRC-16/CCITT-FALSE (poly 0x1021, init 0xFFFF, no reflection, no final xor).
+// Words are fed into the CRC as little-endian byte pairs, matching the string packing in `serdes`.
+
+use crate::serdes::{need, CredMap, FlashRange, SerdesError};
+use crate::{ComSpec, ComState};
+
+pub const CRC16_POLY: u16 = 0x1021;
+pub const CRC16_INIT: u16 = 0xFFFF;
+/// number of trailer words appended to a framed payload
+pub const CRC_TRAILER_WORDS: u16 = 1;
+
+/// Framing modes, as written with LINK_SET_CRC_MODE and read back with LINK_GET_CRC_MODE
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum CrcMode {
+    Off = 0,
+    Crc16 = 1,
+    Invalid = 0xFFFF,
+}
+impl CrcMode {
+    pub fn decode_u16(mode: u16) -> Self {
+        match mode {
+            0 => CrcMode::Off,
+            1 => CrcMode::Crc16,
+            _ => CrcMode::Invalid,
+        }
+    }
+}
+
+/// Fold one byte into a running CRC.
+pub const fn crc16_update(crc: u16, byte: u8) -> u16 {
+    let mut crc = crc ^ ((byte as u16) << 8);
+    let mut i = 0;
+    while i < 8 {
+        if crc & 0x8000 != 0 {
+            crc = (crc << 1) ^ CRC16_POLY;
+        } else {
+            crc <<= 1;
+        }
+        i += 1;
+    }
+    crc
+}
+
+/// Fold one COM word into a running CRC, low byte first.
+pub const fn crc16_update_word(crc: u16, word: u16) -> u16 {
+    crc16_update(crc16_update(crc, word as u8), (word >> 8) as u8)
+}
+
+/// CRC-16/CCITT-FALSE of a byte slice.
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(CRC16_INIT, |crc, &b| crc16_update(crc, b))
+}
+
+/// CRC-16/CCITT-FALSE of a word slice, each word fed low byte first.
+pub fn crc16_words(data: &[u16]) -> u16 {
+    data.iter().fold(CRC16_INIT, |crc, &w| crc16_update_word(crc, w))
+}
+
+/// Compute the trailer word for a payload sent or received under `verb`.
+/// The verb is covered by the CRC so that a corrupted verb word is also detected.
+pub fn frame_crc(verb: u16, payload: &[u16]) -> u16 {
+    payload.iter().fold(crc16_update_word(CRC16_INIT, verb), |crc, &w| crc16_update_word(crc, w))
+}
+
+/// Check a received payload against its trailer word.
+pub fn frame_crc_ok(verb: u16, payload: &[u16], trailer: u16) -> bool {
+    frame_crc(verb, payload) == trailer
+}
+
+/// Returns true if payloads of this spec carry a trailer when framing is on.
+pub fn is_framed(spec: &ComSpec) -> bool {
+    // protocol overhead verbs live in 0xF000-0xFFFF and are never framed
+    spec.verb & 0xF000 != 0xF000
+}
+
+/// Number of words actually written for `spec` under the given framing mode.
+pub fn framed_w_words(spec: &ComSpec, mode: CrcMode) -> u16 {
+    if mode == CrcMode::Crc16 && spec.w_words != 0 && is_framed(spec) {
+        spec.w_words + CRC_TRAILER_WORDS
+    } else {
+        spec.w_words
+    }
+}
+
+/// Number of words actually read back for `spec` under the given framing mode.
+pub fn framed_r_words(spec: &ComSpec, mode: CrcMode) -> u16 {
+    if mode == CrcMode::Crc16 && spec.r_words != 0 && is_framed(spec) {
+        spec.r_words + CRC_TRAILER_WORDS
+    } else {
+        spec.r_words
+    }
+}
+
+/// A payload struct that can be sent with a trailing checksum word
+pub trait WireChecksum: Sized {
+    /// number of words in the encoding, not counting the checksum word
+    const WORDS: usize;
+    /// Encode into exactly `WORDS` words
+    fn encode_words(&self, out: &mut [u16]);
+    /// Decode from exactly `WORDS` words
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError>;
+    /// Checksum over the encoded words; CRC-16/CCITT-FALSE unless overridden
+    fn checksum(words: &[u16]) -> u16 {
+        crc16_words(words)
+    }
+}
+
+/// Encode `value` followed by its checksum word into the start of `out`, returning the number of
+/// words written (`T::WORDS + 1`).
+pub fn encode_with_checksum<T: WireChecksum>(value: &T, out: &mut [u16]) -> Result<usize, SerdesError> {
+    let actual = out.len();
+    let (body, trailer) = match out.get_mut(..T::WORDS + 1) {
+        Some(framed) => framed.split_at_mut(T::WORDS),
+        None => return Err(SerdesError::BufferTooShort { expected: T::WORDS + 1, actual }),
+    };
+    value.encode_words(body);
+    trailer[0] = T::checksum(body);
+    Ok(T::WORDS + 1)
+}
+
+/// Decode a `T` from the `T::WORDS + 1` words written by encode_with_checksum, checking the
+/// checksum word before looking at the contents.
+pub fn decode_with_checksum<T: WireChecksum>(words: &[u16]) -> Result<T, SerdesError> {
+    let (body, trailer) = need(words, T::WORDS + 1)?.split_at(T::WORDS);
+    let expected = T::checksum(body);
+    if expected != trailer[0] {
+        return Err(SerdesError::ChecksumMismatch { expected, actual: trailer[0] });
+    }
+    T::decode_words(body)
+}
+
+impl WireChecksum for FlashRange {
+    const WORDS: usize = ComState::FLASH_ERASE.w_words as usize;
+    fn encode_words(&self, out: &mut [u16]) {
+        out.copy_from_slice(&self.encode_u16());
+    }
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        Ok(FlashRange::decode_u16(&[words[0], words[1], words[2], words[3]]))
+    }
+}
+
+impl WireChecksum for CredMap {
+    const WORDS: usize = ComState::WLAN_CRED_MAP_SET.w_words as usize;
+    fn encode_words(&self, out: &mut [u16]) {
+        out.copy_from_slice(&self.encode_u16());
+    }
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        let mut data = [0u16; ComState::WLAN_CRED_MAP_GET.r_words as usize];
+        data.copy_from_slice(words);
+        Ok(CredMap::decode_u16(&data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc16_check_value() {
+        // standard check value for CRC-16/CCITT-FALSE
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+    }
+
+    #[test]
+    fn crc16_words_matches_bytes() {
+        let words = [u16::from_le_bytes([b'1', b'2']), u16::from_le_bytes([b'3', b'4'])];
+        assert_eq!(crc16_words(&words), crc16(b"1234"));
+    }
+
+    #[test]
+    fn frame_crc_detects_corruption() {
+        let payload = [0x1234, 0x5678, 0x9abc, 0xdef0];
+        let trailer = frame_crc(ComState::FLASH_ERASE.verb, &payload);
+        assert!(frame_crc_ok(ComState::FLASH_ERASE.verb, &payload, trailer));
+        let corrupt = [0x1234, 0x5679, 0x9abc, 0xdef0];
+        assert!(!frame_crc_ok(ComState::FLASH_ERASE.verb, &corrupt, trailer));
+        assert!(!frame_crc_ok(ComState::FLASH_PP.verb, &payload, trailer));
+    }
+
+    #[test]
+    fn link_verbs_are_not_framed() {
+        assert_eq!(framed_w_words(&ComState::LINK_SET_CRC_MODE, CrcMode::Crc16), 1);
+        assert_eq!(framed_w_words(&ComState::FLASH_ERASE, CrcMode::Crc16), 5);
+        assert_eq!(framed_r_words(&ComState::FLASH_ERASE, CrcMode::Crc16), 0);
+        assert_eq!(framed_r_words(&ComState::UPTIME, CrcMode::Off), 4);
+    }
+
+    #[test]
+    fn checksummed_payloads() {
+        let range = FlashRange { addr: 0x0008_0000, len: 0x1000 };
+        let mut words = [0u16; 6];
+        assert_eq!(encode_with_checksum(&range, &mut words), Ok(5));
+        assert_eq!(words[5], 0);
+        assert_eq!(decode_with_checksum::<FlashRange>(&words), Ok(range));
+
+        words[1] ^= 1;
+        assert!(matches!(decode_with_checksum::<FlashRange>(&words), Err(SerdesError::ChecksumMismatch { .. })));
+        assert_eq!(
+            decode_with_checksum::<FlashRange>(&words[..4]),
+            Err(SerdesError::BufferTooShort { expected: 5, actual: 4 })
+        );
+        assert_eq!(
+            encode_with_checksum(&range, &mut words[..4]),
+            Err(SerdesError::BufferTooShort { expected: 5, actual: 4 })
+        );
+
+        let mut map = CredMap::new();
+        map.insert("home", 2, 1);
+        let mut words = [0u16; 25];
+        encode_with_checksum(&map, &mut words).unwrap();
+        assert_eq!(decode_with_checksum::<CredMap>(&words), Ok(map));
+    }
+}
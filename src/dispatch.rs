@@ -0,0 +1,176 @@
+#![forbid(unsafe_code)]
+
+// EC-side verb dispatch.
+//
+// The EC feeds every word it receives into a `Dispatcher`. The dispatcher looks each verb up in
+// a verb table, buffers exactly `w_words` of payload, and only then calls the handler, with a
+// `ResponseWriter`. When the handler returns, the dispatcher checks that it wrote exactly
+// `r_words`: a handler that writes too few or too many words is reported as an error at the
+// point of the bug, instead of surfacing later on the SoC as a FIFO desync.
+//
+// Verbs that encode an argument in their low bits (the BL_START..BL_END backlight range, the
+// length-encoded NET_FRAME verbs) are not in the table; the EC recognizes those before handing
+// the word to the dispatcher.
+
+use crate::{ComSpec, ComState};
+
+/// Errors from Dispatcher::push_word
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DispatchError {
+    /// the verb is not in the table, or is a response code rather than a verb
+    UnknownVerb(u16),
+    /// the verb's payload or response does not fit the dispatcher's buffers
+    TooLarge(u16),
+    /// the handler did not write exactly r_words
+    ResponseLength { verb: u16, expected: u16, actual: usize },
+}
+
+/// Collects a handler's response words
+pub struct ResponseWriter<'a> {
+    buf: &'a mut [u16],
+    len: usize,
+    overrun: bool,
+}
+impl<'a> ResponseWriter<'a> {
+    /// Append one word. Words beyond r_words are counted but dropped.
+    pub fn push(&mut self, word: u16) {
+        match self.buf.get_mut(self.len) {
+            Some(dest) => *dest = word,
+            None => self.overrun = true,
+        }
+        self.len += 1;
+    }
+    pub fn extend_from_slice(&mut self, words: &[u16]) {
+        for &word in words {
+            self.push(word);
+        }
+    }
+    /// Number of words written so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// An EC-side verb handler
+pub trait Handler {
+    /// Execute `spec` with its complete payload, writing exactly `spec.r_words` response words.
+    fn handle(&mut self, spec: &ComSpec, payload: &[u16], response: &mut ResponseWriter<'_>);
+}
+
+/// Word-at-a-time dispatcher over a verb table.
+/// MAX_W and MAX_R bound the largest payload and response of any verb it will accept.
+pub struct Dispatcher<'t, const MAX_W: usize, const MAX_R: usize> {
+    table: &'t [ComSpec],
+    pending: Option<ComSpec>,
+    payload: [u16; MAX_W],
+    received: usize,
+    response: [u16; MAX_R],
+}
+impl<'t, const MAX_W: usize, const MAX_R: usize> Dispatcher<'t, MAX_W, MAX_R> {
+    pub fn new(table: &'t [ComSpec]) -> Self {
+        Dispatcher { table, pending: None, payload: [0; MAX_W], received: 0, response: [0; MAX_R] }
+    }
+
+    /// Drop any partially received verb, as on LINK_SYNC
+    pub fn reset(&mut self) {
+        self.pending = None;
+        self.received = 0;
+    }
+
+    /// True if a verb is waiting for more payload words
+    pub fn is_receiving(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Feed one received word. Returns the response words to queue for the SoC once a verb has
+    /// been executed; `Ok(None)` while payload is still arriving, and for LINK_READ and LINK_SYNC.
+    pub fn push_word<H: Handler>(&mut self, word: u16, handler: &mut H) -> Result<Option<&[u16]>, DispatchError> {
+        let spec = match self.pending {
+            Some(spec) => {
+                self.payload[self.received] = word;
+                self.received += 1;
+                spec
+            }
+            None => {
+                if word == ComState::LINK_READ.verb {
+                    return Ok(None);
+                }
+                if word == ComState::LINK_SYNC.verb {
+                    self.reset();
+                    return Ok(None);
+                }
+                let spec = *self
+                    .table
+                    .iter()
+                    .find(|spec| spec.verb == word && !spec.response)
+                    .ok_or(DispatchError::UnknownVerb(word))?;
+                if spec.w_words as usize > MAX_W || spec.r_words as usize > MAX_R {
+                    return Err(DispatchError::TooLarge(word));
+                }
+                self.pending = Some(spec);
+                self.received = 0;
+                spec
+            }
+        };
+        if self.received < spec.w_words as usize {
+            return Ok(None);
+        }
+        self.pending = None;
+        let mut writer = ResponseWriter { buf: &mut self.response[..spec.r_words as usize], len: 0, overrun: false };
+        handler.handle(&spec, &self.payload[..spec.w_words as usize], &mut writer);
+        if writer.overrun || writer.len != spec.r_words as usize {
+            return Err(DispatchError::ResponseLength { verb: spec.verb, expected: spec.r_words, actual: writer.len });
+        }
+        Ok(Some(&self.response[..spec.r_words as usize]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Ec {
+        pings: u16,
+    }
+    impl Handler for Ec {
+        fn handle(&mut self, spec: &ComSpec, payload: &[u16], response: &mut ResponseWriter<'_>) {
+            match *spec {
+                ComState::LINK_PING => {
+                    self.pings += 1;
+                    response.push(!payload[0]);
+                    response.push(self.pings);
+                }
+                // bug: UPTIME is 4 words
+                ComState::UPTIME => response.extend_from_slice(&[1, 2, 3]),
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn payload_is_buffered_before_dispatch() {
+        let mut dispatcher = Dispatcher::<4, 4>::new(ComState::ALL);
+        let mut ec = Ec { pings: 0 };
+        assert_eq!(dispatcher.push_word(ComState::LINK_PING.verb, &mut ec), Ok(None));
+        assert!(dispatcher.is_receiving());
+        assert_eq!(dispatcher.push_word(0x00FF, &mut ec), Ok(Some(&[0xFF00, 1][..])));
+        assert_eq!(dispatcher.push_word(ComState::LINK_READ.verb, &mut ec), Ok(None));
+        assert_eq!(dispatcher.push_word(ComState::WLAN_JOIN.verb, &mut ec), Ok(Some(&[][..])));
+    }
+
+    #[test]
+    fn handler_length_bugs_are_caught() {
+        let mut dispatcher = Dispatcher::<4, 4>::new(ComState::ALL);
+        let mut ec = Ec { pings: 0 };
+        assert_eq!(
+            dispatcher.push_word(ComState::UPTIME.verb, &mut ec),
+            Err(DispatchError::ResponseLength { verb: ComState::UPTIME.verb, expected: 4, actual: 3 })
+        );
+        assert_eq!(dispatcher.push_word(0x1234, &mut ec), Err(DispatchError::UnknownVerb(0x1234)));
+        assert_eq!(dispatcher.push_word(ComState::FLASH_ACK.verb, &mut ec), Err(DispatchError::UnknownVerb(0x3CC3)));
+        assert_eq!(dispatcher.push_word(ComState::FLASH_PP.verb, &mut ec), Err(DispatchError::TooLarge(0x3300)));
+    }
+}
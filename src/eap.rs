@@ -0,0 +1,95 @@
+#![forbid(unsafe_code)]
+
+// WPA2-Enterprise credential upload.
+//
+// The identity, password and CA cert of an enterprise network are too large for one payload, so
+// they cross the bus in pieces: WLAN_EAP_CONFIG announces the length of each item, each item
+// follows as numbered WLAN_EAP_FRAGMENTs, and WLAN_EAP_COMMIT has the EC check that everything
+// arrived before it replaces the credentials of the slot. `EapCredentials` is the host's side of
+// this: it yields the config and the fragments in the order the EC expects them.
+
+use crate::serdes::{
+    EapConfig, EapFragment, EapItem, EapMethod, Flags16, SerdesError, EAP_FRAGMENT_LEN, EAP_ITEM_MAX_LEN,
+};
+
+/// The credentials of one enterprise network, ready to upload
+#[derive(Copy, Clone)]
+pub struct EapCredentials<'a> {
+    pub slot: u8,
+    pub method: EapMethod,
+    pub flags: Flags16,
+    pub identity: &'a str,
+    pub password: &'a str,
+    /// DER, may be empty if EAP_FLAG_VERIFY_SERVER is not set
+    pub ca_cert: &'a [u8],
+}
+impl<'a> EapCredentials<'a> {
+    fn item(&self, item: EapItem) -> &'a [u8] {
+        match item {
+            EapItem::Identity => self.identity.as_bytes(),
+            EapItem::Password => self.password.as_bytes(),
+            EapItem::CaCert => self.ca_cert,
+        }
+    }
+    /// The WLAN_EAP_CONFIG payload, or BytesLenTooBig if an item is longer than EAP_ITEM_MAX_LEN
+    pub fn config(&self) -> Result<EapConfig, SerdesError> {
+        if EapItem::ALL.iter().any(|item| self.item(*item).len() > EAP_ITEM_MAX_LEN) {
+            return Err(SerdesError::BytesLenTooBig);
+        }
+        Ok(EapConfig {
+            slot: self.slot,
+            method: self.method,
+            flags: self.flags,
+            identity_len: self.identity.len() as u16,
+            password_len: self.password.len() as u16,
+            ca_cert_len: self.ca_cert.len() as u16,
+        })
+    }
+    /// The WLAN_EAP_FRAGMENT payloads, item by item in EapItem order
+    pub fn fragments(&self) -> impl Iterator<Item = EapFragment> + 'a {
+        let creds = *self;
+        IntoIterator::into_iter(EapItem::ALL).flat_map(move |item| {
+            creds
+                .item(item)
+                .chunks(EAP_FRAGMENT_LEN)
+                .enumerate()
+                .map(move |(seq, chunk)| EapFragment::new(item, seq as u16, chunk))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serdes::EAP_FLAG_VERIFY_SERVER;
+
+    #[test]
+    fn fragments_follow_the_config() {
+        let cert = [0x30u8; 300];
+        let creds = EapCredentials {
+            slot: 1,
+            method: EapMethod::Peap,
+            flags: Flags16::empty().with(EAP_FLAG_VERIFY_SERVER),
+            identity: "alice@example.edu",
+            password: "hunter2",
+            ca_cert: &cert,
+        };
+        let config = creds.config().unwrap();
+        assert_eq!(config.fragments_of(EapItem::CaCert), 3);
+
+        let mut sent = [0u16; 3];
+        let mut cert_len = 0;
+        for fragment in creds.fragments() {
+            assert_eq!(fragment.seq, sent[fragment.item as usize]);
+            sent[fragment.item as usize] += 1;
+            if fragment.item == EapItem::CaCert {
+                cert_len += fragment.bytes().len();
+            }
+        }
+        assert_eq!(sent, [1, 1, 3]);
+        assert_eq!(cert_len, 300);
+
+        let big = [0u8; EAP_ITEM_MAX_LEN + 1];
+        assert_eq!(EapCredentials { ca_cert: &big, ..creds }.config(), Err(SerdesError::BytesLenTooBig));
+    }
+}
@@ -0,0 +1,417 @@
+#![forbid(unsafe_code)]
+
+// Network frame extensions.
+//
+// The length-encoded NET_FRAME verbs carry at most NET_FRAME_MAX_LEN bytes. Larger logical
+// payloads (jumbo frames, bulk transfers) are split into fragments when NET_FRAME_FLAG_FRAGMENTS
+// has been set with NET_FRAME_CONFIG. With the flag set, every frame in both directions starts
+// with a fragment header word:
+// - bit 15: more fragments follow
+// - bits 0-14: fragment index, counting from 0 within the logical payload
+// followed by up to FRAG_MAX_DATA bytes of the payload. The header is packed MSB first like the
+// rest of the frame, and counts towards the length encoded in the verb. A payload that fits in
+// one frame is sent as fragment 0 with the more bit clear.
+//
+// With NET_FRAME_FLAG_RX_META set, each fetched frame starts with RX_META_WORDS words of
+// serdes::RxMeta (RSSI, channel, EC receive timestamp), right after the confirmation word and
+// before the fragment header, if any. The metadata counts towards the length in the verb and in
+// GET_INTERRUPT's rx length. Sent frames never carry metadata.
+//
+// With NET_FRAME_FLAG_TX_PRIORITY set, each sent frame starts with a serdes::Priority word,
+// before the fragment header, if any, which picks the EC TX queue the frame goes to. The word
+// counts towards the length in the verb. Fetched frames never carry a priority.
+//
+// With PROTO_FEATURE_NET_CREDITS, NET_FRAME_SEND is flow controlled by credits. Frames are
+// numbered by their handle, and NET_TX_CREDITS (or the argument of INT_NET_TX_CREDITS) reports
+// the handle of the first frame the EC has no buffer for. Both counters wrap at 16 bits, so a
+// limit read while frames are in flight is never wrong, only stale. `CreditTracker` keeps the
+// host's side of the accounting.
+//
+// `FrameWriter` packs a frame kept in several buffers straight into the NET_FRAME_SEND word
+// stream, so network stacks need not gather headers and payload into one buffer first.
+
+use crate::serdes::{need, pack_frame_words, Flags16, Priority, RxMeta, SerdesError, RX_META_WORDS};
+use crate::{ComError, ComSpec, ComState, NET_FRAME_MAX_LEN};
+
+pub const NET_FRAME_FLAG_FRAGMENTS: Flags16 = Flags16::bit(0); // every frame starts with a FragHeader
+pub const NET_FRAME_FLAG_RX_META: Flags16 = Flags16::bit(1); // fetched frames start with an RxMeta
+pub const NET_FRAME_FLAG_TX_PRIORITY: Flags16 = Flags16::bit(2); // sent frames start with a Priority word
+const _: () =
+    assert!(Flags16::disjoint(&[NET_FRAME_FLAG_FRAGMENTS, NET_FRAME_FLAG_RX_META, NET_FRAME_FLAG_TX_PRIORITY]));
+/// Every NET_FRAME_CONFIG flag, the configuration with the longest frame prefix
+pub const NET_FRAME_FLAGS_ALL: Flags16 =
+    NET_FRAME_FLAG_FRAGMENTS.with(NET_FRAME_FLAG_RX_META).with(NET_FRAME_FLAG_TX_PRIORITY);
+
+/// Bytes that the NET_FRAME_CONFIG `flags` put in front of the frame data, in whichever direction
+/// puts more: the RxMeta of fetched frames or the Priority word of sent ones, then the fragment
+/// header. They count towards the length encoded in the verb.
+pub const fn frame_prefix_len(flags: Flags16) -> u16 {
+    let rx = if flags.contains(NET_FRAME_FLAG_RX_META) { 2 * RX_META_WORDS as u16 } else { 0 };
+    let tx = if flags.contains(NET_FRAME_FLAG_TX_PRIORITY) { 2 } else { 0 };
+    let frag = if flags.contains(NET_FRAME_FLAG_FRAGMENTS) { FRAG_HEADER_BYTES as u16 } else { 0 };
+    if rx > tx {
+        rx + frag
+    } else {
+        tx + frag
+    }
+}
+
+/// Bytes taken by the fragment header at the start of each frame
+pub const FRAG_HEADER_BYTES: usize = 2;
+/// Largest number of payload bytes in one fragment
+pub const FRAG_MAX_DATA: usize = NET_FRAME_MAX_LEN as usize - FRAG_HEADER_BYTES;
+/// Largest fragment index
+pub const FRAG_MAX_INDEX: u16 = 0x7FFF;
+
+const FRAG_MORE: u16 = 0x8000;
+
+/// The header word at the start of a fragment
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FragHeader {
+    /// more fragments of the same payload follow this one
+    pub more: bool,
+    /// position of this fragment in the payload, from 0
+    pub index: u16,
+}
+impl FragHeader {
+    pub fn encode_u16(&self) -> u16 {
+        (if self.more { FRAG_MORE } else { 0 }) | (self.index & FRAG_MAX_INDEX)
+    }
+    pub fn decode_u16(word: u16) -> Self {
+        FragHeader { more: word & FRAG_MORE != 0, index: word & FRAG_MAX_INDEX }
+    }
+}
+
+/// Errors from fragmenting or reassembling a payload
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FragError {
+    /// the frame is too short to hold a fragment header
+    Truncated,
+    /// a fragment arrived out of sequence; the partial payload was dropped
+    OutOfOrder { expected: u16, actual: u16 },
+    /// the payload does not fit the reassembly buffer, or needs more than FRAG_MAX_INDEX fragments
+    TooLarge,
+}
+
+/// Splits a payload into fragments of at most FRAG_MAX_DATA bytes.
+/// Each item is the header and data of one frame; an empty payload yields one empty fragment.
+pub struct Fragments<'a> {
+    payload: &'a [u8],
+    index: u16,
+    done: bool,
+}
+impl<'a> Fragments<'a> {
+    pub fn new(payload: &'a [u8]) -> Result<Self, FragError> {
+        if payload.len() > (FRAG_MAX_INDEX as usize + 1) * FRAG_MAX_DATA {
+            return Err(FragError::TooLarge);
+        }
+        Ok(Fragments { payload, index: 0, done: false })
+    }
+}
+impl<'a> Iterator for Fragments<'a> {
+    type Item = (FragHeader, &'a [u8]);
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let (data, rest) = self.payload.split_at(self.payload.len().min(FRAG_MAX_DATA));
+        let header = FragHeader { more: !rest.is_empty(), index: self.index };
+        self.payload = rest;
+        self.index = self.index.wrapping_add(1);
+        self.done = rest.is_empty();
+        Some((header, data))
+    }
+}
+
+/// Reassembles fragmented payloads of up to N bytes from the fetched frames
+pub struct Reassembler<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    next_index: u16,
+}
+impl<const N: usize> Reassembler<N> {
+    pub const fn new() -> Self {
+        Reassembler { buf: [0; N], len: 0, next_index: 0 }
+    }
+
+    /// Drop any partial payload
+    pub fn reset(&mut self) {
+        self.len = 0;
+        self.next_index = 0;
+    }
+
+    /// True if part of a payload has been received
+    pub fn is_partial(&self) -> bool {
+        self.next_index != 0
+    }
+
+    /// Feed one frame, header included. Returns the payload once its last fragment has arrived.
+    /// Fragment 0 always starts a new payload, so a sender that gave up on a payload midway
+    /// costs only the partial payload.
+    pub fn push_frame(&mut self, frame: &[u8]) -> Result<Option<&[u8]>, FragError> {
+        if frame.len() < FRAG_HEADER_BYTES {
+            self.reset();
+            return Err(FragError::Truncated);
+        }
+        let header = FragHeader::decode_u16(u16::from_be_bytes([frame[0], frame[1]]));
+        let data = &frame[FRAG_HEADER_BYTES..];
+        if header.index == 0 {
+            self.reset();
+        } else if header.index != self.next_index {
+            let expected = self.next_index;
+            self.reset();
+            return Err(FragError::OutOfOrder { expected, actual: header.index });
+        }
+        let dest = match self.buf.get_mut(self.len..self.len + data.len()) {
+            Some(dest) => dest,
+            None => {
+                self.reset();
+                return Err(FragError::TooLarge);
+            }
+        };
+        dest.copy_from_slice(data);
+        self.len += data.len();
+        if header.more {
+            self.next_index = header.index + 1;
+            return Ok(None);
+        }
+        let len = self.len;
+        self.reset();
+        Ok(Some(&self.buf[..len]))
+    }
+}
+impl<const N: usize> Default for Reassembler<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Split the metadata off a fetched frame (the words after the confirmation word), when
+/// NET_FRAME_FLAG_RX_META is set. None if the frame is too short to hold the metadata.
+pub fn split_rx_meta(words: &[u16]) -> Option<(RxMeta, &[u16])> {
+    if words.len() < RX_META_WORDS {
+        return None;
+    }
+    let (meta, frame) = words.split_at(RX_META_WORDS);
+    Some((RxMeta::decode_u16(&[meta[0], meta[1], meta[2], meta[3]]), frame))
+}
+
+/// Split the priority word off a sent frame, when NET_FRAME_FLAG_TX_PRIORITY is set
+pub fn split_tx_priority(words: &[u16]) -> Result<(Priority, &[u16]), SerdesError> {
+    need(words, 1)?;
+    Ok((Priority::decode_u16(words[0])?, &words[1..]))
+}
+
+/// Host-side credit accounting for NET_FRAME_SEND.
+/// Starts with no credits: read NET_TX_CREDITS after EC reset and pass the limit to `update`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct CreditTracker {
+    next_handle: u16,
+    limit: u16,
+}
+impl CreditTracker {
+    pub const fn new() -> Self {
+        CreditTracker { next_handle: 0, limit: 0 }
+    }
+    /// Forget all state, as after EC reset
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+    /// Record a limit reported by the EC. Limits older than the current one are ignored, so
+    /// reports that cross in flight do no harm.
+    pub fn update(&mut self, limit: u16) {
+        if (limit.wrapping_sub(self.limit) as i16) > 0 {
+            self.limit = limit;
+        }
+    }
+    /// Frames that may be sent before the EC runs out of buffers
+    pub fn available(&self) -> u16 {
+        let credits = self.limit.wrapping_sub(self.next_handle) as i16;
+        credits.max(0) as u16
+    }
+    /// Take a credit for one NET_FRAME_SEND. Returns the frame's handle, or None if the EC has
+    /// no buffer for it; wait for INT_NET_TX_CREDITS.
+    pub fn acquire(&mut self) -> Option<u16> {
+        if self.available() == 0 {
+            return None;
+        }
+        let handle = self.next_handle;
+        self.next_handle = handle.wrapping_add(1);
+        Some(handle)
+    }
+    /// Handle of the next frame to be sent
+    pub fn next_handle(&self) -> u16 {
+        self.next_handle
+    }
+}
+
+/// Packs a frame held in several byte slices (e.g. a header and a payload in separate buffers)
+/// into the MSB-ordered NET_FRAME_SEND word stream, without first copying it into one buffer.
+/// Bytes pair up across slice boundaries; an odd final byte is padded with 0.
+pub struct FrameWriter<'a> {
+    bytes: core::iter::Flatten<core::iter::Copied<core::slice::Iter<'a, &'a [u8]>>>,
+    remaining: usize,
+}
+impl<'a> FrameWriter<'a> {
+    pub fn new(parts: &'a [&'a [u8]]) -> Self {
+        FrameWriter { bytes: parts.iter().copied().flatten(), remaining: parts.iter().map(|p| p.len()).sum() }
+    }
+    /// Bytes not yet packed into words
+    pub fn len_bytes(&self) -> usize {
+        self.remaining
+    }
+    /// The NET_FRAME_SEND verb for the bytes not yet packed. Call before taking any words.
+    pub fn spec(&self) -> Result<ComSpec, ComError> {
+        if self.remaining > NET_FRAME_MAX_LEN as usize {
+            return Err(ComError::FrameTooLong(self.remaining.min(u16::MAX as usize) as u16));
+        }
+        ComState::net_frame_send(self.remaining as u16)
+    }
+}
+impl<'a> Iterator for FrameWriter<'a> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        let pair = [*self.bytes.next()?, self.bytes.next().copied().unwrap_or(0)];
+        let len = self.remaining.min(2);
+        self.remaining -= len;
+        let mut word = 0;
+        pack_frame_words(&pair[..len], core::slice::from_mut(&mut word)).ok()?;
+        Some(word)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let words = self.remaining.div_ceil(2);
+        (words, Some(words))
+    }
+}
+impl<'a> ExactSizeIterator for FrameWriter<'a> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The frame for one fragment: the header, MSB first, then the data
+    fn frame<'a>(header: FragHeader, data: &[u8], out: &'a mut [u8; NET_FRAME_MAX_LEN as usize]) -> &'a [u8] {
+        out[..2].copy_from_slice(&header.encode_u16().to_be_bytes());
+        out[2..2 + data.len()].copy_from_slice(data);
+        &out[..2 + data.len()]
+    }
+
+    #[test]
+    fn jumbo_payload_round_trip() {
+        let mut payload = [0u8; 5000];
+        for (i, b) in payload.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        let mut reassembler = Reassembler::<9000>::new();
+        let mut buf = [0u8; NET_FRAME_MAX_LEN as usize];
+        let mut fragments = 0;
+        let mut result = None;
+        for (header, data) in Fragments::new(&payload).unwrap() {
+            assert!(data.len() + FRAG_HEADER_BYTES <= NET_FRAME_MAX_LEN as usize);
+            fragments += 1;
+            if let Some(done) = reassembler.push_frame(frame(header, data, &mut buf)).unwrap() {
+                result = Some(done.len());
+                assert_eq!(done, &payload[..]);
+            }
+        }
+        assert_eq!(fragments, 3);
+        assert_eq!(result, Some(5000));
+        assert!(!reassembler.is_partial());
+    }
+
+    #[test]
+    fn lost_fragments_are_reported() {
+        let payload = [0xA5u8; 3 * FRAG_MAX_DATA];
+        let frames: [(FragHeader, &[u8]); 3] = {
+            let mut it = Fragments::new(&payload).unwrap();
+            [it.next().unwrap(), it.next().unwrap(), it.next().unwrap()]
+        };
+        let mut buf = [0u8; NET_FRAME_MAX_LEN as usize];
+        let mut reassembler = Reassembler::<{ 3 * FRAG_MAX_DATA }>::new();
+        assert_eq!(reassembler.push_frame(frame(frames[0].0, frames[0].1, &mut buf)), Ok(None));
+        assert_eq!(
+            reassembler.push_frame(frame(frames[2].0, frames[2].1, &mut buf)),
+            Err(FragError::OutOfOrder { expected: 1, actual: 2 })
+        );
+        assert!(!reassembler.is_partial());
+        assert_eq!(reassembler.push_frame(&[0x80]), Err(FragError::Truncated));
+
+        // a payload larger than the buffer
+        let mut small = Reassembler::<16>::new();
+        assert_eq!(small.push_frame(frame(frames[0].0, frames[0].1, &mut buf)), Err(FragError::TooLarge));
+    }
+
+    #[test]
+    fn short_payload_is_one_fragment() {
+        let mut it = Fragments::new(&[1, 2, 3]).unwrap();
+        assert_eq!(it.next(), Some((FragHeader { more: false, index: 0 }, &[1u8, 2, 3][..])));
+        assert_eq!(it.next(), None);
+        assert_eq!(FragHeader::decode_u16(0x8003), FragHeader { more: true, index: 3 });
+    }
+
+    #[test]
+    fn scatter_gather_matches_contiguous() {
+        let header = [0x45u8, 0x00, 0x01];
+        let payload = [0x11u8, 0x22, 0x33, 0x44];
+        let parts: [&[u8]; 3] = [&header, &[], &payload];
+        let writer = FrameWriter::new(&parts);
+        assert_eq!(writer.len(), 4);
+        assert_eq!(writer.spec(), ComState::net_frame_send(7));
+        let mut words = [0u16; 4];
+        for (dest, word) in words.iter_mut().zip(writer) {
+            *dest = word;
+        }
+        assert_eq!(words, [0x4500, 0x0111, 0x2233, 0x4400]);
+
+        // a fragment is its header word followed by the data
+        let frag = FragHeader { more: true, index: 1 }.encode_u16().to_be_bytes();
+        let parts: [&[u8]; 2] = [&frag, &payload[..1]];
+        let mut writer = FrameWriter::new(&parts);
+        assert_eq!((writer.next(), writer.next(), writer.next()), (Some(0x8001), Some(0x1100), None));
+
+        let big = [0u8; NET_FRAME_MAX_LEN as usize + 1];
+        let parts: [&[u8]; 1] = [&big];
+        assert_eq!(FrameWriter::new(&parts).spec(), Err(ComError::FrameTooLong(0x800)));
+    }
+
+    #[test]
+    fn rx_meta_prefix() {
+        let meta = RxMeta { channel: 6, timestamp_us: 1234, ..Default::default() };
+        let mut words = [0u16; RX_META_WORDS + 1];
+        words[..RX_META_WORDS].copy_from_slice(&meta.encode_u16());
+        words[RX_META_WORDS] = 0xAABB;
+        assert_eq!(split_rx_meta(&words), Some((meta, &[0xAABB][..])));
+        assert_eq!(split_rx_meta(&words[..2]), None);
+    }
+
+    #[test]
+    fn tx_priority_prefix() {
+        let prio = Priority::from_user_priority(6).encode_u16().to_be_bytes();
+        let payload = [0x11u8, 0x22];
+        let parts: [&[u8]; 2] = [&prio, &payload];
+        let mut words = [0u16; 2];
+        for (dest, word) in words.iter_mut().zip(FrameWriter::new(&parts)) {
+            *dest = word;
+        }
+        assert_eq!(split_tx_priority(&words), Ok((Priority::Voice, &[0x1122][..])));
+        assert_eq!(split_tx_priority(&[4]), Err(SerdesError::InvalidEnumValue { field: "Priority", value: 4 }));
+        assert!(split_tx_priority(&[]).is_err());
+    }
+
+    #[test]
+    fn credits_limit_sends() {
+        let mut credits = CreditTracker::new();
+        assert_eq!(credits.acquire(), None);
+        credits.update(2);
+        assert_eq!((credits.acquire(), credits.acquire(), credits.acquire()), (Some(0), Some(1), None));
+        // a stale report does not take back credits
+        credits.update(4);
+        credits.update(3);
+        assert_eq!(credits.available(), 2);
+
+        // handles and limits wrap together
+        let mut credits = CreditTracker { next_handle: 0xFFFF, limit: 0xFFFF };
+        credits.update(0x0001);
+        assert_eq!((credits.acquire(), credits.acquire(), credits.acquire()), (Some(0xFFFF), Some(0), None));
+    }
+}
@@ -1,10 +1,38 @@
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "transport")]
+pub mod client;
+pub mod crc;
+pub mod dispatch;
+pub mod eap;
+pub mod frame;
+pub mod link;
+pub mod payload;
+pub mod power;
+pub mod provision;
+pub mod scan;
 pub mod serdes;
+pub mod stream;
+#[cfg(feature = "transport")]
+pub mod transport;
+pub mod vendor;
+pub mod verb;
 
-/// COM link states. These constants encode the commands sent from the SoC to the EC.
+use serdes::{u32_to_words, words_to_u32, ProtocolVersion};
+use verb::VerbClass;
+
+/// Version of the COM wire ABI: verb values, word counts, and the layout of every serialized
+/// payload. Bump this whenever an incompatible change is made, and regenerate the golden
+/// images checked by `tests/wire_compat.rs` in the same commit.
+pub const COM_ABI_VERSION: u16 = 3;
 
-#[derive(Copy, Clone, Debug)]
+/// COM link states. These constants encode the commands sent from the SoC to the EC.
+/// Outside this crate, build specs with `ComSpec::new` and the `with_*` methods, so that
+/// metadata fields added later do not break callers.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct ComSpec {
     /// the "verb" specifying the command
     pub verb: u16,
@@ -19,24 +47,148 @@ pub struct ComSpec {
     /// This field is used exclusively by the SoC to determine which commands are safe
     /// to use with the current EC rev.
     pub apilevel: [u8; 4],
+    /// how much harm issuing the command by mistake can do; see DangerLevel
+    pub danger: DangerLevel,
+    /// retired verb that current EC firmware no longer implements. ComState::lookup refuses
+    /// these unless the `legacy-verbs` feature is enabled.
+    pub deprecated: bool,
+}
+
+impl ComSpec {
+    /// A plain Safe, non-deprecated verb; attach other metadata with the `with_*` methods, e.g.
+    /// `ComSpec::new(0x2400, 1, 0, [0, 9, 9, 1]).with_danger(DangerLevel::Disruptive)`
+    pub const fn new(verb: u16, w_words: u16, r_words: u16, apilevel: [u8; 4]) -> Self {
+        ComSpec { verb, w_words, r_words, response: false, apilevel, danger: DangerLevel::Safe, deprecated: false }
+    }
+    pub const fn with_danger(mut self, danger: DangerLevel) -> Self {
+        self.danger = danger;
+        self
+    }
+    /// Mark this verb retired; see `deprecated`
+    pub const fn deprecated(mut self) -> Self {
+        self.deprecated = true;
+        self
+    }
+    /// Subsystem the command belongs to, for grouping by generic tooling. This is the block of
+    /// the verb space the verb is allocated in (see the verb module), rather than a stored field:
+    /// verbs are allocated by subsystem, so a stored category could only repeat the verb's block
+    /// or contradict it, and the verbs built at run time with an argument in their low bits
+    /// (backlight levels, frame lengths) get the same category as their table entry for free.
+    /// The mapping is exhaustive: every u16 belongs to exactly one VerbClass, each upstream verb
+    /// is checked at build time to be in an assigned block, and vendor verbs are all
+    /// VerbClass::Vendor, leaving any finer grouping to the fork that defines them.
+    pub const fn category(&self) -> VerbClass {
+        verb::classify(self.verb)
+    }
+    /// Protocol revision that introduced this verb, derived from its apilevel
+    pub fn introduced_in(&self) -> ProtocolVersion {
+        PROTOCOL_REVISIONS
+            .iter()
+            .rev()
+            .find(|(apilevel, _)| *apilevel <= self.apilevel)
+            .map_or(ProtocolVersion::V1_0, |(_, version)| *version)
+    }
+    /// Protocol revision that removed this verb, if any
+    pub fn removed_in(&self) -> Option<ProtocolVersion> {
+        REMOVED_VERBS.iter().find(|(verb, _)| *verb == self.verb).map(|(_, version)| *version)
+    }
+    /// True if a peer reporting `version` implements this verb. Verbs that need an optional
+    /// feature must also be checked against `version.features`.
+    pub fn supported_by(&self, version: &ProtocolVersion) -> bool {
+        version.at_least(&self.introduced_in()) && !self.removed_in().is_some_and(|removed| version.at_least(&removed))
+    }
+}
+
+/// First apilevel of each protocol revision, oldest first. Verbs added after a revision has
+/// shipped take the apilevel of a new revision, listed here with a new ProtocolVersion constant.
+const PROTOCOL_REVISIONS: [([u8; 4], ProtocolVersion); 5] = [
+    ([0, 0, 0, 0], ProtocolVersion::V1_0),
+    ([0, 9, 9, 0], ProtocolVersion::V1_1),
+    ([0, 9, 9, 1], ProtocolVersion::V1_2),
+    ([0, 9, 9, 2], ProtocolVersion::V1_3),
+    ([0, 9, 9, 3], ProtocolVersion::V1_4),
+];
+/// Verbs retired from the protocol, with the revision that dropped them. None so far; a retired
+/// verb stays in ComState::ALL so older peers can still be served.
+const REMOVED_VERBS: [(u16, ProtocolVersion); 0] = [];
+
+/// Consequences of issuing a verb by mistake, so host-side shells and services can ask for
+/// confirmation or elevated permission first.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum DangerLevel {
+    /// no lasting effect
+    Safe,
+    /// interrupts service (power, radio, update state), but recovers without intervention
+    Disruptive,
+    /// can lose data, brick the EC, or need physical intervention to recover from
+    /// (erased flash, ship mode, boosted charger, wrong gas gauge calibration)
+    Destructive,
+}
+impl DangerLevel {
+    /// True if an interactive caller should confirm before issuing the verb
+    pub fn requires_confirmation(&self) -> bool {
+        *self >= DangerLevel::Destructive
+    }
 }
 
 #[non_exhaustive]
 pub struct ComState;
-#[rustfmt::skip]
-impl ComState {
+/// Declares the verbs. Each `pub const NAME: ComSpec = ...;` becomes a ComState constant, an
+/// entry of ComState::ALL (and so of lookup, name_of and the uniqueness check), and a marker type
+/// in `verbs`, so adding a verb is one line in one place.
+macro_rules! com_verb {
+    ($($(#[$attr:meta])* pub const $name:ident: ComSpec = $spec:expr;)*) => {
+        #[rustfmt::skip]
+        impl ComState {
+            $($(#[$attr])* pub const $name: ComSpec = $spec;)*
+        }
+        impl ComState {
+            /// Every verb declared with com_verb!, in declaration order. The build fails if two
+            /// verbs share a value.
+            #[allow(deprecated)]
+            pub const ALL: &'static [ComSpec] = &[$(ComState::$name,)*];
+            /// Name of each entry of ALL, at the same index
+            const NAMES: &'static [&'static str] = &[$(stringify!($name),)*];
+        }
+
+        /// Marker types for the verbs of ComState::ALL, named after their constants, for use
+        /// with the typed payload buffers in `payload`.
+        #[allow(non_camel_case_types, deprecated)]
+        pub mod verbs {
+            use crate::payload::Verb;
+            use crate::{ComSpec, ComState};
+            $(
+                pub struct $name;
+                impl Verb for $name {
+                    const SPEC: ComSpec = ComState::$name;
+                    type Request = [u16; ComState::$name.w_words as usize];
+                    type Response = [u16; ComState::$name.r_words as usize];
+                }
+            )*
+        }
+    };
+}
+com_verb! {
     // wifi-related
-    pub const SSID_CHECK: ComSpec            = ComSpec{verb: 0x2000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const SSID_FETCH: ComSpec            = ComSpec{verb: 0x2100, w_words: 0,     r_words: 16*6  ,response: false, apilevel: [0, 9, 5, 0]}; // legacy, not implemented in newer revs
-    pub const SSID_FETCH_STR: ComSpec        = ComSpec{verb: 0x2101, w_words: 0,     r_words: 34*8  ,response: false, apilevel: [0, 9, 5, 0]}; // legacy, not implemented in newer revs
-    pub const WFX_PDS_LINE_SET: ComSpec      = ComSpec{verb: 0x2200, w_words: 129,   r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]}; // 1 length + 128 buffer. length is in *bytes* not words. Sends one line of a PDS.
-    pub const WFX_RXSTAT_GET: ComSpec        = ComSpec{verb: 0x2201, w_words: 0,     r_words: 376/2 ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WFX_FW_REV_GET: ComSpec        = ComSpec{verb: 0x2202, w_words: 0,     r_words: 3     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WF200_RESET: ComSpec           = ComSpec{verb: 0x2203, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const SSID_SCAN_ON: ComSpec          = ComSpec{verb: 0x2204, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const SSID_SCAN_OFF: ComSpec         = ComSpec{verb: 0x2205, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const SSID_CHECK: ComSpec            = ComSpec{verb: 0x2000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    #[cfg_attr(not(feature = "legacy-verbs"), deprecated(note = "not implemented by current EC firmware; use SSID_SCAN_FETCH"))]
+    pub const SSID_FETCH: ComSpec            = ComSpec{verb: 0x2100, w_words: 0,     r_words: 16*6  ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: true}; // legacy, not implemented in newer revs
+    #[cfg_attr(not(feature = "legacy-verbs"), deprecated(note = "not implemented by current EC firmware; use SSID_SCAN_FETCH"))]
+    pub const SSID_FETCH_STR: ComSpec        = ComSpec{verb: 0x2101, w_words: 0,     r_words: 34*8  ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: true}; // legacy, not implemented in newer revs
+    // summary of the last completed scan, serdes::ScanSummary (4 words). Read after INT_WLAN_SSID_FINISHED.
+    pub const SSID_SCAN_RESULT_SUMMARY: ComSpec = ComSpec{verb: 0x2102, w_words: 0,  r_words: 4     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // scan records, replacing SSID_FETCH/SSID_FETCH_STR. Payload: 1 word, index of the first record wanted.
+    // Response: serdes::ScanResultPage, up to SCAN_PAGE_LEN serdes::ScanResult records from that index. Records
+    // can be fetched while the scan runs; see the scan module for the continuation convention.
+    pub const SSID_SCAN_FETCH: ComSpec       = ComSpec{verb: 0x2103, w_words: 1,     r_words: 4+4*22,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WFX_PDS_LINE_SET: ComSpec      = ComSpec{verb: 0x2200, w_words: 129,   r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false}; // 1 length + 128 buffer. length is in *bytes* not words. Sends one line of a PDS.
+    pub const WFX_RXSTAT_GET: ComSpec        = ComSpec{verb: 0x2201, w_words: 0,     r_words: 376/2 ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WFX_FW_REV_GET: ComSpec        = ComSpec{verb: 0x2202, w_words: 0,     r_words: 3     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WF200_RESET: ComSpec           = ComSpec{verb: 0x2203, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false};
+    pub const SSID_SCAN_ON: ComSpec          = ComSpec{verb: 0x2204, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const SSID_SCAN_OFF: ComSpec         = ComSpec{verb: 0x2205, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
     // config(2) - control - alloc_fail(2) - alloc_oversize(2) - alloc_count
-    pub const WF200_DEBUG: ComSpec           = ComSpec{verb: 0x2206, w_words: 0,     r_words: 8     ,response: false, apilevel: [0, 9, 6, 0]};
+    pub const WF200_DEBUG: ComSpec           = ComSpec{verb: 0x2206, w_words: 0,     r_words: 8     ,response: false, apilevel: [0, 9, 6, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // WLAN_*
     // - SSID & PASS fields are sized to match requirements of the WF200 fullMAC driver API.
@@ -45,72 +197,237 @@ impl ComState {
     // - PASS:   2 bytes length + 64 bytes data = 66 bytes --> 33 words
     // - STATUS: 2 bytes length + 64 bytes data = 66 bytes --> 33 words
     // - IPV4_CONF: serialized binary data according to serdes::Ipv4Conf -> 14 words
-    pub const WLAN_ON: ComSpec               = ComSpec{verb: 0x2300, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_OFF: ComSpec              = ComSpec{verb: 0x2301, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_SET_SSID: ComSpec         = ComSpec{verb: 0x2302, w_words: 17,    r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_SET_PASS: ComSpec         = ComSpec{verb: 0x2303, w_words: 33,    r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_JOIN: ComSpec             = ComSpec{verb: 0x2304, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_LEAVE: ComSpec            = ComSpec{verb: 0x2305, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_STATUS: ComSpec           = ComSpec{verb: 0x2306, w_words: 0,     r_words: 33    ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_GET_IPV4_CONF: ComSpec    = ComSpec{verb: 0x2307, w_words: 0,     r_words: 14    ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const WLAN_GET_ERRCOUNTS: ComSpec    = ComSpec{verb: 0x2308, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0]};
-    // binary status reports the following:
-    // rssi(1), interface_status(1), ipv4_state(14), ssid(17)
-    pub const WLAN_BIN_STATUS: ComSpec       = ComSpec{verb: 0x2309, w_words: 0,     r_words: 2+14+17 ,response: false, apilevel: [0, 9, 6, 0]};
-    pub const WLAN_GET_RSSI: ComSpec         = ComSpec{verb: 0x230A, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const WLAN_ON: ComSpec               = ComSpec{verb: 0x2300, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_OFF: ComSpec              = ComSpec{verb: 0x2301, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false};
+    pub const WLAN_SET_SSID: ComSpec         = ComSpec{verb: 0x2302, w_words: 17,    r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_SET_PASS: ComSpec         = ComSpec{verb: 0x2303, w_words: 33,    r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_JOIN: ComSpec             = ComSpec{verb: 0x2304, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_LEAVE: ComSpec            = ComSpec{verb: 0x2305, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_STATUS: ComSpec           = ComSpec{verb: 0x2306, w_words: 0,     r_words: 33    ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_IPV4_CONF: ComSpec    = ComSpec{verb: 0x2307, w_words: 0,     r_words: 14    ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    // WF200 driver error counters, as serdes::WlanErrCounts (4 words)
+    pub const WLAN_GET_ERRCOUNTS: ComSpec    = ComSpec{verb: 0x2308, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    // binary status reports the following, as the untagged serdes::WlanBinStatus:
+    // rcpi(1), interface_status(1), ipv4_state(14), ssid(17)
+    pub const WLAN_BIN_STATUS: ComSpec       = ComSpec{verb: 0x2309, w_words: 0,     r_words: 2+14+17 ,response: false, apilevel: [0, 9, 6, 0], danger: DangerLevel::Safe, deprecated: false};
+    // link signal strength in the legacy RCPI encoding, as serdes::Rcpi; prefer WLAN_GET_RSSI_AVG
+    pub const WLAN_GET_RSSI: ComSpec         = ComSpec{verb: 0x230A, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
     // use on resume to sync up the state with the COM. Returns linkstate then dhcpstate
-    pub const WLAN_SYNC_STATE: ComSpec       = ComSpec{verb: 0x230B, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const WLAN_SYNC_STATE: ComSpec       = ComSpec{verb: 0x230B, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    // averaged link RSSI, as serdes::Rssi
+    pub const WLAN_GET_RSSI_AVG: ComSpec     = ComSpec{verb: 0x230C, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+
+    // Wi-Fi direct (P2P), for device-to-device links without an AP. Only available on EC builds whose
+    // WF200 firmware supports P2P; otherwise WLAN_P2P_STATUS reports P2pState::Unsupported.
+    // - P2P_START: serdes::P2pConfig (2 words). Starts group owner negotiation; progress via WLAN_P2P_STATUS
+    //   and INT_WLAN_CONNECT_EVENT.
+    // - P2P_STATUS: serdes::P2pStatus (5 words)
+    pub const WLAN_P2P_START: ComSpec        = ComSpec{verb: 0x2310, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_P2P_STOP: ComSpec         = ComSpec{verb: 0x2311, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_P2P_STATUS: ComSpec       = ComSpec{verb: 0x2312, w_words: 0,     r_words: 5     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+
+    // Credential map: which of the EC's stored credential slots to use for which SSID, so the EC can pick the right
+    // passphrase when it decides which AP to join (e.g. after resume). The whole table is written and read at once.
+    // - CRED_MAP_SET / CRED_MAP_GET: serdes::CredMap, CRED_MAP_LEN entries of 3 words
+    pub const WLAN_CRED_MAP_SET: ComSpec     = ComSpec{verb: 0x2314, w_words: 8*3,   r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_CRED_MAP_GET: ComSpec     = ComSpec{verb: 0x2315, w_words: 0,     r_words: 8*3   ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+
+    // scan parameters used by SSID_SCAN_ON from then on: serdes::ScanConfig (4 words). Until it is set, the EC scans
+    // every channel actively with its default dwell time.
+    pub const WLAN_SCAN_CONFIG: ComSpec      = ComSpec{verb: 0x2316, w_words: 4,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // BSSID pin, for SSIDs served by several APs. While a pin is set, WLAN_JOIN only associates with that AP.
+    // - SET_BSSID: serdes::MacAddress (3 words). The pin stays until CLEAR_BSSID or WLAN_OFF.
+    pub const WLAN_SET_BSSID: ComSpec        = ComSpec{verb: 0x2317, w_words: 3,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_CLEAR_BSSID: ComSpec      = ComSpec{verb: 0x2318, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // Known networks: credentials stored on the EC in KNOWN_NET_SLOTS slots, the slots WLAN_CRED_MAP entries refer to.
+    // Networks flagged KNOWN_NET_FLAG_AUTOJOIN are joined by the EC on its own, e.g. after a resume; each attempt
+    // ends with INT_WLAN_AUTOJOIN.
+    // - KNOWN_NET_STORE: serdes::KnownNetwork (52 words), replacing whatever the slot held
+    // - KNOWN_NET_GET: 1 word slot -> serdes::KnownNetworkInfo (19 words). The passphrase is never read back.
+    // - KNOWN_NET_DELETE: 1 word slot, or KNOWN_NET_DELETE_ALL
+    pub const WLAN_KNOWN_NET_STORE: ComSpec  = ComSpec{verb: 0x2319, w_words: 2+17+33, r_words: 0   ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_KNOWN_NET_GET: ComSpec    = ComSpec{verb: 0x231A, w_words: 1,     r_words: 2+17  ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_KNOWN_NET_DELETE: ComSpec = ComSpec{verb: 0x231B, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // roaming between APs of the joined SSID: serdes::RoamConfig (3 words). While the link RSSI is below the trigger,
+    // the EC scans in the background and moves to an AP that is better by the hysteresis, raising
+    // INT_WLAN_CONNECT_EVENT as for a join. Off until configured, and while a WLAN_SET_BSSID pin is set.
+    pub const WLAN_ROAM_CONFIG: ComSpec      = ComSpec{verb: 0x231C, w_words: 3,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // WPA2-Enterprise credentials, uploaded in fragments since a CA cert does not fit in one payload.
+    // - EAP_CONFIG: serdes::EapConfig (6 words). Starts an upload for a known-network slot, dropping any upload in
+    //   progress, and gives the length of each item.
+    // - EAP_FRAGMENT: serdes::EapFragment (67 words), up to EAP_FRAGMENT_LEN bytes of one item. The fragments of an
+    //   item are numbered from 0 and sent in order.
+    // - EAP_COMMIT: -> serdes::EapCommitStatus (1 word). Checks the upload and, if it is complete, attaches the
+    //   credentials to the slot in place of its passphrase. A failed commit drops the upload.
+    pub const WLAN_EAP_CONFIG: ComSpec       = ComSpec{verb: 0x231D, w_words: 6,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_EAP_FRAGMENT: ComSpec     = ComSpec{verb: 0x231E, w_words: 2+65,  r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_EAP_COMMIT: ComSpec       = ComSpec{verb: 0x231F, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // WPS enrollment: the EC obtains the credentials from the AP and stores them in a known-network slot. The session
+    // runs for up to two minutes and ends with INT_WLAN_WPS.
+    // - WPS_PBC_START: 1 word slot. Push-button mode: the user presses the button on the AP.
+    // - WPS_PIN_START: serdes::WpsPinStart (3 words). The user enters the PIN on the AP.
+    pub const WLAN_WPS_PBC_START: ComSpec    = ComSpec{verb: 0x2320, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_WPS_PIN_START: ComSpec    = ComSpec{verb: 0x2321, w_words: 3,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // SoftAP: the EC runs an access point instead of joining one, e.g. for provisioning from a phone. While the AP
+    // runs, the station link is down and the NET_FRAME verbs carry the AP's traffic; frames between two stations
+    // are forwarded by the EC unless AP_FLAG_ISOLATE is set. INT_WLAN_AP_STATION is raised when a station
+    // associates or leaves.
+    // - AP_START: serdes::ApConfig (52 words)
+    // - AP_STATIONS: 1 word index of the first station wanted -> serdes::ApStationPage (2+4*4 words)
+    pub const WLAN_AP_START: ComSpec         = ComSpec{verb: 0x2322, w_words: 17+33+2, r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_AP_STOP: ComSpec          = ComSpec{verb: 0x2323, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_AP_STATIONS: ComSpec      = ComSpec{verb: 0x2324, w_words: 1,     r_words: 2+4*4 ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // regulatory domain, which sets the allowed channels and TX power: serdes::CountryCode (1 word). The EC starts in
+    // the domain of its PDS. Setting a domain that does not allow the channel in use drops the link or stops the AP.
+    pub const WLAN_SET_COUNTRY: ComSpec      = ComSpec{verb: 0x2325, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Disruptive, deprecated: false};
+    pub const WLAN_GET_COUNTRY: ComSpec      = ComSpec{verb: 0x2326, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // transmit power limit: serdes::TxPower (1 word, quarter dBm as in the WF200 API). The EC transmits at no more
+    // than the lower of this limit and what the regulatory domain allows; GET_TX_POWER returns that effective limit.
+    pub const WLAN_SET_TX_POWER: ComSpec     = ComSpec{verb: 0x2327, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_TX_POWER: ComSpec     = ComSpec{verb: 0x2328, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    // channels the current regulatory domain allows: serdes::ChannelList (1+14 words)
+    pub const WLAN_GET_CHANNELS: ComSpec     = ComSpec{verb: 0x2329, w_words: 0,     r_words: 1+14  ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // MAC address of the station and AP interfaces: serdes::MacConfig (4 words). A new address takes effect at the
+    // next WLAN_JOIN or WLAN_AP_START and is kept until WLAN_SET_MAC with MAC_FLAG_FACTORY or an EC reset.
+    pub const WLAN_SET_MAC: ComSpec          = ComSpec{verb: 0x232A, w_words: 4,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_MAC: ComSpec          = ComSpec{verb: 0x232B, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // multicast filter: while the table has entries, the EC forwards received multicast frames only for the listed
+    // group addresses, so mDNS or SSDP can be received without waking the SoC for all multicast traffic. Broadcast
+    // frames are always forwarded. The table is cleared by WLAN_OFF.
+    // - MCAST_ADD / MCAST_REMOVE: serdes::MacAddress (3 words) of a group. Adding to a full table does nothing.
+    // - MCAST_LIST: serdes::McastFilterTable (1+8*3 words)
+    pub const WLAN_MCAST_ADD: ComSpec        = ComSpec{verb: 0x232C, w_words: 3,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_MCAST_REMOVE: ComSpec     = ComSpec{verb: 0x232D, w_words: 3,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_MCAST_LIST: ComSpec       = ComSpec{verb: 0x232E, w_words: 0,     r_words: 1+8*3 ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // monitor mode, for on-device Wi-Fi debugging: serdes::MonitorConfig (2 words). The station link and any AP are
+    // taken down, and every 802.11 data frame received on the channel, from any network, is fetched with
+    // NET_FRAME_FETCH: the raw frame from its 802.11 header on, FCS stripped, after a serdes::RxMeta whatever
+    // NET_FRAME_FLAG_RX_META says. Destructive, so it needs confirmation, and audited, since it exposes other
+    // stations' traffic.
+    pub const WLAN_MONITOR_START: ComSpec    = ComSpec{verb: 0x232F, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Destructive, deprecated: false};
+    pub const WLAN_MONITOR_STOP: ComSpec     = ComSpec{verb: 0x2330, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Disruptive, deprecated: false};
+
+    // WF200 power save: serdes::PowerSaveConfig (3 words). Off keeps the radio awake for the lowest latency; the
+    // PS modes let it doze between beacons, at the cost of frames to the device waiting at the AP until it wakes.
+    // The setting applies to the station link, is kept across joins, and is reset to off by WLAN_OFF.
+    pub const WLAN_SET_POWER_SAVE: ComSpec   = ComSpec{verb: 0x2331, w_words: 3,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_POWER_SAVE: ComSpec   = ComSpec{verb: 0x2332, w_words: 0,     r_words: 3     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // 802.11 PHY: some old APs misbehave with 11n, so the standards the EC may use can be restricted.
+    // - SET_PHY_MODE: 1 word serdes::PhyMode. Takes effect at the next WLAN_JOIN; the EC starts in PhyMode::Bgn.
+    // - GET_PHY_STATUS: serdes::PhyStatus (4 words): the configured mode and the rates of the current link
+    pub const WLAN_SET_PHY_MODE: ComSpec     = ComSpec{verb: 0x2333, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_PHY_STATUS: ComSpec   = ComSpec{verb: 0x2334, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Safe, deprecated: false};
+
+    // connection diagnostics, to explain an INT_WLAN_DISCONNECT: serdes::ConnDiagnostics (5 words). The reason and
+    // flags describe the last disconnection and are kept until the next one; the counters cover the current or last
+    // link and restart at each join.
+    pub const WLAN_GET_DIAGNOSTICS: ComSpec  = ComSpec{verb: 0x2335, w_words: 0,     r_words: 5     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+
+    // RSSI history, for signal strength graphs without polling WLAN_GET_RSSI: the EC samples the link RSSI every
+    // interval and keeps the last samples in a ring. SET_RSSI_HISTORY takes serdes::RssiHistoryConfig (2 words) and
+    // clears the ring; an interval of 0 stops sampling. GET_RSSI_HISTORY returns serdes::RssiHistory (2+32 words).
+    pub const WLAN_SET_RSSI_HISTORY: ComSpec = ComSpec{verb: 0x2336, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_RSSI_HISTORY: ComSpec = ComSpec{verb: 0x2337, w_words: 0,     r_words: 2+32  ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+
+    // RSSI filtering: serdes::RssiFilterConfig (2 words) sets the moving average behind WLAN_GET_RSSI_AVG and the
+    // change in the average that raises INT_WLAN_RSSI_UPDATE, so the SoC need not poll for signal strength.
+    pub const WLAN_SET_RSSI_FILTER: ComSpec  = ComSpec{verb: 0x2338, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const WLAN_GET_RSSI_FILTER: ComSpec  = ComSpec{verb: 0x2339, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+
+    // outcome of the last join or roam, to read on INT_WLAN_CONNECT_EVENT: serdes::ConnectStatus (2 words), a
+    // ConnectResult and the ConnectDetail that explains a Reject or AuthFail
+    pub const WLAN_GET_CONNECT_RESULT: ComSpec = ComSpec{verb: 0x233A, w_words: 0,   r_words: 2     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+
+    // WLAN_GET_IPV4_CONF with a leading format word: serdes::Ipv4Conf::encode_versioned (1+14 words). The untagged
+    // WLAN_GET_IPV4_CONF keeps its 14 words for older hosts; this layout may grow, and hosts decode any version of it.
+    pub const WLAN_GET_IPV4_CONF_TAGGED: ComSpec = ComSpec{verb: 0x233B, w_words: 0, r_words: 1+14  ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+
+    // WLAN_BIN_STATUS with a leading format word and the security of the current network, as a versioned
+    // serdes::WlanBinStatus: format(1), rcpi(1), interface_status(1), ipv4_state(14), ssid(17), security(1)
+    pub const WLAN_BIN_STATUS_TAGGED: ComSpec = ComSpec{verb: 0x233C, w_words: 0,    r_words: 1+2+14+17+1 ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
 
     // flash commands
-    pub const FLASH_WAITACK: ComSpec         = ComSpec{verb: 0x3000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const FLASH_ACK: ComSpec             = ComSpec{verb: 0x3CC3, w_words: 0,     r_words: 0     ,response: true,  apilevel: [0, 9, 5, 0]};
-    pub const FLASH_ERASE: ComSpec           = ComSpec{verb: 0x3200, w_words: 4,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const FLASH_PP: ComSpec              = ComSpec{verb: 0x3300, w_words: 130,   r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const FLASH_LOCK: ComSpec            = ComSpec{verb: 0x3400, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]}; // lock activity for updates
-    pub const FLASH_UNLOCK: ComSpec          = ComSpec{verb: 0x3434, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]}; // unlock activity for updates
-    pub const FLASH_VERIFY: ComSpec          = ComSpec{verb: 0x3500, w_words: 2,     r_words: 128   ,response: false, apilevel: [0, 9, 8, 8]};
+    pub const FLASH_WAITACK: ComSpec         = ComSpec{verb: 0x3000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const FLASH_ACK: ComSpec             = ComSpec{verb: 0x3CC3, w_words: 0,     r_words: 0     ,response: true,  apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const FLASH_ERASE: ComSpec           = ComSpec{verb: 0x3200, w_words: 4,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Destructive, deprecated: false};
+    pub const FLASH_PP: ComSpec              = ComSpec{verb: 0x3300, w_words: 130,   r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Destructive, deprecated: false};
+    pub const FLASH_LOCK: ComSpec            = ComSpec{verb: 0x3400, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false}; // lock activity for updates
+    pub const FLASH_UNLOCK: ComSpec          = ComSpec{verb: 0x3434, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false}; // unlock activity for updates
+    pub const FLASH_VERIFY: ComSpec          = ComSpec{verb: 0x3500, w_words: 2,     r_words: 128   ,response: false, apilevel: [0, 9, 8, 8], danger: DangerLevel::Safe, deprecated: false};
 
     // system meta commands
-    pub const LOOP_TEST: ComSpec             = ComSpec{verb: 0x4000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const EC_GIT_REV: ComSpec            = ComSpec{verb: 0x4001, w_words: 0,     r_words: 3     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const UPTIME: ComSpec                = ComSpec{verb: 0x4002, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const TRNG_SEED: ComSpec             = ComSpec{verb: 0x4003, w_words: 8,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const EC_SW_TAG: ComSpec             = ComSpec{verb: 0x4004, w_words: 0,     r_words: 16    ,response: false, apilevel: [0, 9, 6, 0]};
-    pub const LINK_PING: ComSpec             = ComSpec{verb: 0x4005, w_words: 1,     r_words: 2     , response: false, apilevel: [0, 9, 6, 0]};
-
-    // charger "dangerous" commands
-    pub const CHG_START: ComSpec             = ComSpec{verb: 0x5A00, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const CHG_BOOST_ON: ComSpec          = ComSpec{verb: 0x5ABB, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const CHG_BOOST_OFF: ComSpec         = ComSpec{verb: 0x5AFE, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const LOOP_TEST: ComSpec             = ComSpec{verb: 0x4000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const EC_GIT_REV: ComSpec            = ComSpec{verb: 0x4001, w_words: 0,     r_words: 3     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const UPTIME: ComSpec                = ComSpec{verb: 0x4002, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const TRNG_SEED: ComSpec             = ComSpec{verb: 0x4003, w_words: 8,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const EC_SW_TAG: ComSpec             = ComSpec{verb: 0x4004, w_words: 0,     r_words: 16    ,response: false, apilevel: [0, 9, 6, 0], danger: DangerLevel::Safe, deprecated: false};
+    // LINK_PING: 1 word argument; returns the bitwise inverse of the argument, then a free-running ping counter
+    // that increments on every LINK_PING and restarts from 0 when the EC reboots. See link::Liveness.
+    pub const LINK_PING: ComSpec             = ComSpec{verb: 0x4005, w_words: 1,     r_words: 2     , response: false, apilevel: [0, 9, 6, 0], danger: DangerLevel::Safe, deprecated: false};
+    // TRNG_STREAM_FETCH: 1 header word (serdes::TrngStream) + TRNG_STREAM_WORDS words of raw EC entropy.
+    // Unlike TRNG_SEED, which pushes SoC entropy down to the EC, this pulls EC entropy up for mixing on the SoC.
+    pub const TRNG_STREAM_FETCH: ComSpec     = ComSpec{verb: 0x4006, w_words: 0,     r_words: 1+16  ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // EC_LOG_ESCALATION_SET: serdes::LogEscalation (2 words). EC log records are normally batched before
+    // INT_EC_LOG_READY is raised; records at or above the configured severity raise it immediately, subject
+    // to a rate limit so a flood of errors cannot storm the SoC.
+    pub const EC_LOG_ESCALATION_SET: ComSpec = ComSpec{verb: 0x4007, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // EC_LOG_FETCH: 1 header word (count of valid records in bits 0-7, records still queued in bits 8-15), then
+    // EC_LOG_PAGE_LEN serdes::LogRecords of 20 words each, oldest first. Large: decode with stream::PageDecoder.
+    pub const EC_LOG_FETCH: ComSpec          = ComSpec{verb: 0x4008, w_words: 0,     r_words: 1+16*20,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // AUDIT_LOG_FETCH: 1 header word (count of valid records in bits 0-7, records still queued in bits 8-15), then
+    // AUDIT_PAGE_LEN serdes::AuditRecords of 4 words each, oldest first. The EC appends a record whenever a verb in
+    // serdes::AUDITED_VERBS executes; records are removed as they are fetched.
+    pub const AUDIT_LOG_FETCH: ComSpec       = ComSpec{verb: 0x4009, w_words: 0,     r_words: 1+16*4,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+
+    // charger "dangerous" commands: see the danger field
+    pub const CHG_START: ComSpec             = ComSpec{verb: 0x5A00, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false};
+    pub const CHG_BOOST_ON: ComSpec          = ComSpec{verb: 0x5ABB, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Destructive, deprecated: false};
+    pub const CHG_BOOST_OFF: ComSpec         = ComSpec{verb: 0x5AFE, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false};
 
     // backlight: this is an odd bird: back light is set by directly using the lower 10 bits to code the backlight level
-    pub const BL_START: ComSpec              = ComSpec{verb: 0x6800, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const BL_END: ComSpec                = ComSpec{verb: 0x6BFF, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const BL_START: ComSpec              = ComSpec{verb: 0x6800, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const BL_END: ComSpec                = ComSpec{verb: 0x6BFF, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // gas gauge commands
-    pub const GAS_GAUGE: ComSpec             = ComSpec{verb: 0x7000, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GG_FACTORY_CAPACITY: ComSpec   = ComSpec{verb: 0x7676, w_words: 1,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GG_GET_CAPACITY: ComSpec       = ComSpec{verb: 0x7600, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GG_DEBUG: ComSpec              = ComSpec{verb: 0x7200, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GG_SOC: ComSpec                = ComSpec{verb: 0x7300, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GG_REMAINING: ComSpec          = ComSpec{verb: 0x7400, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GG_FULL_CAPACITY: ComSpec      = ComSpec{verb: 0x7402, w_words: 0,     r_words: 1,     response: false, apilevel: [0, 9, 5, 0]};
+    pub const GAS_GAUGE: ComSpec             = ComSpec{verb: 0x7000, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const GG_FACTORY_CAPACITY: ComSpec   = ComSpec{verb: 0x7676, w_words: 1,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Destructive, deprecated: false};
+    pub const GG_GET_CAPACITY: ComSpec       = ComSpec{verb: 0x7600, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const GG_DEBUG: ComSpec              = ComSpec{verb: 0x7200, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const GG_SOC: ComSpec                = ComSpec{verb: 0x7300, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const GG_REMAINING: ComSpec          = ComSpec{verb: 0x7400, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const GG_FULL_CAPACITY: ComSpec      = ComSpec{verb: 0x7402, w_words: 0,     r_words: 1,     response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // charger status - non-dangerous charger commands
-    pub const STAT: ComSpec                  = ComSpec{verb: 0x8000, w_words: 0,     r_words: 16    ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const STAT_RETURN: ComSpec           = ComSpec{verb: 0x8001, w_words: 0,     r_words: 0     ,response: true,  apilevel: [0, 9, 5, 0]};
+    pub const STAT: ComSpec                  = ComSpec{verb: 0x8000, w_words: 0,     r_words: 16    ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const STAT_RETURN: ComSpec           = ComSpec{verb: 0x8001, w_words: 0,     r_words: 0     ,response: true,  apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // power state commands
-    pub const POWER_OFF: ComSpec             = ComSpec{verb: 0x9000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const POWER_CHARGER_STATE: ComSpec   = ComSpec{verb: 0x9100, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const POWER_SHIPMODE: ComSpec        = ComSpec{verb: 0x9200, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const POWER_OFF: ComSpec             = ComSpec{verb: 0x9000, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Disruptive, deprecated: false};
+    pub const POWER_CHARGER_STATE: ComSpec   = ComSpec{verb: 0x9100, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const POWER_SHIPMODE: ComSpec        = ComSpec{verb: 0x9200, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Destructive, deprecated: false};
 
     // gyro commands
-    pub const GYRO_UPDATE: ComSpec           = ComSpec{verb: 0xA000, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const GYRO_READ: ComSpec             = ComSpec{verb: 0xA100, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const GYRO_UPDATE: ComSpec           = ComSpec{verb: 0xA000, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const GYRO_READ: ComSpec             = ComSpec{verb: 0xA100, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    // IMU die temperature in degrees C as serdes::I8F8
+    pub const GYRO_READ_TEMP: ComSpec        = ComSpec{verb: 0xA101, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // IMU_WOM_CONFIG: 2 words, serdes::ImuWomConfig. Arms wake on motion, which raises INT_IMU_MOTION; threshold 0 disarms it.
+    pub const IMU_WOM_CONFIG: ComSpec        = ComSpec{verb: 0xA200, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // USB CC commands
-    pub const POLL_USB_CC: ComSpec           = ComSpec{verb: 0xB000, w_words: 0,     r_words: 5     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const POLL_USB_CC: ComSpec           = ComSpec{verb: 0xB000, w_words: 0,     r_words: 5     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // encoded length WLAN frames
     // LSB mask of 0x7FF encodes number of *bytes* to fetch or send; in the case that an odd number of bytes are
@@ -118,13 +435,77 @@ impl ComState {
     // note: entries are not comprehensively encoded, just a few examples provided
     // The first word of a "FETCH" frame confirms the number of words to be sent. It should be equal to the LSB of the verb minus 1.
     // "SEND" frames do not encode a confirmation of words to send
-    pub const NET_FRAME_FETCH_0: ComSpec     = ComSpec{verb: 0xC800, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const NET_FRAME_FETCH_1: ComSpec     = ComSpec{verb: 0xC801, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const NET_FRAME_FETCH_2: ComSpec     = ComSpec{verb: 0xC802, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const NET_FRAME_FETCH_7FF: ComSpec   = ComSpec{verb: 0xCFFF, w_words: 0,     r_words: 0x7FF ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const NET_FRAME_SEND_0: ComSpec      = ComSpec{verb: 0xC000, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const NET_FRAME_SEND_1: ComSpec      = ComSpec{verb: 0xC001, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const NET_FRAME_SEND_7FF: ComSpec    = ComSpec{verb: 0xC7FF, w_words: 0x7FF, r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const NET_FRAME_FETCH_0: ComSpec     = ComSpec{verb: 0xC800, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_FRAME_FETCH_1: ComSpec     = ComSpec{verb: 0xC801, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_FRAME_FETCH_2: ComSpec     = ComSpec{verb: 0xC802, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_FRAME_FETCH_7FF: ComSpec   = ComSpec{verb: 0xCFFF, w_words: 0,     r_words: 0x7FF ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_FRAME_SEND_0: ComSpec      = ComSpec{verb: 0xC000, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_FRAME_SEND_1: ComSpec      = ComSpec{verb: 0xC001, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_FRAME_SEND_7FF: ComSpec    = ComSpec{verb: 0xC7FF, w_words: 0x7FF, r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    // NET_FRAME_CONFIG: 1 word of frame::NET_FRAME_FLAG_* bits, selecting optional framing extensions.
+    // Each flag requires its PROTO_FEATURE_NET_* bit; all flags are clear after reset.
+    pub const NET_FRAME_CONFIG: ComSpec      = ComSpec{verb: 0x2400, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 1], danger: DangerLevel::Safe, deprecated: false};
+    // NET_TX_STATUS: serdes::TxStatusPage, up to TX_STATUS_PAGE_LEN of the oldest unread TX completions, removed
+    // from the EC's queue. Each frame is identified by its handle: the number of NET_FRAME_SEND verbs the EC has
+    // received since reset, counting from 0 and wrapping at 16 bits, so the host can track it too.
+    pub const NET_TX_STATUS: ComSpec         = ComSpec{verb: 0x2401, w_words: 0,     r_words: 1+8*2 ,response: false, apilevel: [0, 9, 9, 1], danger: DangerLevel::Safe, deprecated: false};
+    // NET_TX_CREDITS: 1 word, the credit limit: the handle (see NET_TX_STATUS) of the first frame the EC has no
+    // buffer for. The host may send frames while its next handle is before the limit; frame::CreditTracker
+    // does the accounting. Requires PROTO_FEATURE_NET_CREDITS.
+    pub const NET_TX_CREDITS: ComSpec        = ComSpec{verb: 0x2402, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 1], danger: DangerLevel::Safe, deprecated: false};
+    // NET_SET_MTU: 1 word serdes::Mtu the host would like; the EC clamps it to what its buffers allow.
+    // NET_GET_MTU: 1 word serdes::Mtu in effect, which sizes both ends' frame buffers. Starts at Mtu::DEFAULT.
+    pub const NET_SET_MTU: ComSpec           = ComSpec{verb: 0x2403, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_GET_MTU: ComSpec           = ComSpec{verb: 0x2404, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // IPv4 multicast membership offload: the EC keeps the host's group memberships, answering IGMP queries and
+    // sending reports on every join even while the SoC sleeps, and adds each group's MAC address to the
+    // WLAN_MCAST_* filter, whose capacity it shares. Groups are kept across reconnects and cleared by WLAN_OFF.
+    // - IGMP_JOIN / IGMP_LEAVE: serdes::MulticastGroup (2 words). Joining when the filter is full does nothing.
+    pub const NET_IGMP_JOIN: ComSpec         = ComSpec{verb: 0x2405, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_IGMP_LEAVE: ComSpec        = ComSpec{verb: 0x2406, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // ARP offload, so the device stays reachable on the LAN while the SoC is suspended. While enabled, the EC
+    // answers ARP requests for the configured address itself and does not forward them to the SoC.
+    // - ARP_OFFLOAD_CONFIG: serdes::ArpOffloadConfig (3 words). Clears the answered counter.
+    // - ARP_OFFLOAD_STATUS: serdes::ArpOffloadStatus (5 words): the config, and the number of requests answered
+    //   since it was set
+    pub const NET_ARP_OFFLOAD_CONFIG: ComSpec = ComSpec{verb: 0x2407, w_words: 3,    r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_ARP_OFFLOAD_STATUS: ComSpec = ComSpec{verb: 0x2408, w_words: 0,    r_words: 3+2   ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // wake on WLAN: while the link is up, a received frame that matches one of the installed patterns raises
+    // INT_NET_WAKE, so the SoC can sleep through other traffic. The matching frame is queued for NET_FRAME_FETCH
+    // as usual. The patterns are kept across reconnects and cleared by WLAN_OFF.
+    // - WAKE_SET: serdes::WakePatternEntry (4 words), installs a pattern in a slot or clears the slot
+    // - WAKE_LIST: serdes::WakePatternTable (8*3 words)
+    pub const NET_WAKE_SET: ComSpec          = ComSpec{verb: 0x2409, w_words: 1+3,   r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_WAKE_LIST: ComSpec         = ComSpec{verb: 0x240A, w_words: 0,     r_words: 8*3   ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // TCP keepalive offload, so long-lived connections survive SoC sleep. The EC sends the template's segment to
+    // the peer every interval and counts the ACKs it gets back; a RST from the peer, or three probes in a row
+    // without an ACK, stop the slot. The template's sequence numbers go stale as soon as the host uses the
+    // connection again, so the host clears or reprograms the slot before it does. Slots are cleared by WLAN_OFF.
+    // - KEEPALIVE_SET: serdes::KeepaliveTemplate (11+17 words); an interval of 0 clears the slot
+    // - KEEPALIVE_STATUS: 1 word slot -> serdes::KeepaliveStatus (6 words)
+    pub const NET_KEEPALIVE_SET: ComSpec     = ComSpec{verb: 0x240B, w_words: 11+17, r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_KEEPALIVE_STATUS: ComSpec  = ComSpec{verb: 0x240C, w_words: 1,     r_words: 6     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // mDNS responder offload: the EC answers mDNS queries for <hostname>.local and the registered services itself,
+    // with the address from WLAN_GET_IPV4_CONF, so the device stays discoverable while the SoC is suspended. The
+    // queries it answers are not forwarded to the SoC. Hostname and services are cleared by WLAN_OFF.
+    // - MDNS_SET_HOSTNAME: hostname label (no ".local"), in the StringSer<STR_64_WORDS> format; empty turns the
+    //   responder off
+    // - MDNS_SET_SERVICE: serdes::MdnsService (2+17+33+33 words); port 0 clears the slot
+    // - MDNS_STATS: serdes::MdnsStats (4 words)
+    pub const NET_MDNS_SET_HOSTNAME: ComSpec = ComSpec{verb: 0x240D, w_words: 33,    r_words: 0     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_MDNS_SET_SERVICE: ComSpec  = ComSpec{verb: 0x240E, w_words: 2+17+33+33, r_words: 0 ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    pub const NET_MDNS_STATS: ComSpec        = ComSpec{verb: 0x240F, w_words: 0,     r_words: 4     ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // NET_TRAFFIC_STATS: serdes::TrafficStats (7*2 words), cumulative counters of the station link since WLAN_ON
+    pub const NET_TRAFFIC_STATS: ComSpec     = ComSpec{verb: 0x2410, w_words: 0,     r_words: 7*2   ,response: false, apilevel: [0, 9, 9, 3], danger: DangerLevel::Safe, deprecated: false};
+    // encoded length raw 802.11 frames
+    // NET_RAW_SEND: LSB mask of 0x7FF encodes the number of bytes to send, packed as for NET_FRAME_SEND. The payload is a
+    // complete 802.11 frame, MAC header included, which the EC transmits as is: no header rewriting, no encryption,
+    // no sequence numbering. For factory RF tests and protocol research only. Requires PROTO_FEATURE_RAW_TX; EC
+    // builds that do not advertise it discard the payload. Every raw send is recorded in the audit log. Destructive:
+    // a malformed or spoofed frame can disrupt other stations on the channel, not just this link.
+    pub const NET_RAW_SEND_0: ComSpec        = ComSpec{verb: 0x2800, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Destructive, deprecated: false};
+    pub const NET_RAW_SEND_1: ComSpec        = ComSpec{verb: 0x2801, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Destructive, deprecated: false};
+    pub const NET_RAW_SEND_7FF: ComSpec      = ComSpec{verb: 0x2FFF, w_words: 0x400, r_words: 0     ,response: false, apilevel: [0, 9, 9, 2], danger: DangerLevel::Destructive, deprecated: false};
 
     // protocol overhead commands
     // - GET_INTERRUPT: 1 word interrupt source, 1 word rx len argument *in bytes* (always returned) -> 2 words
@@ -132,16 +513,151 @@ impl ComState {
     // - GET_INTMASK: 1 read word for the current interrupt bitmask
     // - ACK_INTERRUPT: 1 word for acknowledging interrupts. All bits set in the ACK will set the GET_INTERRUPT bit to 0.
     //   note that also calling a verb that handles an interrupt will implicitly acknowledge and clear the interrupt source
-    pub const LINK_READ: ComSpec             = ComSpec{verb: 0xF0F0, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]}; // dummy command to "pump" the bus to read data
-    pub const LINK_SYNC: ComSpec             = ComSpec{verb: 0xFFFF, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const LINK_GET_INTERRUPT: ComSpec    = ComSpec{verb: 0xF108, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const LINK_SET_INTMASK: ComSpec      = ComSpec{verb: 0xF109, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const LINK_GET_INTMASK: ComSpec      = ComSpec{verb: 0xF10A, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0]};
-    pub const LINK_ACK_INTERRUPT: ComSpec    = ComSpec{verb: 0xF10B, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0]};
+    pub const LINK_READ: ComSpec             = ComSpec{verb: 0xF0F0, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false}; // dummy command to "pump" the bus to read data
+    pub const LINK_SYNC: ComSpec             = ComSpec{verb: 0xFFFF, w_words: 0,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_GET_INTERRUPT: ComSpec    = ComSpec{verb: 0xF108, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_SET_INTMASK: ComSpec      = ComSpec{verb: 0xF109, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_GET_INTMASK: ComSpec      = ComSpec{verb: 0xF10A, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_ACK_INTERRUPT: ComSpec    = ComSpec{verb: 0xF10B, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
+    // 32-bit interrupt variants. Masks and sources are 2 words, low word first; see the INT32_* constants.
+    // - GET_INTERRUPT32: 2 words interrupt source, 1 word rx len argument *in bytes* -> 3 words
+    // - SET_INTMASK32, GET_INTMASK32, ACK_INTERRUPT32: as above, with 2 word bitmasks
+    // The 16-bit verbs remain and only see the bits both schemes share: SET_INTMASK leaves bits 16-30 of
+    // the 32-bit mask alone, and GET_INTERRUPT does not report them.
+    pub const LINK_GET_INTERRUPT32: ComSpec  = ComSpec{verb: 0xF10C, w_words: 0,     r_words: 3     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_SET_INTMASK32: ComSpec    = ComSpec{verb: 0xF10D, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_GET_INTMASK32: ComSpec    = ComSpec{verb: 0xF10E, w_words: 0,     r_words: 2     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_ACK_INTERRUPT32: ComSpec  = ComSpec{verb: 0xF10F, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - SET_CRC_MODE: 1 word crc::CrcMode. When on, a CRC-16 trailer word follows every non-empty payload
+    //   in both directions (see the crc module). LINK_* verbs are never framed.
+    // - GET_CRC_MODE: 1 read word for the current crc::CrcMode
+    pub const LINK_SET_CRC_MODE: ComSpec     = ComSpec{verb: 0xF110, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_GET_CRC_MODE: ComSpec     = ComSpec{verb: 0xF111, w_words: 0,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - SEQ_RESET: 1 word starting sequence number for both sides
+    // - SEQ_TAG: 1 word sequence number of the transaction that follows; returns 1 word, the sequence number the EC expected
+    // - SEQ_NAK: 1 word sequence number of a transaction whose response was lost. The EC re-queues that
+    //   response, which is then pumped out with LINK_READ. See link::LinkSeq for the tracking rules.
+    pub const LINK_SEQ_RESET: ComSpec        = ComSpec{verb: 0xF112, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_SEQ_TAG: ComSpec          = ComSpec{verb: 0xF113, w_words: 1,     r_words: 1     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_SEQ_NAK: ComSpec          = ComSpec{verb: 0xF114, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - SUBSCRIBE: 2 words serdes::Subscription (state-id, threshold). INT_LINK_SUBSCRIPTION is raised when the
+    //   tracked value crosses the threshold; the GET_INTERRUPT argument is the source id of the subscription.
+    pub const LINK_SUBSCRIBE: ComSpec        = ComSpec{verb: 0xF115, w_words: 2,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - GET_STATS: serdes::LinkStats, 4 counters of 2 words each
+    pub const LINK_GET_STATS: ComSpec        = ComSpec{verb: 0xF116, w_words: 0,     r_words: 8     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - SHUTDOWN_NOTIFY: 1 word ShutdownReason. Sent by the SoC before it takes the link down; on receipt the EC
+    //   finishes in-flight transactions and stops raising interrupts until the next LINK_SYNC. The EC announces
+    //   its own shutdowns (e.g. reboot into new firmware) with INT_LINK_SHUTDOWN instead.
+    pub const LINK_SHUTDOWN_NOTIFY: ComSpec  = ComSpec{verb: 0xF117, w_words: 1,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Disruptive, deprecated: false};
+    // - PROTOCOL_VERSION: serdes::ProtocolVersion, major/minor/patch + 2 words of feature bits
+    pub const LINK_PROTOCOL_VERSION: ComSpec = ComSpec{verb: 0xF118, w_words: 0,     r_words: 5     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - LIST_VERBS: 1 word page index; returns serdes::VerbListPage, the EC's implemented verbs VERB_LIST_PAGE_LEN at a time
+    pub const LINK_LIST_VERBS: ComSpec       = ComSpec{verb: 0xF119, w_words: 1,     r_words: 1+16*3,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - EVENT_QUEUE_ENABLE: 1 word, nonzero to enable. While enabled, the EC also records every interrupt it raises
+    //   into a FIFO event queue, so bursts keep their order and individual arguments.
+    // - EVENT_FETCH: serdes::EventPage, up to EVENT_PAGE_LEN of the oldest queued events, removed from the queue
+    pub const LINK_EVENT_QUEUE_ENABLE: ComSpec = ComSpec{verb: 0xF11A, w_words: 1,   r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    pub const LINK_EVENT_FETCH: ComSpec      = ComSpec{verb: 0xF11B, w_words: 0,     r_words: 1+8*4 ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
+    // - SET_COALESCE: serdes::CoalesceConfig (4 words), rate-limits assertion of one interrupt source
+    pub const LINK_SET_COALESCE: ComSpec     = ComSpec{verb: 0xF11C, w_words: 4,     r_words: 0     ,response: false, apilevel: [0, 9, 9, 0], danger: DangerLevel::Safe, deprecated: false};
 
     // catch-all error code
-    pub const ERROR: ComSpec                 = ComSpec{verb: 0xDEAD, w_words: 0,     r_words: 0     ,response: true, apilevel: [0, 9, 5, 0]};
+    pub const ERROR: ComSpec                 = ComSpec{verb: 0xDEAD, w_words: 0,     r_words: 0     ,response: true, apilevel: [0, 9, 5, 0], danger: DangerLevel::Safe, deprecated: false};
 }
+impl ComState {
+    /// The spec of `verb`, if it is one of ALL. Deprecated verbs are refused unless the
+    /// `legacy-verbs` feature is enabled.
+    pub fn lookup(verb: u16) -> Option<&'static ComSpec> {
+        ComState::ALL.iter().find(|spec| spec.verb == verb && (cfg!(feature = "legacy-verbs") || !spec.deprecated))
+    }
+
+    /// The name of `verb` as written in this crate, e.g. "WLAN_JOIN" for 0x2304, for traces and
+    /// debug shells
+    pub fn name_of(verb: u16) -> Option<&'static str> {
+        ComState::ALL.iter().position(|spec| spec.verb == verb).map(|i| ComState::NAMES[i])
+    }
+
+    /// The verbs of one category, in table order
+    pub fn in_category(category: VerbClass) -> impl Iterator<Item = &'static ComSpec> {
+        ComState::ALL.iter().filter(move |spec| spec.category() == category)
+    }
+
+    /// The NET_FRAME_FETCH verb for a frame of `len_bytes`. The response is the confirmation
+    /// word followed by the frame, zero-padded to whole words.
+    pub const fn net_frame_fetch(len_bytes: u16) -> Result<ComSpec, ComError> {
+        if len_bytes > NET_FRAME_MAX_LEN {
+            return Err(ComError::FrameTooLong(len_bytes));
+        }
+        Ok(ComSpec {
+            verb: ComState::NET_FRAME_FETCH_0.verb | len_bytes,
+            r_words: 1 + len_bytes.div_ceil(2),
+            ..ComState::NET_FRAME_FETCH_0
+        })
+    }
+
+    /// The NET_FRAME_SEND verb for a frame of `len_bytes`. The payload is the frame, zero-padded
+    /// to whole words, with no confirmation word.
+    pub const fn net_frame_send(len_bytes: u16) -> Result<ComSpec, ComError> {
+        if len_bytes > NET_FRAME_MAX_LEN {
+            return Err(ComError::FrameTooLong(len_bytes));
+        }
+        Ok(ComSpec {
+            verb: ComState::NET_FRAME_SEND_0.verb | len_bytes,
+            w_words: len_bytes.div_ceil(2),
+            ..ComState::NET_FRAME_SEND_0
+        })
+    }
+
+    /// The NET_RAW_SEND verb for a raw 802.11 frame of `len_bytes`, for a peer advertising
+    /// `features` (ProtocolVersion::features). Refused unless the peer has PROTO_FEATURE_RAW_TX.
+    pub const fn net_raw_send(len_bytes: u16, features: u32) -> Result<ComSpec, ComError> {
+        if features & serdes::PROTO_FEATURE_RAW_TX == 0 {
+            return Err(ComError::RawTxDisabled);
+        }
+        if len_bytes > NET_FRAME_MAX_LEN {
+            return Err(ComError::FrameTooLong(len_bytes));
+        }
+        Ok(ComSpec {
+            verb: ComState::NET_RAW_SEND_0.verb | len_bytes,
+            w_words: len_bytes.div_ceil(2),
+            ..ComState::NET_RAW_SEND_0
+        })
+    }
+
+    /// True if `verb` is a NET_RAW_SEND verb, of any length
+    pub const fn is_net_raw_send(verb: u16) -> bool {
+        verb & !NET_FRAME_MAX_LEN == ComState::NET_RAW_SEND_0.verb
+    }
+}
+
+/// Longest frame the length-encoded NET_FRAME and NET_RAW_SEND verbs can carry, in bytes
+pub const NET_FRAME_MAX_LEN: u16 = 0x7FF;
+
+/// Errors from building a ComSpec
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ComError {
+    /// the frame length does not fit the 11-bit length field of the NET_FRAME verbs
+    FrameTooLong(u16),
+    /// the peer does not advertise PROTO_FEATURE_RAW_TX
+    RawTxDisabled,
+}
+
+/// True if no two specs in `table` share a verb value
+pub const fn verbs_unique(table: &[ComSpec]) -> bool {
+    let mut i = 0;
+    while i < table.len() {
+        let mut j = i + 1;
+        while j < table.len() {
+            if table[i].verb == table[j].verb {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+const _: () = assert!(verbs_unique(ComState::ALL), "two ComState verbs share a value");
 
 
 // COM interrupt mask bits
@@ -153,21 +669,271 @@ pub const INT_WLAN_IPCONF_UPDATE: u16 = 0b0000_0000_0000_0010;
 pub const INT_WLAN_SSID_UPDATE: u16   = 0b0000_0000_0000_0100;
 // set when battery is critical and system is about to shut down
 pub const INT_BATTERY_CRITICAL: u16   = 0b0000_0000_0000_1000;
-// set if there's an error transmitting a packet
+// set if there's an error transmitting a packet. NET_TX_STATUS reports which one, and why.
 pub const INT_WLAN_TX_ERROR: u16      = 0b0000_0000_0001_0000;
 // set if there's an error receiving a packet
 pub const INT_WLAN_RX_ERROR: u16      = 0b0000_0000_0010_0000;
 // set if there's a disconnect event happened
 pub const INT_WLAN_DISCONNECT: u16    = 0b0000_0000_0100_0000;
-// set when a connection attempt finishes. must read the status code for the exact result.
+// set when a connection attempt finishes. Read WLAN_GET_CONNECT_RESULT for the exact result.
 pub const INT_WLAN_CONNECT_EVENT: u16 = 0b0000_0000_1000_0000;
-// set when SSID scan has new data.
+// set when an SSID scan finishes. Read SSID_SCAN_RESULT_SUMMARY for whether the results are complete.
 pub const INT_WLAN_SSID_FINISHED: u16 = 0b0000_0001_0000_0000;
 // set when the WFX chip throws a HIF error
 pub const INT_WLAN_WFX_ERR: u16       = 0b0000_0010_0000_0000;
+// set when a LINK_SUBSCRIBE threshold is crossed. Argument is the subscription source id.
+pub const INT_LINK_SUBSCRIPTION: u16  = 0b0000_0100_0000_0000;
+// set when the EC is about to take the link down. Argument is the ShutdownReason.
+pub const INT_LINK_SHUTDOWN: u16      = 0b0000_1000_0000_0000;
 // reserve one code for internal error handling
 pub const INT_INVALID: u16            = 0b1000_0000_0000_0000;
 
+// 32-bit interrupt scheme, used with the LINK_*32 verbs.
+// Bits 0-14 keep the positions of the 16-bit INT_* bits above. The invalid marker moves from bit 15
+// to bit 31, and new interrupt sources are allocated from bit 16 upwards, so they are only visible
+// to hosts that use the 32-bit verbs.
+// bits that mean the same thing in both schemes
+pub const INT32_LEGACY_MASK: u32      = 0x0000_7FFF;
+// reserve one code for internal error handling; the 32-bit equivalent of INT_INVALID
+pub const INT32_INVALID: u32          = 0x8000_0000;
+
+/// Map a 16-bit interrupt bitmask into the 32-bit scheme
+pub const fn int16_to_int32(bits: u16) -> u32 {
+    let mut ret = bits as u32 & INT32_LEGACY_MASK;
+    if bits & INT_INVALID != 0 {
+        ret |= INT32_INVALID;
+    }
+    ret
+}
+
+/// Map a 32-bit interrupt bitmask into the 16-bit scheme, dropping bits that have no 16-bit equivalent
+pub const fn int32_to_int16(bits: u32) -> u16 {
+    let mut ret = (bits & INT32_LEGACY_MASK) as u16;
+    if bits & INT32_INVALID != 0 {
+        ret |= INT_INVALID;
+    }
+    ret
+}
+
+// 32-bit only interrupt sources
+// set when EC log records are ready to be fetched. Raised per batch, or immediately for escalated records.
+pub const INT_EC_LOG_READY: u32       = 0x0001_0000;
+// set when a charger is plugged in. Argument is the POWER_CHARGER_STATE word after the change.
+pub const INT_CHG_INSERTED: u32       = 0x0002_0000;
+// set when the charger is unplugged. Argument is the POWER_CHARGER_STATE word after the change.
+pub const INT_CHG_REMOVED: u32        = 0x0004_0000;
+// set once when the state of charge falls to BATTERY_LOW_SOC_PERCENT, well ahead of INT_BATTERY_CRITICAL.
+// Argument is the state of charge in percent, as GG_SOC would return it. Re-armed by charging above the threshold.
+pub const INT_BATTERY_LOW: u32        = 0x0008_0000;
+/// state of charge at which INT_BATTERY_LOW is raised
+pub const BATTERY_LOW_SOC_PERCENT: u16 = 10;
+// set when the IMU detects motion above the IMU_WOM_CONFIG threshold. Raised at most once per IMU_WOM_CONFIG;
+// re-arm by sending IMU_WOM_CONFIG again.
+pub const INT_IMU_MOTION: u32         = 0x0010_0000;
+// set when the EC frees TX buffers while the host has no NET_FRAME_SEND credits left. Argument is the new
+// NET_TX_CREDITS limit.
+pub const INT_NET_TX_CREDITS: u32     = 0x0020_0000;
+// set when the EC finishes joining a known network on its own. Argument is a serdes::AutojoinResult.
+pub const INT_WLAN_AUTOJOIN: u32      = 0x0040_0000;
+// set when a WPS session ends. Argument is a serdes::WpsResult.
+pub const INT_WLAN_WPS: u32           = 0x0080_0000;
+// set when a station associates with or leaves the SoftAP. Argument is the new number of stations.
+pub const INT_WLAN_AP_STATION: u32    = 0x0100_0000;
+// set when a received frame matches a wake pattern installed with NET_WAKE_SET. Argument is the slot of the
+// pattern.
+pub const INT_NET_WAKE: u32           = 0x0200_0000;
+// set when the averaged RSSI has moved by the WLAN_SET_RSSI_FILTER delta since it was last reported. Argument is
+// the new average as serdes::Rssi, as WLAN_GET_RSSI_AVG would return it.
+pub const INT_WLAN_RSSI_UPDATE: u32   = 0x0400_0000;
+
+/// Typed set of interrupt sources in the 32-bit scheme.
+/// The associated consts mirror the INT_* constants, which remain for existing code.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct InterruptFlags(u32);
+impl InterruptFlags {
+    pub const WLAN_RX_READY: InterruptFlags = InterruptFlags(INT_WLAN_RX_READY as u32);
+    pub const WLAN_IPCONF_UPDATE: InterruptFlags = InterruptFlags(INT_WLAN_IPCONF_UPDATE as u32);
+    pub const WLAN_SSID_UPDATE: InterruptFlags = InterruptFlags(INT_WLAN_SSID_UPDATE as u32);
+    pub const BATTERY_CRITICAL: InterruptFlags = InterruptFlags(INT_BATTERY_CRITICAL as u32);
+    pub const WLAN_TX_ERROR: InterruptFlags = InterruptFlags(INT_WLAN_TX_ERROR as u32);
+    pub const WLAN_RX_ERROR: InterruptFlags = InterruptFlags(INT_WLAN_RX_ERROR as u32);
+    pub const WLAN_DISCONNECT: InterruptFlags = InterruptFlags(INT_WLAN_DISCONNECT as u32);
+    pub const WLAN_CONNECT_EVENT: InterruptFlags = InterruptFlags(INT_WLAN_CONNECT_EVENT as u32);
+    pub const WLAN_SSID_FINISHED: InterruptFlags = InterruptFlags(INT_WLAN_SSID_FINISHED as u32);
+    pub const WLAN_WFX_ERR: InterruptFlags = InterruptFlags(INT_WLAN_WFX_ERR as u32);
+    pub const LINK_SUBSCRIPTION: InterruptFlags = InterruptFlags(INT_LINK_SUBSCRIPTION as u32);
+    pub const LINK_SHUTDOWN: InterruptFlags = InterruptFlags(INT_LINK_SHUTDOWN as u32);
+    pub const EC_LOG_READY: InterruptFlags = InterruptFlags(INT_EC_LOG_READY);
+    pub const CHG_INSERTED: InterruptFlags = InterruptFlags(INT_CHG_INSERTED);
+    pub const CHG_REMOVED: InterruptFlags = InterruptFlags(INT_CHG_REMOVED);
+    pub const BATTERY_LOW: InterruptFlags = InterruptFlags(INT_BATTERY_LOW);
+    pub const IMU_MOTION: InterruptFlags = InterruptFlags(INT_IMU_MOTION);
+    pub const NET_TX_CREDITS: InterruptFlags = InterruptFlags(INT_NET_TX_CREDITS);
+    pub const WLAN_AUTOJOIN: InterruptFlags = InterruptFlags(INT_WLAN_AUTOJOIN);
+    pub const WLAN_WPS: InterruptFlags = InterruptFlags(INT_WLAN_WPS);
+    pub const WLAN_AP_STATION: InterruptFlags = InterruptFlags(INT_WLAN_AP_STATION);
+    pub const NET_WAKE: InterruptFlags = InterruptFlags(INT_NET_WAKE);
+    pub const WLAN_RSSI_UPDATE: InterruptFlags = InterruptFlags(INT_WLAN_RSSI_UPDATE);
+    pub const INVALID: InterruptFlags = InterruptFlags(INT32_INVALID);
+
+    /// Names of the defined sources, used by `Display`
+    const NAMES: &'static [(InterruptFlags, &'static str)] = &[
+        (InterruptFlags::WLAN_RX_READY, "WLAN_RX_READY"),
+        (InterruptFlags::WLAN_IPCONF_UPDATE, "WLAN_IPCONF_UPDATE"),
+        (InterruptFlags::WLAN_SSID_UPDATE, "WLAN_SSID_UPDATE"),
+        (InterruptFlags::BATTERY_CRITICAL, "BATTERY_CRITICAL"),
+        (InterruptFlags::WLAN_TX_ERROR, "WLAN_TX_ERROR"),
+        (InterruptFlags::WLAN_RX_ERROR, "WLAN_RX_ERROR"),
+        (InterruptFlags::WLAN_DISCONNECT, "WLAN_DISCONNECT"),
+        (InterruptFlags::WLAN_CONNECT_EVENT, "WLAN_CONNECT_EVENT"),
+        (InterruptFlags::WLAN_SSID_FINISHED, "WLAN_SSID_FINISHED"),
+        (InterruptFlags::WLAN_WFX_ERR, "WLAN_WFX_ERR"),
+        (InterruptFlags::LINK_SUBSCRIPTION, "LINK_SUBSCRIPTION"),
+        (InterruptFlags::LINK_SHUTDOWN, "LINK_SHUTDOWN"),
+        (InterruptFlags::EC_LOG_READY, "EC_LOG_READY"),
+        (InterruptFlags::CHG_INSERTED, "CHG_INSERTED"),
+        (InterruptFlags::CHG_REMOVED, "CHG_REMOVED"),
+        (InterruptFlags::BATTERY_LOW, "BATTERY_LOW"),
+        (InterruptFlags::IMU_MOTION, "IMU_MOTION"),
+        (InterruptFlags::NET_TX_CREDITS, "NET_TX_CREDITS"),
+        (InterruptFlags::WLAN_AUTOJOIN, "WLAN_AUTOJOIN"),
+        (InterruptFlags::WLAN_WPS, "WLAN_WPS"),
+        (InterruptFlags::WLAN_AP_STATION, "WLAN_AP_STATION"),
+        (InterruptFlags::NET_WAKE, "NET_WAKE"),
+        (InterruptFlags::WLAN_RSSI_UPDATE, "WLAN_RSSI_UPDATE"),
+        (InterruptFlags::INVALID, "INVALID"),
+    ];
+
+    pub const fn empty() -> Self {
+        InterruptFlags(0)
+    }
+    pub const fn from_bits(bits: u32) -> Self {
+        InterruptFlags(bits)
+    }
+    /// From a mask in the 16-bit scheme
+    pub const fn from_u16(bits: u16) -> Self {
+        InterruptFlags(int16_to_int32(bits))
+    }
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+    /// To a mask in the 16-bit scheme, dropping sources it cannot express
+    pub const fn to_u16(&self) -> u16 {
+        int32_to_int16(self.0)
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// True if every source in `other` is set
+    pub const fn contains(&self, other: InterruptFlags) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// True if any source in `other` is set
+    pub const fn intersects(&self, other: InterruptFlags) -> bool {
+        self.0 & other.0 != 0
+    }
+    pub fn insert(&mut self, other: InterruptFlags) {
+        self.0 |= other.0;
+    }
+    pub fn remove(&mut self, other: InterruptFlags) {
+        self.0 &= !other.0;
+    }
+    /// Iterate over the set sources, one bit at a time, lowest bit first
+    pub fn iter(&self) -> InterruptFlagsIter {
+        InterruptFlagsIter(self.0)
+    }
+    /// Name of a single-bit set, if it is a defined source
+    pub fn name(&self) -> Option<&'static str> {
+        InterruptFlags::NAMES.iter().find(|(flag, _)| flag == self).map(|(_, name)| *name)
+    }
+    /// Wire format for the 32-bit verbs: low word first
+    pub fn encode_u16(&self) -> [u16; 2] {
+        u32_to_words(self.0)
+    }
+    pub fn decode_u16(data: &[u16; 2]) -> Self {
+        InterruptFlags(words_to_u32(*data))
+    }
+}
+impl core::ops::BitOr for InterruptFlags {
+    type Output = InterruptFlags;
+    fn bitor(self, rhs: InterruptFlags) -> InterruptFlags {
+        InterruptFlags(self.0 | rhs.0)
+    }
+}
+impl core::ops::BitOrAssign for InterruptFlags {
+    fn bitor_assign(&mut self, rhs: InterruptFlags) {
+        self.0 |= rhs.0;
+    }
+}
+impl core::ops::BitAnd for InterruptFlags {
+    type Output = InterruptFlags;
+    fn bitand(self, rhs: InterruptFlags) -> InterruptFlags {
+        InterruptFlags(self.0 & rhs.0)
+    }
+}
+impl core::ops::BitAndAssign for InterruptFlags {
+    fn bitand_assign(&mut self, rhs: InterruptFlags) {
+        self.0 &= rhs.0;
+    }
+}
+impl core::ops::Sub for InterruptFlags {
+    type Output = InterruptFlags;
+    /// Sources set in `self` but not in `rhs`
+    fn sub(self, rhs: InterruptFlags) -> InterruptFlags {
+        InterruptFlags(self.0 & !rhs.0)
+    }
+}
+impl core::ops::Not for InterruptFlags {
+    type Output = InterruptFlags;
+    fn not(self) -> InterruptFlags {
+        InterruptFlags(!self.0)
+    }
+}
+impl IntoIterator for InterruptFlags {
+    type Item = InterruptFlags;
+    type IntoIter = InterruptFlagsIter;
+    fn into_iter(self) -> InterruptFlagsIter {
+        self.iter()
+    }
+}
+impl core::fmt::Display for InterruptFlags {
+    /// Formats as names joined by '|', e.g. "WLAN_RX_READY|WLAN_DISCONNECT"; unnamed bits print in hex
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        if self.is_empty() {
+            return write!(f, "(none)");
+        }
+        for (i, flag) in self.iter().enumerate() {
+            if i != 0 {
+                write!(f, "|")?;
+            }
+            match flag.name() {
+                Some(name) => write!(f, "{}", name)?,
+                None => write!(f, "{:#x}", flag.0)?,
+            }
+        }
+        Ok(())
+    }
+}
+impl core::fmt::Debug for InterruptFlags {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "InterruptFlags({})", self)
+    }
+}
+
+/// Iterator over the sources set in an `InterruptFlags`
+pub struct InterruptFlagsIter(u32);
+impl Iterator for InterruptFlagsIter {
+    type Item = InterruptFlags;
+    fn next(&mut self) -> Option<InterruptFlags> {
+        if self.0 == 0 {
+            return None;
+        }
+        let lowest = self.0 & self.0.wrapping_neg();
+        self.0 &= !lowest;
+        Some(InterruptFlags(lowest))
+    }
+}
+
 /// Possible link layer connection states
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[repr(u16)]
@@ -256,3 +1022,59 @@ impl ConnectResult {
         }
     }
 }
+
+/// Why a connection attempt failed, reported alongside the ConnectResult by WLAN_GET_CONNECT_RESULT
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum ConnectDetail {
+    /// no detail: the attempt succeeded, or the EC could not tell
+    None = 0,
+    /// the 4-way handshake failed, almost always a wrong passphrase
+    WrongPassword = 1,
+    /// the AP requires a pairwise or group cipher the WF200 does not support
+    UnsupportedCipher = 2,
+    /// the AP requires a key management scheme the EC does not support, e.g. enterprise without EAP credentials
+    UnsupportedAkm = 3,
+    /// the AP refused the association because it has too many stations
+    ApFull = 4,
+    /// the AP refused authentication or association for another reason
+    RejectedByAp = 5,
+    /// the AP did not answer authentication or association requests
+    NoResponse = 6,
+    Unknown = 0xFFFF,
+}
+impl ConnectDetail {
+    pub fn decode_u16(detail: u16) -> Self {
+        match detail {
+            0 => ConnectDetail::None,
+            1 => ConnectDetail::WrongPassword,
+            2 => ConnectDetail::UnsupportedCipher,
+            3 => ConnectDetail::UnsupportedAkm,
+            4 => ConnectDetail::ApFull,
+            5 => ConnectDetail::RejectedByAp,
+            6 => ConnectDetail::NoResponse,
+            _ => ConnectDetail::Unknown,
+        }
+    }
+}
+
+/// Reasons for taking the COM link down, carried by LINK_SHUTDOWN_NOTIFY and INT_LINK_SHUTDOWN
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum ShutdownReason {
+    Unknown = 0,
+    /// the EC is rebooting into updated firmware
+    EcUpdate = 1,
+    PowerOff = 2,
+    Suspend = 3,
+}
+impl ShutdownReason {
+    pub fn decode_u16(reason: u16) -> Self {
+        match reason {
+            1 => ShutdownReason::EcUpdate,
+            2 => ShutdownReason::PowerOff,
+            3 => ShutdownReason::Suspend,
+            _ => ShutdownReason::Unknown,
+        }
+    }
+}
@@ -0,0 +1,534 @@
+#![forbid(unsafe_code)]
+
+// Link layer helpers shared by the SoC and the EC.
+//
+// Sequence numbering: after LINK_SEQ_RESET, the SoC tags each transaction it cares about with
+// LINK_SEQ_TAG carrying the next sequence number. The EC answers with the sequence number it
+// expected, so either side can detect words lost in the FIFO without a full LINK_SYNC reset.
+// If a response was lost or corrupted, the SoC issues LINK_SEQ_NAK with the sequence number of
+// that transaction, and the EC re-queues the response words to be pumped out with LINK_READ.
+//
+// Liveness: the SoC periodically issues LINK_PING and feeds the response to `Liveness`, which
+// flags a hung or rebooted EC so LINK_SYNC can be re-run before a real command times out.
+//
+// Bring-up: `LinkBringup` sequences a cold boot or post-EC-update reconnect: LINK_SYNC, protocol version
+// check, interrupt mask restore, then waiting for the WLAN state to settle out of Uninitialized/Initializing.
+//
+// Verb introspection: the SoC collects the EC's LINK_LIST_VERBS pages and runs `diff_verbs()`
+// against this crate's table to warn about verbs whose shape disagrees before issuing them.
+
+use crate::serdes::{ComEvent, EventPage, ProtocolVersion, VerbEntry, EVENT_PAGE_LEN};
+use crate::{ComSpec, ComState, LinkState};
+
+/// Result of checking a received sequence number against the expected one
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SeqStatus {
+    /// the sequence number was the one expected
+    InOrder,
+    /// this many transactions were skipped; the tracker has resynchronized past them
+    Dropped(u16),
+    /// the sequence number is this far behind the expected one (a duplicate or a replay)
+    Stale(u16),
+}
+
+/// Tracks the expected sequence number on one side of the link.
+/// Sequence numbers are 16 bits and wrap; anything up to half the sequence space ahead
+/// of the expected value is treated as a drop, anything behind it as stale.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct LinkSeq {
+    expected: u16,
+}
+impl LinkSeq {
+    pub const fn new(start: u16) -> Self {
+        LinkSeq { expected: start }
+    }
+
+    /// The sequence number expected next
+    pub fn expected(&self) -> u16 {
+        self.expected
+    }
+
+    /// Restart tracking at `start`, as done by LINK_SEQ_RESET
+    pub fn reset(&mut self, start: u16) {
+        self.expected = start;
+    }
+
+    /// Allocate the sequence number for an outgoing LINK_SEQ_TAG
+    pub fn tag(&mut self) -> u16 {
+        let seq = self.expected;
+        self.expected = self.expected.wrapping_add(1);
+        seq
+    }
+
+    /// Check a received sequence number, advancing the expected value past it unless it is stale.
+    pub fn accept(&mut self, seq: u16) -> SeqStatus {
+        let ahead = seq.wrapping_sub(self.expected);
+        if ahead == 0 {
+            self.expected = self.expected.wrapping_add(1);
+            SeqStatus::InOrder
+        } else if ahead < 0x8000 {
+            self.expected = seq.wrapping_add(1);
+            SeqStatus::Dropped(ahead)
+        } else {
+            SeqStatus::Stale(self.expected.wrapping_sub(seq))
+        }
+    }
+}
+impl Default for LinkSeq {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+/// Outcome of one LINK_PING exchange as judged by `Liveness`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum LivenessStatus {
+    /// echo correct and the counter advanced
+    Alive,
+    /// the echo word did not match the argument: the FIFO is out of step
+    EchoMismatch,
+    /// the counter did not advance: the EC is returning stale data
+    Stalled,
+    /// the counter went backwards: the EC rebooted since the last ping
+    Rebooted,
+}
+impl LivenessStatus {
+    /// Returns true if the SoC should run LINK_SYNC before issuing further commands
+    pub fn needs_sync(&self) -> bool {
+        *self != LivenessStatus::Alive
+    }
+}
+
+/// Host-side tracker for LINK_PING responses.
+///
+/// Issue LINK_PING with any argument, then hand the argument and the two response words
+/// to `check()`. Pings skipped by the host (e.g. timeouts) show up as the counter jumping
+/// ahead, which is still considered alive.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct Liveness {
+    last_count: Option<u16>,
+}
+impl Liveness {
+    pub const fn new() -> Self {
+        Liveness { last_count: None }
+    }
+
+    /// Forget the last counter value, e.g. after a deliberate EC reset
+    pub fn reset(&mut self) {
+        self.last_count = None;
+    }
+
+    pub fn check(&mut self, arg: u16, response: &[u16; ComState::LINK_PING.r_words as usize]) -> LivenessStatus {
+        if response[0] != !arg {
+            return LivenessStatus::EchoMismatch;
+        }
+        let count = response[1];
+        let status = match self.last_count {
+            None => LivenessStatus::Alive,
+            Some(last) => {
+                let ahead = count.wrapping_sub(last);
+                if ahead == 0 {
+                    LivenessStatus::Stalled
+                } else if ahead < 0x8000 {
+                    LivenessStatus::Alive
+                } else {
+                    LivenessStatus::Rebooted
+                }
+            }
+        };
+        if status != LivenessStatus::Stalled {
+            self.last_count = Some(count);
+        }
+        status
+    }
+}
+
+/// Stages of `LinkBringup`, in order
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BringupStage {
+    Idle,
+    /// LINK_SYNC issued, waiting for the FIFO to drain
+    Sync,
+    /// LINK_PROTOCOL_VERSION issued
+    Version,
+    /// LINK_SET_INTMASK or LINK_SET_INTMASK32 issued
+    RestoreIntmask,
+    /// polling WLAN_SYNC_STATE until the WLAN leaves Uninitialized/Initializing
+    WlanSettle,
+    Ready,
+}
+
+/// Per-stage timeouts for `LinkBringup`, in ms
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct BringupTimeouts {
+    pub sync_ms: u32,
+    /// EC firmware that predates LINK_PROTOCOL_VERSION never answers; after this long it is assumed to be V1_0
+    pub version_ms: u32,
+    pub intmask_ms: u32,
+    pub wlan_settle_ms: u32,
+}
+impl Default for BringupTimeouts {
+    fn default() -> Self {
+        BringupTimeouts {
+            sync_ms: 200,
+            version_ms: 100,
+            intmask_ms: 100,
+            wlan_settle_ms: 5000,
+        }
+    }
+}
+
+/// Inputs to `LinkBringup::handle`
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BringupEvent {
+    /// begin (or restart) the sequence
+    Start,
+    /// periodic poll, used for timeouts and WLAN state polling
+    Tick,
+    /// LINK_SYNC completed
+    Synced,
+    /// LINK_PROTOCOL_VERSION answered
+    Version(ProtocolVersion),
+    /// the interrupt mask verb completed
+    IntmaskRestored,
+    /// WLAN_SYNC_STATE answered with this link state
+    WlanState(LinkState),
+}
+
+/// What the host should do next
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BringupAction {
+    /// issue this verb, then report its completion
+    Issue(ComSpec),
+    /// nothing to do until the next event
+    Wait,
+    /// the link is up
+    Ready,
+    /// the stage timed out; the host should reset the EC and start again
+    Failed(BringupStage),
+}
+
+/// Host-side sequencer for bringing up the COM link.
+///
+/// Drive it with `handle()`, feeding the completion of each verb it asks for, plus periodic
+/// `Tick`s with a millisecond timestamp. A WLAN in ResetHold counts as settled: the EC is
+/// deliberately holding the WF200 in reset and the rest of the link is usable.
+pub struct LinkBringup {
+    timeouts: BringupTimeouts,
+    stage: BringupStage,
+    stage_start_ms: u32,
+    version: ProtocolVersion,
+    wlan: LinkState,
+}
+impl LinkBringup {
+    pub fn new(timeouts: BringupTimeouts) -> Self {
+        LinkBringup {
+            timeouts,
+            stage: BringupStage::Idle,
+            stage_start_ms: 0,
+            version: ProtocolVersion::V1_0,
+            wlan: LinkState::Unknown,
+        }
+    }
+    pub fn stage(&self) -> BringupStage {
+        self.stage
+    }
+    /// Protocol version of the EC, valid once the Version stage has passed
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+    /// Last WLAN state reported during bring-up
+    pub fn wlan_state(&self) -> LinkState {
+        self.wlan
+    }
+    /// The interrupt mask verb to use for this EC
+    pub fn intmask_verb(&self) -> ComSpec {
+        if self.version.at_least(&ProtocolVersion::V1_1) {
+            ComState::LINK_SET_INTMASK32
+        } else {
+            ComState::LINK_SET_INTMASK
+        }
+    }
+
+    fn enter(&mut self, stage: BringupStage, now_ms: u32) {
+        self.stage = stage;
+        self.stage_start_ms = now_ms;
+    }
+
+    fn timeout(&self) -> u32 {
+        match self.stage {
+            BringupStage::Sync => self.timeouts.sync_ms,
+            BringupStage::Version => self.timeouts.version_ms,
+            BringupStage::RestoreIntmask => self.timeouts.intmask_ms,
+            BringupStage::WlanSettle => self.timeouts.wlan_settle_ms,
+            BringupStage::Idle | BringupStage::Ready => u32::MAX,
+        }
+    }
+
+    fn after_version(&mut self, version: ProtocolVersion, now_ms: u32) -> BringupAction {
+        self.version = version;
+        self.enter(BringupStage::RestoreIntmask, now_ms);
+        BringupAction::Issue(self.intmask_verb())
+    }
+
+    pub fn handle(&mut self, event: BringupEvent, now_ms: u32) -> BringupAction {
+        match (self.stage, event) {
+            (_, BringupEvent::Start) => {
+                self.version = ProtocolVersion::V1_0;
+                self.wlan = LinkState::Unknown;
+                self.enter(BringupStage::Sync, now_ms);
+                BringupAction::Issue(ComState::LINK_SYNC)
+            }
+            (BringupStage::Sync, BringupEvent::Synced) => {
+                self.enter(BringupStage::Version, now_ms);
+                BringupAction::Issue(ComState::LINK_PROTOCOL_VERSION)
+            }
+            (BringupStage::Version, BringupEvent::Version(version)) => self.after_version(version, now_ms),
+            (BringupStage::RestoreIntmask, BringupEvent::IntmaskRestored) => {
+                self.enter(BringupStage::WlanSettle, now_ms);
+                BringupAction::Issue(ComState::WLAN_SYNC_STATE)
+            }
+            (BringupStage::WlanSettle, BringupEvent::WlanState(state)) => {
+                self.wlan = state;
+                match state {
+                    LinkState::Uninitialized | LinkState::Initializing | LinkState::Unknown => BringupAction::Wait,
+                    _ => {
+                        self.enter(BringupStage::Ready, now_ms);
+                        BringupAction::Ready
+                    }
+                }
+            }
+            (BringupStage::Ready, _) => BringupAction::Ready,
+            (BringupStage::Idle, _) => BringupAction::Wait,
+            (stage, BringupEvent::Tick) => {
+                if now_ms.wrapping_sub(self.stage_start_ms) < self.timeout() {
+                    if stage == BringupStage::WlanSettle {
+                        BringupAction::Issue(ComState::WLAN_SYNC_STATE)
+                    } else {
+                        BringupAction::Wait
+                    }
+                } else if stage == BringupStage::Version {
+                    self.after_version(ProtocolVersion::V1_0, now_ms)
+                } else {
+                    BringupAction::Failed(stage)
+                }
+            }
+            // completions that do not belong to the current stage are stale; ignore them
+            _ => BringupAction::Wait,
+        }
+    }
+}
+
+/// Fixed-size FIFO of interrupt events, as kept by the EC for LINK_EVENT_FETCH.
+/// When full, new events are dropped and counted rather than overwriting older ones,
+/// so the host always sees the start of a burst.
+pub struct EventRing<const N: usize> {
+    events: [ComEvent; N],
+    head: usize,
+    len: usize,
+    dropped: u32,
+}
+impl<const N: usize> EventRing<N> {
+    pub const fn new() -> Self {
+        EventRing {
+            events: [EventPage::EMPTY_EVENT; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    /// Number of events dropped because the ring was full
+    pub fn dropped(&self) -> u32 {
+        self.dropped
+    }
+    /// Queue an event; returns false if it was dropped
+    pub fn push(&mut self, event: ComEvent) -> bool {
+        if self.len == N {
+            self.dropped = self.dropped.wrapping_add(1);
+            return false;
+        }
+        self.events[(self.head + self.len) % N] = event;
+        self.len += 1;
+        true
+    }
+    /// Remove and return the oldest event
+    pub fn pop(&mut self) -> Option<ComEvent> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head];
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        Some(event)
+    }
+    /// Remove up to a page of the oldest events, for answering LINK_EVENT_FETCH
+    pub fn fetch_page(&mut self) -> EventPage {
+        let mut page = EventPage {
+            count: 0,
+            pending: 0,
+            events: [EventPage::EMPTY_EVENT; EVENT_PAGE_LEN],
+        };
+        for slot in page.events.iter_mut() {
+            match self.pop() {
+                Some(event) => {
+                    *slot = event;
+                    page.count += 1;
+                }
+                None => break,
+            }
+        }
+        page.pending = self.len.min(u8::MAX as usize) as u8;
+        page
+    }
+}
+impl<const N: usize> Default for EventRing<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A disagreement between the EC's verb list and the local `ComState` table
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VerbMismatch {
+    /// defined locally but not implemented by the EC
+    Missing(ComSpec),
+    /// implemented by the EC but unknown locally
+    Unknown(VerbEntry),
+    /// implemented by both sides with different word counts
+    Shape { local: ComSpec, ec: VerbEntry },
+}
+
+/// Compare the verbs reported by the EC with a local table, calling `report` once per mismatch.
+/// Returns the number of mismatches found.
+pub fn diff_verbs<F: FnMut(VerbMismatch)>(local: &[ComSpec], ec: &[VerbEntry], mut report: F) -> usize {
+    let mut count = 0;
+    for spec in local {
+        match ec.iter().find(|e| e.verb == spec.verb) {
+            None => {
+                count += 1;
+                report(VerbMismatch::Missing(*spec));
+            }
+            Some(entry) if entry.w_words != spec.w_words || entry.r_words != spec.r_words => {
+                count += 1;
+                report(VerbMismatch::Shape { local: *spec, ec: *entry });
+            }
+            _ => (),
+        }
+    }
+    for entry in ec {
+        if !local.iter().any(|spec| spec.verb == entry.verb) {
+            count += 1;
+            report(VerbMismatch::Unknown(*entry));
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seq_in_order_and_drops() {
+        let mut tx = LinkSeq::new(0xFFFE);
+        let mut rx = LinkSeq::new(0xFFFE);
+        assert_eq!(rx.accept(tx.tag()), SeqStatus::InOrder);
+        assert_eq!(rx.accept(tx.tag()), SeqStatus::InOrder);
+        // wraps through zero
+        assert_eq!(tx.expected(), 0);
+        tx.tag();
+        tx.tag();
+        assert_eq!(rx.accept(tx.tag()), SeqStatus::Dropped(2));
+        assert_eq!(rx.expected(), tx.expected());
+    }
+
+    #[test]
+    fn seq_stale() {
+        let mut rx = LinkSeq::new(10);
+        assert_eq!(rx.accept(8), SeqStatus::Stale(2));
+        assert_eq!(rx.expected(), 10);
+    }
+
+    #[test]
+    fn liveness() {
+        let mut live = Liveness::new();
+        assert_eq!(live.check(0x1234, &[!0x1234, 7]), LivenessStatus::Alive);
+        assert_eq!(live.check(0x1234, &[!0x1234, 8]), LivenessStatus::Alive);
+        assert_eq!(live.check(0x1234, &[!0x1234, 8]), LivenessStatus::Stalled);
+        assert_eq!(live.check(0x1234, &[0x1234, 9]), LivenessStatus::EchoMismatch);
+        assert_eq!(live.check(0x5555, &[!0x5555, 0]), LivenessStatus::Rebooted);
+        assert!(LivenessStatus::Rebooted.needs_sync());
+        assert_eq!(live.check(0x5555, &[!0x5555, 1]), LivenessStatus::Alive);
+    }
+
+    #[test]
+    fn verb_diff() {
+        let local = [ComState::LINK_PING, ComState::UPTIME, ComState::WLAN_ON];
+        let ec = [
+            VerbEntry::from(&ComState::LINK_PING),
+            VerbEntry { verb: ComState::UPTIME.verb, w_words: 0, r_words: 2 },
+            VerbEntry { verb: 0x4FFF, w_words: 0, r_words: 0 },
+        ];
+        let mut missing = 0;
+        let count = diff_verbs(&local, &ec, |m| {
+            if m == VerbMismatch::Missing(ComState::WLAN_ON) {
+                missing += 1;
+            }
+        });
+        assert_eq!(count, 3);
+        assert_eq!(missing, 1);
+        assert_eq!(diff_verbs(ComState::ALL, &[], |_| ()), ComState::ALL.len());
+    }
+
+    #[test]
+    fn event_ring_pages() {
+        use crate::InterruptFlags;
+        let mut ring = EventRing::<10>::new();
+        for i in 0..12u16 {
+            ring.push(ComEvent { source: InterruptFlags::WLAN_RX_READY, timestamp_ms: i as u32, arg: i });
+        }
+        assert_eq!(ring.dropped(), 2);
+        let page = EventPage::decode_u16(&ring.fetch_page().encode_u16());
+        assert_eq!(page.count as usize, EVENT_PAGE_LEN);
+        assert_eq!(page.pending, 2);
+        assert_eq!(page.valid()[0].arg, 0);
+        assert_eq!(page.valid()[0].source, InterruptFlags::WLAN_RX_READY);
+        let page = ring.fetch_page();
+        assert_eq!((page.count, page.pending), (2, 0));
+        assert_eq!(page.valid()[1].timestamp_ms, 9);
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn bringup_sequence() {
+        let mut bringup = LinkBringup::new(BringupTimeouts::default());
+        assert_eq!(bringup.handle(BringupEvent::Start, 0), BringupAction::Issue(ComState::LINK_SYNC));
+        assert_eq!(bringup.handle(BringupEvent::Synced, 10), BringupAction::Issue(ComState::LINK_PROTOCOL_VERSION));
+        assert_eq!(
+            bringup.handle(BringupEvent::Version(ProtocolVersion::CURRENT), 11),
+            BringupAction::Issue(ComState::LINK_SET_INTMASK32)
+        );
+        assert_eq!(bringup.handle(BringupEvent::IntmaskRestored, 12), BringupAction::Issue(ComState::WLAN_SYNC_STATE));
+        assert_eq!(bringup.handle(BringupEvent::WlanState(LinkState::Initializing), 13), BringupAction::Wait);
+        assert_eq!(bringup.handle(BringupEvent::Tick, 100), BringupAction::Issue(ComState::WLAN_SYNC_STATE));
+        assert_eq!(bringup.handle(BringupEvent::WlanState(LinkState::ResetHold), 101), BringupAction::Ready);
+        assert_eq!(bringup.stage(), BringupStage::Ready);
+    }
+
+    #[test]
+    fn bringup_old_ec_and_timeouts() {
+        let mut bringup = LinkBringup::new(BringupTimeouts::default());
+        bringup.handle(BringupEvent::Start, 0);
+        bringup.handle(BringupEvent::Synced, 0);
+        // no answer to LINK_PROTOCOL_VERSION: fall back to the 16-bit mask
+        assert_eq!(bringup.handle(BringupEvent::Tick, 500), BringupAction::Issue(ComState::LINK_SET_INTMASK));
+        assert_eq!(bringup.version(), ProtocolVersion::V1_0);
+        assert_eq!(bringup.handle(BringupEvent::Tick, 1000), BringupAction::Failed(BringupStage::RestoreIntmask));
+    }
+}
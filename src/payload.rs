@@ -0,0 +1,475 @@
+#![forbid(unsafe_code)]
+
+// Typed payload buffers.
+//
+// Every verb in ComState::ALL has a marker type of the same name in `crate::verbs`, whose
+// `Verb` impl fixes the request and response buffer types to `[u16; w_words]` and
+// `[u16; r_words]` of its spec. Buffers declared as `Request<V>` / `Response<V>` change size with
+// the spec, and anything that still assumes the old size (a literal `[u16; 14]`, a codec taking
+// the old array type) stops compiling, instead of desyncing the FIFO at runtime.
+//
+// Verbs with a serdes type on either side also implement `Command`, which ties the verb to its
+// request and response types; `ComClient::exchange::<C>` then takes a `C::Req` and returns a
+// `C::Resp`, so a payload of the wrong type does not compile.
+
+use crate::serdes::{
+    ApConfig, ArpOffloadConfig, ArpOffloadStatus, ApStationPage, ChannelList, CoalesceConfig, ConnDiagnostics,
+    ConnectStatus, CountryCode, CredMap, EapCommitStatus, EapConfig, EapFragment, EventPage, ImuWomConfig, Ipv4Conf,
+    KeepaliveStatus, KeepaliveTemplate, KnownNetwork, KnownNetworkInfo, LinkStats, LogEscalation, MacAddress, MacConfig,
+    McastFilterTable, MdnsService, MdnsStats, MonitorConfig, Mtu, MulticastGroup, P2pConfig, P2pStatus, PhyMode,
+    PhyStatus, PowerSaveConfig, ProtocolVersion, Rcpi, RoamConfig, Rssi, RssiFilterConfig, RssiHistory,
+    RssiHistoryConfig, ScanConfig, ScanResultPage, ScanSummary, SerdesError, StringSer, Subscription, TrafficStats,
+    TxPower, TxStatusPage, VersionedDecode, WakePatternEntry, WakePatternTable, WlanBinStatus, WlanErrCounts,
+    WpsPinStart, STR_32_WORDS, STR_64_WORDS,
+};
+use crate::verbs;
+use crate::ComSpec;
+
+/// A fixed-size word buffer
+pub trait WordArray: AsRef<[u16]> + AsMut<[u16]> + Copy {
+    const LEN: usize;
+    fn zeroed() -> Self;
+}
+impl<const N: usize> WordArray for [u16; N] {
+    const LEN: usize = N;
+    fn zeroed() -> Self {
+        [0; N]
+    }
+}
+
+/// A verb, as a type. Implemented by the marker types in `crate::verbs`.
+pub trait Verb {
+    const SPEC: ComSpec;
+    /// `[u16; SPEC.w_words]`
+    type Request: WordArray;
+    /// `[u16; SPEC.r_words]`
+    type Response: WordArray;
+}
+
+/// The payload buffer of verb V
+pub type Request<V> = <V as Verb>::Request;
+/// The response buffer of verb V
+pub type Response<V> = <V as Verb>::Response;
+
+/// A zeroed payload buffer for verb V
+pub fn request<V: Verb>() -> Request<V> {
+    WordArray::zeroed()
+}
+/// A zeroed response buffer for verb V
+pub fn response<V: Verb>() -> Response<V> {
+    WordArray::zeroed()
+}
+
+/// A verb with typed request and response
+pub trait Command: Verb {
+    type Req: ?Sized;
+    type Resp;
+    fn encode_req(req: &Self::Req) -> Result<Self::Request, SerdesError>;
+    fn decode_resp(words: &Self::Response) -> Result<Self::Resp, SerdesError>;
+}
+
+/// Commands with no payload and no response
+macro_rules! action {
+    ($($verb:ident),* $(,)?) => {$(
+        impl Command for verbs::$verb {
+            type Req = ();
+            type Resp = ();
+            fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+                Ok(request::<Self>())
+            }
+            fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+                Ok(())
+            }
+        }
+    )*};
+}
+/// Commands whose payload is one serdes struct, with no response
+macro_rules! setter {
+    ($($verb:ident: $req:ty),* $(,)?) => {$(
+        impl Command for verbs::$verb {
+            type Req = $req;
+            type Resp = ();
+            fn encode_req(req: &$req) -> Result<Self::Request, SerdesError> {
+                Ok(req.encode_u16())
+            }
+            fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+                Ok(())
+            }
+        }
+    )*};
+}
+/// Commands with no payload whose response is one serdes struct
+macro_rules! getter {
+    ($($verb:ident: $resp:ty),* $(,)?) => {$(
+        impl Command for verbs::$verb {
+            type Req = ();
+            type Resp = $resp;
+            fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+                Ok(request::<Self>())
+            }
+            fn decode_resp(words: &Self::Response) -> Result<$resp, SerdesError> {
+                Ok(<$resp>::decode_u16(words))
+            }
+        }
+    )*};
+}
+
+action!(WLAN_ON, WLAN_OFF, WLAN_JOIN, WLAN_LEAVE, WLAN_CLEAR_BSSID, WLAN_AP_STOP, WLAN_MONITOR_STOP, SSID_SCAN_ON, SSID_SCAN_OFF);
+setter!(
+    IMU_WOM_CONFIG: ImuWomConfig,
+    WLAN_CRED_MAP_SET: CredMap,
+    WLAN_P2P_START: P2pConfig,
+    LINK_SUBSCRIBE: Subscription,
+    LINK_SET_COALESCE: CoalesceConfig,
+    EC_LOG_ESCALATION_SET: LogEscalation,
+    WLAN_SCAN_CONFIG: ScanConfig,
+    WLAN_SET_BSSID: MacAddress,
+    WLAN_KNOWN_NET_STORE: KnownNetwork,
+    WLAN_ROAM_CONFIG: RoamConfig,
+    WLAN_EAP_CONFIG: EapConfig,
+    WLAN_EAP_FRAGMENT: EapFragment,
+    WLAN_WPS_PIN_START: WpsPinStart,
+    WLAN_AP_START: ApConfig,
+    WLAN_SET_COUNTRY: CountryCode,
+    WLAN_SET_TX_POWER: TxPower,
+    WLAN_SET_MAC: MacConfig,
+    WLAN_MCAST_ADD: MacAddress,
+    WLAN_MCAST_REMOVE: MacAddress,
+    WLAN_MONITOR_START: MonitorConfig,
+    WLAN_SET_POWER_SAVE: PowerSaveConfig,
+    WLAN_SET_PHY_MODE: PhyMode,
+    NET_SET_MTU: Mtu,
+    NET_IGMP_JOIN: MulticastGroup,
+    NET_IGMP_LEAVE: MulticastGroup,
+    NET_ARP_OFFLOAD_CONFIG: ArpOffloadConfig,
+    NET_WAKE_SET: WakePatternEntry,
+    NET_KEEPALIVE_SET: KeepaliveTemplate,
+    NET_MDNS_SET_SERVICE: MdnsService,
+    WLAN_SET_RSSI_HISTORY: RssiHistoryConfig,
+    WLAN_SET_RSSI_FILTER: RssiFilterConfig,
+);
+getter!(
+    LINK_PROTOCOL_VERSION: ProtocolVersion,
+    LINK_GET_STATS: LinkStats,
+    LINK_EVENT_FETCH: EventPage,
+    WLAN_CRED_MAP_GET: CredMap,
+    WLAN_P2P_STATUS: P2pStatus,
+    SSID_SCAN_RESULT_SUMMARY: ScanSummary,
+    NET_TX_STATUS: TxStatusPage,
+    WLAN_GET_MAC: MacConfig,
+    WLAN_MCAST_LIST: McastFilterTable,
+    NET_ARP_OFFLOAD_STATUS: ArpOffloadStatus,
+    NET_MDNS_STATS: MdnsStats,
+    NET_TRAFFIC_STATS: TrafficStats,
+    WLAN_GET_DIAGNOSTICS: ConnDiagnostics,
+    WLAN_GET_RSSI_HISTORY: RssiHistory,
+    WLAN_GET_ERRCOUNTS: WlanErrCounts,
+);
+
+impl Command for verbs::WLAN_GET_IPV4_CONF {
+    type Req = ();
+    type Resp = Ipv4Conf;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<Ipv4Conf, SerdesError> {
+        // untagged: the version 0 layout
+        Ipv4Conf::decode_version(0, words)
+    }
+}
+impl Command for verbs::WLAN_GET_IPV4_CONF_TAGGED {
+    type Req = ();
+    type Resp = Ipv4Conf;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<Ipv4Conf, SerdesError> {
+        Ipv4Conf::decode_versioned(words)
+    }
+}
+
+impl Command for verbs::LINK_PING {
+    type Req = u16;
+    /// the inverted argument and the EC's ping counter
+    type Resp = (u16, u16);
+    fn encode_req(req: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*req])
+    }
+    fn decode_resp(words: &Self::Response) -> Result<(u16, u16), SerdesError> {
+        Ok((words[0], words[1]))
+    }
+}
+impl Command for verbs::WLAN_BIN_STATUS {
+    type Req = ();
+    type Resp = WlanBinStatus;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<WlanBinStatus, SerdesError> {
+        // untagged: the version 0 layout
+        WlanBinStatus::decode_version(0, words)
+    }
+}
+impl Command for verbs::WLAN_BIN_STATUS_TAGGED {
+    type Req = ();
+    type Resp = WlanBinStatus;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<WlanBinStatus, SerdesError> {
+        WlanBinStatus::decode_versioned(words)
+    }
+}
+impl Command for verbs::SSID_SCAN_FETCH {
+    /// index of the first record wanted
+    type Req = u16;
+    type Resp = ScanResultPage;
+    fn encode_req(start: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*start])
+    }
+    fn decode_resp(words: &Self::Response) -> Result<ScanResultPage, SerdesError> {
+        ScanResultPage::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_KNOWN_NET_GET {
+    /// slot
+    type Req = u16;
+    type Resp = KnownNetworkInfo;
+    fn encode_req(slot: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*slot])
+    }
+    fn decode_resp(words: &Self::Response) -> Result<KnownNetworkInfo, SerdesError> {
+        KnownNetworkInfo::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_KNOWN_NET_DELETE {
+    /// slot, or KNOWN_NET_DELETE_ALL
+    type Req = u16;
+    type Resp = ();
+    fn encode_req(slot: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*slot])
+    }
+    fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+        Ok(())
+    }
+}
+impl Command for verbs::WLAN_WPS_PBC_START {
+    /// slot
+    type Req = u16;
+    type Resp = ();
+    fn encode_req(slot: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*slot])
+    }
+    fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+        Ok(())
+    }
+}
+impl Command for verbs::WLAN_AP_STATIONS {
+    /// index of the first station wanted
+    type Req = u16;
+    type Resp = ApStationPage;
+    fn encode_req(start: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*start])
+    }
+    fn decode_resp(words: &Self::Response) -> Result<ApStationPage, SerdesError> {
+        Ok(ApStationPage::decode_u16(words))
+    }
+}
+impl Command for verbs::WLAN_GET_COUNTRY {
+    type Req = ();
+    type Resp = CountryCode;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<CountryCode, SerdesError> {
+        CountryCode::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_GET_TX_POWER {
+    type Req = ();
+    type Resp = TxPower;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<TxPower, SerdesError> {
+        TxPower::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_GET_POWER_SAVE {
+    type Req = ();
+    type Resp = PowerSaveConfig;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<PowerSaveConfig, SerdesError> {
+        PowerSaveConfig::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_GET_PHY_STATUS {
+    type Req = ();
+    type Resp = PhyStatus;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<PhyStatus, SerdesError> {
+        PhyStatus::decode_u16(words)
+    }
+}
+impl Command for verbs::NET_GET_MTU {
+    type Req = ();
+    type Resp = Mtu;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<Mtu, SerdesError> {
+        Mtu::decode_u16(words)
+    }
+}
+impl Command for verbs::NET_WAKE_LIST {
+    type Req = ();
+    type Resp = WakePatternTable;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<WakePatternTable, SerdesError> {
+        WakePatternTable::decode_u16(words)
+    }
+}
+impl Command for verbs::NET_KEEPALIVE_STATUS {
+    type Req = u16;
+    type Resp = KeepaliveStatus;
+    fn encode_req(slot: &u16) -> Result<Self::Request, SerdesError> {
+        Ok([*slot])
+    }
+    fn decode_resp(words: &Self::Response) -> Result<KeepaliveStatus, SerdesError> {
+        KeepaliveStatus::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_GET_RSSI {
+    type Req = ();
+    type Resp = Rcpi;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<Rcpi, SerdesError> {
+        Rcpi::decode_u16(words[0])
+    }
+}
+impl Command for verbs::WLAN_GET_RSSI_AVG {
+    type Req = ();
+    type Resp = Rssi;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<Rssi, SerdesError> {
+        Ok(Rssi::decode_u16(words[0]))
+    }
+}
+impl Command for verbs::WLAN_GET_RSSI_FILTER {
+    type Req = ();
+    type Resp = RssiFilterConfig;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<RssiFilterConfig, SerdesError> {
+        RssiFilterConfig::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_GET_CONNECT_RESULT {
+    type Req = ();
+    type Resp = ConnectStatus;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<ConnectStatus, SerdesError> {
+        ConnectStatus::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_GET_CHANNELS {
+    type Req = ();
+    type Resp = ChannelList;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<ChannelList, SerdesError> {
+        ChannelList::decode_u16(words)
+    }
+}
+impl Command for verbs::WLAN_EAP_COMMIT {
+    type Req = ();
+    type Resp = EapCommitStatus;
+    fn encode_req(_: &()) -> Result<Self::Request, SerdesError> {
+        Ok(request::<Self>())
+    }
+    fn decode_resp(words: &Self::Response) -> Result<EapCommitStatus, SerdesError> {
+        EapCommitStatus::decode_u16(words[0])
+    }
+}
+impl Command for verbs::WLAN_SET_SSID {
+    type Req = str;
+    type Resp = ();
+    fn encode_req(ssid: &str) -> Result<Self::Request, SerdesError> {
+        Ok(*StringSer::<STR_32_WORDS>::new().encode(ssid)?)
+    }
+    fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+        Ok(())
+    }
+}
+impl Command for verbs::WLAN_SET_PASS {
+    type Req = str;
+    type Resp = ();
+    fn encode_req(pass: &str) -> Result<Self::Request, SerdesError> {
+        Ok(*StringSer::<STR_64_WORDS>::new().encode(pass)?)
+    }
+    fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+        Ok(())
+    }
+}
+impl Command for verbs::NET_MDNS_SET_HOSTNAME {
+    type Req = str;
+    type Resp = ();
+    fn encode_req(hostname: &str) -> Result<Self::Request, SerdesError> {
+        Ok(*StringSer::<STR_64_WORDS>::new().encode(hostname)?)
+    }
+    fn decode_resp(_: &Self::Response) -> Result<(), SerdesError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serdes::Ipv4Conf;
+    use crate::verbs::{FLASH_ERASE, WLAN_GET_IPV4_CONF};
+    use crate::ComState;
+
+    #[test]
+    fn buffers_follow_the_spec() {
+        let rx: Response<WLAN_GET_IPV4_CONF> = response::<WLAN_GET_IPV4_CONF>();
+        // the codec takes exactly the response buffer type
+        let _ = Ipv4Conf::decode_u16(&rx);
+        assert_eq!(<Response<WLAN_GET_IPV4_CONF>>::LEN, ComState::WLAN_GET_IPV4_CONF.r_words as usize);
+        assert_eq!(request::<FLASH_ERASE>().len(), 4);
+        assert_eq!(<Request<WLAN_GET_IPV4_CONF>>::LEN, 0);
+        assert_eq!(FLASH_ERASE::SPEC, ComState::FLASH_ERASE);
+    }
+
+    #[test]
+    fn commands_encode_typed_payloads() {
+        let words = <verbs::WLAN_SET_SSID as Command>::encode_req("precursor").unwrap();
+        assert_eq!(words[0], 9);
+        assert_eq!(
+            <verbs::WLAN_SET_SSID as Command>::encode_req("0123456789abcdef0123456789abcdef!"),
+            Err(SerdesError::StrLenTooBig)
+        );
+        assert_eq!(<verbs::LINK_PING as Command>::decode_resp(&[0xFFFE, 3]), Ok((0xFFFE, 3)));
+    }
+
+    #[test]
+    fn ipv4_conf_with_and_without_format_word() {
+        let conf = Ipv4Conf { addr: [10, 0, 0, 2], ..Default::default() };
+        let untagged = <verbs::WLAN_GET_IPV4_CONF as Command>::decode_resp(&conf.encode_u16()).unwrap();
+        let tagged = <verbs::WLAN_GET_IPV4_CONF_TAGGED as Command>::decode_resp(&conf.encode_versioned()).unwrap();
+        assert_eq!((untagged.addr, tagged.addr), ([10, 0, 0, 2], [10, 0, 0, 2]));
+    }
+}
@@ -0,0 +1,129 @@
+#![forbid(unsafe_code)]
+
+// Host-side view of the battery and charger state, assembled from GAS_GAUGE, GG_SOC and
+// POWER_CHARGER_STATE.
+//
+// The status bar polls these verbs periodically. Most polls return readings that differ only by
+// measurement noise, so `PowerSnapshot::changes_since` reports which fields changed by more than
+// a deadband; the UI redraws only when the result is non-empty.
+
+use crate::ComState;
+
+/// voltage changes smaller than this are treated as noise
+pub const VOLTAGE_DEADBAND_MV: u16 = 20;
+/// current changes smaller than this are treated as noise
+pub const CURRENT_DEADBAND_MA: u16 = 10;
+
+/// Set of PowerSnapshot fields that changed between two polls
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PowerChanges(u16);
+impl PowerChanges {
+    pub const VOLTAGE: PowerChanges = PowerChanges(0x0001);
+    pub const CURRENT: PowerChanges = PowerChanges(0x0002);
+    pub const SOC: PowerChanges = PowerChanges(0x0004);
+    pub const CHARGER: PowerChanges = PowerChanges(0x0008);
+    /// the current changed sign: the battery went from charging to discharging, or back
+    pub const DIRECTION: PowerChanges = PowerChanges(0x0010);
+
+    pub const fn empty() -> Self {
+        PowerChanges(0)
+    }
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// True if every field in `other` changed
+    pub const fn contains(&self, other: PowerChanges) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub fn insert(&mut self, other: PowerChanges) {
+        self.0 |= other.0;
+    }
+}
+impl core::ops::BitOr for PowerChanges {
+    type Output = PowerChanges;
+    fn bitor(self, rhs: PowerChanges) -> PowerChanges {
+        PowerChanges(self.0 | rhs.0)
+    }
+}
+
+/// Battery and charger readings from one poll
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct PowerSnapshot {
+    /// battery voltage in mV
+    pub voltage_mv: u16,
+    /// average battery current in mA, positive while charging
+    pub current_ma: i16,
+    /// state of charge in percent, as returned by GG_SOC
+    pub soc_percent: u8,
+    /// the POWER_CHARGER_STATE word
+    pub charger_state: u16,
+}
+impl PowerSnapshot {
+    /// Assemble a snapshot from the responses of GAS_GAUGE, GG_SOC and POWER_CHARGER_STATE.
+    /// GAS_GAUGE returns average current, standby current, voltage and power; only the average
+    /// current and the voltage are kept.
+    pub fn from_words(
+        gas_gauge: &[u16; ComState::GAS_GAUGE.r_words as usize],
+        soc: u16,
+        charger_state: u16,
+    ) -> Self {
+        PowerSnapshot {
+            voltage_mv: gas_gauge[2],
+            current_ma: gas_gauge[0] as i16,
+            soc_percent: soc.min(100) as u8,
+            charger_state,
+        }
+    }
+
+    /// Fields that changed meaningfully since `other`, the previously displayed snapshot.
+    /// Voltage and current must move by more than their deadbands; everything else compares exactly.
+    pub fn changes_since(&self, other: &PowerSnapshot) -> PowerChanges {
+        let mut changes = PowerChanges::empty();
+        if self.voltage_mv.abs_diff(other.voltage_mv) > VOLTAGE_DEADBAND_MV {
+            changes.insert(PowerChanges::VOLTAGE);
+        }
+        if self.current_ma.abs_diff(other.current_ma) > CURRENT_DEADBAND_MA {
+            changes.insert(PowerChanges::CURRENT);
+        }
+        if (self.current_ma > 0) != (other.current_ma > 0) {
+            changes.insert(PowerChanges::DIRECTION);
+        }
+        if self.soc_percent != other.soc_percent {
+            changes.insert(PowerChanges::SOC);
+        }
+        if self.charger_state != other.charger_state {
+            changes.insert(PowerChanges::CHARGER);
+        }
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn noise_is_not_a_change() {
+        let before = PowerSnapshot::from_words(&[(-150i16) as u16, 0, 3900, 0], 80, 0);
+        let mut after = before;
+        after.voltage_mv += VOLTAGE_DEADBAND_MV;
+        after.current_ma -= CURRENT_DEADBAND_MA as i16;
+        assert!(after.changes_since(&before).is_empty());
+
+        after.voltage_mv += 1;
+        after.soc_percent = 79;
+        assert_eq!(after.changes_since(&before), PowerChanges::VOLTAGE | PowerChanges::SOC);
+    }
+
+    #[test]
+    fn plugging_in_a_charger() {
+        let before = PowerSnapshot { voltage_mv: 3900, current_ma: -150, soc_percent: 80, charger_state: 0 };
+        let after = PowerSnapshot { current_ma: 400, charger_state: 1, ..before };
+        let changes = after.changes_since(&before);
+        assert!(changes.contains(PowerChanges::CURRENT | PowerChanges::DIRECTION | PowerChanges::CHARGER));
+        assert!(!changes.contains(PowerChanges::SOC));
+    }
+}
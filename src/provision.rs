@@ -0,0 +1,318 @@
+#![forbid(unsafe_code)]
+
+// Capture and replay of the EC provisioning sequence.
+//
+// The EC forgets its configuration whenever it reboots (a new "epoch", e.g. after an EC update).
+// The SoC records the configuration verbs it sends into a `Provisioner` script, persists the
+// script's words through a `ProvisionStore`, and after an epoch change replays the script to
+// restore the EC to the same state with one call.
+//
+// Script format, as a flat [u16]: a sequence of records, each of which is
+// - word 0: verb
+// - word 1: number of payload words that follow (always the verb's w_words)
+// - words 2..: payload
+//
+// Verbs that undo configuration (see UNDO_VERBS) are not stored: recording one removes the records it
+// undoes, so the script never replays them and no longer holds their payload (e.g. a deleted passphrase).
+
+use crate::serdes::KNOWN_NET_DELETE_ALL;
+use crate::ComSpec;
+use crate::ComState;
+
+/// Verbs that may be recorded into a provisioning script, in no particular order.
+/// Replay preserves the order in which they were recorded, not this order.
+pub const PROVISIONING_VERBS: &[ComSpec] = &[
+    ComState::LINK_SET_INTMASK,
+    ComState::LINK_SET_INTMASK32,
+    ComState::LINK_SET_CRC_MODE,
+    ComState::EC_LOG_ESCALATION_SET,
+    ComState::LINK_EVENT_QUEUE_ENABLE,
+    ComState::LINK_SUBSCRIBE,
+    ComState::LINK_SET_COALESCE,
+    ComState::WFX_PDS_LINE_SET,
+    ComState::WLAN_SET_SSID,
+    ComState::WLAN_SET_PASS,
+    ComState::WLAN_SET_BSSID,
+    ComState::WLAN_CRED_MAP_SET,
+    ComState::WLAN_KNOWN_NET_STORE,
+    ComState::IMU_WOM_CONFIG,
+    ComState::NET_FRAME_CONFIG,
+    ComState::NET_SET_MTU,
+    ComState::WLAN_SCAN_CONFIG,
+    ComState::WLAN_ROAM_CONFIG,
+    ComState::WLAN_SET_COUNTRY,
+    ComState::WLAN_SET_TX_POWER,
+    ComState::WLAN_SET_POWER_SAVE,
+    ComState::WLAN_SET_PHY_MODE,
+    ComState::WLAN_SET_MAC,
+    ComState::WLAN_MCAST_ADD,
+    ComState::WLAN_MCAST_REMOVE,
+    ComState::WLAN_SET_RSSI_HISTORY,
+    ComState::WLAN_SET_RSSI_FILTER,
+    ComState::NET_MDNS_SET_HOSTNAME,
+    ComState::NET_MDNS_SET_SERVICE,
+    ComState::NET_IGMP_JOIN,
+    ComState::NET_IGMP_LEAVE,
+    ComState::NET_ARP_OFFLOAD_CONFIG,
+    ComState::NET_WAKE_SET,
+];
+
+/// An undo verb, the provisioning verb it undoes, and whether a record of that verb (by payload) is
+/// undone by the undo verb's payload
+type UndoRule = (ComSpec, ComSpec, fn(&[u16], &[u16]) -> bool);
+
+/// Verbs that remove earlier records from a provisioning script instead of being recorded
+pub const UNDO_VERBS: &[UndoRule] = &[
+    // by slot, or every slot
+    (ComState::WLAN_KNOWN_NET_DELETE, ComState::WLAN_KNOWN_NET_STORE, |undo, stored| {
+        undo[0] == KNOWN_NET_DELETE_ALL || undo[0] == stored[0]
+    }),
+    (ComState::WLAN_CLEAR_BSSID, ComState::WLAN_SET_BSSID, |_, _| true),
+];
+
+/// Errors from building or loading a provisioning script
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ProvisionError {
+    /// the script buffer has no room for the record
+    Full,
+    /// the verb is not in PROVISIONING_VERBS
+    NotProvisioning(u16),
+    /// the payload length does not match the verb's w_words
+    LengthMismatch { verb: u16, expected: u16, actual: u16 },
+    /// a stored script ends in the middle of a record
+    Truncated,
+}
+
+fn provisioning_spec(verb: u16) -> Option<&'static ComSpec> {
+    PROVISIONING_VERBS.iter().find(|spec| spec.verb == verb)
+}
+
+/// Persistent storage for a provisioning script, implemented by the host (e.g. on top of the PDDB)
+pub trait ProvisionStore {
+    type Error;
+    /// Replace the stored script with `script`
+    fn store(&mut self, script: &[u16]) -> Result<(), Self::Error>;
+    /// Copy the stored script into `script`, returning the number of words copied
+    fn load(&mut self, script: &mut [u16]) -> Result<usize, Self::Error>;
+}
+
+/// A replayable provisioning script of at most N words.
+#[derive(Clone)]
+pub struct Provisioner<const N: usize> {
+    buf: [u16; N],
+    len: usize,
+}
+impl<const N: usize> Provisioner<N> {
+    pub fn new() -> Self {
+        Provisioner { buf: [0; N], len: 0 }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Append one configuration verb and its payload to the script. An undo verb (see UNDO_VERBS)
+    /// instead removes the records it undoes.
+    pub fn record(&mut self, spec: &ComSpec, payload: &[u16]) -> Result<(), ProvisionError> {
+        let undo = UNDO_VERBS.iter().find(|(undo, _, _)| undo.verb == spec.verb);
+        if provisioning_spec(spec.verb).is_none() && undo.is_none() {
+            return Err(ProvisionError::NotProvisioning(spec.verb));
+        }
+        if payload.len() != spec.w_words as usize {
+            return Err(ProvisionError::LengthMismatch {
+                verb: spec.verb,
+                expected: spec.w_words,
+                actual: payload.len() as u16,
+            });
+        }
+        if let Some((_, undone, undoes)) = undo {
+            self.remove_records(undone.verb, |stored| undoes(payload, stored));
+            return Ok(());
+        }
+        let end = self.len + 2 + payload.len();
+        if end > N {
+            return Err(ProvisionError::Full);
+        }
+        self.buf[self.len] = spec.verb;
+        self.buf[self.len + 1] = spec.w_words;
+        self.buf[self.len + 2..end].copy_from_slice(payload);
+        self.len = end;
+        Ok(())
+    }
+
+    /// Drop every record of `verb` whose payload matches, zeroing the freed words
+    fn remove_records<F: Fn(&[u16]) -> bool>(&mut self, verb: u16, matches: F) {
+        let mut read = 0;
+        let mut write = 0;
+        while read < self.len {
+            let end = read + 2 + self.buf[read + 1] as usize;
+            if self.buf[read] != verb || !matches(&self.buf[read + 2..end]) {
+                self.buf.copy_within(read..end, write);
+                write += end - read;
+            }
+            read = end;
+        }
+        self.buf[write..self.len].fill(0);
+        self.len = write;
+    }
+
+    /// The script as words, ready to be persisted
+    pub fn as_words(&self) -> &[u16] {
+        &self.buf[..self.len]
+    }
+
+    /// Rebuild a script from persisted words, validating every record.
+    pub fn from_words(words: &[u16]) -> Result<Self, ProvisionError> {
+        for step in (ScriptIter { words }) {
+            step?;
+        }
+        if words.len() > N {
+            return Err(ProvisionError::Full);
+        }
+        let mut script = Self::new();
+        script.buf[..words.len()].copy_from_slice(words);
+        script.len = words.len();
+        Ok(script)
+    }
+
+    /// Iterate over the recorded (verb, payload) steps in order
+    pub fn steps(&self) -> impl Iterator<Item = (u16, &[u16])> {
+        // records were validated on the way in, so errors cannot occur
+        ScriptIter { words: self.as_words() }.filter_map(|step| step.ok())
+    }
+
+    /// Issue every recorded step through `issue`, stopping at the first error.
+    pub fn replay<E, F: FnMut(u16, &[u16]) -> Result<(), E>>(&self, mut issue: F) -> Result<(), E> {
+        for (verb, payload) in self.steps() {
+            issue(verb, payload)?;
+        }
+        Ok(())
+    }
+
+    pub fn save<S: ProvisionStore>(&self, store: &mut S) -> Result<(), S::Error> {
+        store.store(self.as_words())
+    }
+}
+impl<const N: usize> Default for Provisioner<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct ScriptIter<'a> {
+    words: &'a [u16],
+}
+impl<'a> Iterator for ScriptIter<'a> {
+    type Item = Result<(u16, &'a [u16]), ProvisionError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.words.is_empty() {
+            return None;
+        }
+        if self.words.len() < 2 {
+            self.words = &[];
+            return Some(Err(ProvisionError::Truncated));
+        }
+        let verb = self.words[0];
+        let len = self.words[1] as usize;
+        let spec = match provisioning_spec(verb) {
+            Some(spec) => spec,
+            None => {
+                self.words = &[];
+                return Some(Err(ProvisionError::NotProvisioning(verb)));
+            }
+        };
+        if len != spec.w_words as usize {
+            self.words = &[];
+            return Some(Err(ProvisionError::LengthMismatch {
+                verb,
+                expected: spec.w_words,
+                actual: len as u16,
+            }));
+        }
+        if self.words.len() < 2 + len {
+            self.words = &[];
+            return Some(Err(ProvisionError::Truncated));
+        }
+        let payload = &self.words[2..2 + len];
+        self.words = &self.words[2 + len..];
+        Some(Ok((verb, payload)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serdes::{StringSer, STR_64_WORDS};
+    use core::fmt::Write;
+
+    #[test]
+    fn record_and_replay() {
+        let mut script = Provisioner::<64>::new();
+        script.record(&ComState::LINK_SET_INTMASK, &[0x00FF]).unwrap();
+        script.record(&ComState::WLAN_SET_SSID, &[0; 17]).unwrap();
+        let mut hostname = StringSer::<STR_64_WORDS>::new();
+        write!(hostname, "precursor").unwrap();
+        script.record(&ComState::NET_MDNS_SET_HOSTNAME, hostname.as_u16_slice()).unwrap();
+        assert_eq!(
+            script.record(&ComState::FLASH_ERASE, &[0; 4]),
+            Err(ProvisionError::NotProvisioning(ComState::FLASH_ERASE.verb))
+        );
+        assert_eq!(script.record(&ComState::WLAN_SET_PASS, &[0; 33]), Err(ProvisionError::Full));
+
+        let restored = Provisioner::<64>::from_words(script.as_words()).unwrap();
+        let mut verbs = [0u16; 3];
+        let mut count = 0;
+        restored
+            .replay(|verb, payload| -> Result<(), ()> {
+                if verb == ComState::NET_MDNS_SET_HOSTNAME.verb {
+                    assert_eq!(payload, hostname.as_u16_slice());
+                }
+                verbs[count] = verb;
+                count += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            verbs,
+            [ComState::LINK_SET_INTMASK.verb, ComState::WLAN_SET_SSID.verb, ComState::NET_MDNS_SET_HOSTNAME.verb]
+        );
+    }
+
+    #[test]
+    fn deleted_known_network_is_not_replayed() {
+        let mut script = Provisioner::<128>::new();
+        let mut network = [0u16; ComState::WLAN_KNOWN_NET_STORE.w_words as usize];
+        network[19..].fill(0x6161); // passphrase
+        for slot in 0..2 {
+            network[0] = slot;
+            script.record(&ComState::WLAN_KNOWN_NET_STORE, &network).unwrap();
+        }
+        script.record(&ComState::WLAN_KNOWN_NET_DELETE, &[0]).unwrap();
+        let store = ComState::WLAN_KNOWN_NET_STORE.verb;
+        assert!(script.steps().map(|(verb, payload)| (verb, payload[0])).eq([(store, 1)]));
+
+        script.record(&ComState::WLAN_KNOWN_NET_DELETE, &[KNOWN_NET_DELETE_ALL]).unwrap();
+        assert!(script.is_empty());
+        assert!(script.buf.iter().all(|word| *word == 0));
+        assert!(script.replay(|_, _| -> Result<(), ()> { Err(()) }).is_ok());
+    }
+
+    #[test]
+    fn cleared_bssid_pin_is_not_replayed() {
+        let mut script = Provisioner::<32>::new();
+        script.record(&ComState::WLAN_SET_BSSID, &[0x1102, 0x3322, 0x5544]).unwrap();
+        script.record(&ComState::WLAN_SET_SSID, &[0; 17]).unwrap();
+        script.record(&ComState::WLAN_CLEAR_BSSID, &[]).unwrap();
+        assert!(script.steps().map(|(verb, _)| verb).eq([ComState::WLAN_SET_SSID.verb]));
+    }
+
+    #[test]
+    fn truncated_script_is_rejected() {
+        let words = [ComState::LINK_SET_INTMASK.verb, 1];
+        assert!(matches!(Provisioner::<8>::from_words(&words), Err(ProvisionError::Truncated)));
+    }
+}
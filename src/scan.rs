@@ -0,0 +1,132 @@
+#![forbid(unsafe_code)]
+
+// Scan result fetching.
+//
+// The EC keeps the APs of the current scan in a list, appending to it while the scan runs and
+// raising INT_WLAN_SSID_UPDATE when it does, then INT_WLAN_SSID_FINISHED when the scan ends.
+// SSID_SCAN_FETCH reads the list a page at a time from any index, so the SoC can start fetching
+// before the scan is over and pick up where it left off on the next interrupt. Each page says
+// whether more records can be fetched right away (SCAN_PAGE_FLAG_MORE), whether the scan is
+// still running (SCAN_PAGE_FLAG_IN_PROGRESS), and which scan the list belongs to: when a new scan
+// replaces the list, the scan id changes and the SoC must start again from index 0.
+//
+// `ScanFetch` keeps the SoC's side of this: feed it the interrupts and the fetched pages, and
+// it says which index to fetch next, if any.
+
+use crate::serdes::{ScanResultPage, SCAN_PAGE_FLAG_IN_PROGRESS, SCAN_PAGE_FLAG_MORE};
+use crate::InterruptFlags;
+
+/// Host-side state of an incremental scan fetch
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+pub struct ScanFetch {
+    /// scan id of the list fetched so far, None before the first page
+    scan_id: Option<u16>,
+    /// index of the next record to fetch
+    next: u16,
+    /// the last page had SCAN_PAGE_FLAG_MORE
+    more: bool,
+    /// the last page had SCAN_PAGE_FLAG_IN_PROGRESS
+    in_progress: bool,
+    /// the EC reported new records since the last fetch was started
+    updated: bool,
+}
+impl ScanFetch {
+    pub const fn new() -> Self {
+        ScanFetch { scan_id: None, next: 0, more: false, in_progress: false, updated: false }
+    }
+    /// Forget the fetched list, e.g. after SSID_SCAN_ON
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+    /// Number of records fetched from the current list
+    pub fn fetched(&self) -> u16 {
+        self.next
+    }
+    /// Account for the interrupts of one GET_INTERRUPT
+    pub fn on_interrupt(&mut self, flags: InterruptFlags) {
+        if flags.contains(InterruptFlags::WLAN_SSID_UPDATE) || flags.contains(InterruptFlags::WLAN_SSID_FINISHED) {
+            self.updated = true;
+        }
+    }
+    /// The index to send with SSID_SCAN_FETCH, or None if there is nothing new to fetch.
+    /// Interrupts that arrive after this call count towards the next fetch.
+    pub fn begin_fetch(&mut self) -> Option<u16> {
+        if self.scan_id.is_some() && !self.more && !self.updated {
+            return None;
+        }
+        self.updated = false;
+        Some(self.next)
+    }
+    /// Account for the page returned by the fetch started with begin_fetch. Returns true if every
+    /// record of the page is new to the host; false if a new scan has replaced the list, in which
+    /// case the page is to be dropped along with every record fetched before it, and the next
+    /// fetch starts again from index 0.
+    pub fn on_page(&mut self, page: &ScanResultPage) -> bool {
+        self.more = page.flags.contains(SCAN_PAGE_FLAG_MORE);
+        self.in_progress = page.flags.contains(SCAN_PAGE_FLAG_IN_PROGRESS);
+        match self.scan_id {
+            Some(id) if id != page.scan_id && self.next != 0 => {
+                self.scan_id = Some(page.scan_id);
+                self.next = 0;
+                self.updated = true;
+                false
+            }
+            _ => {
+                self.scan_id = Some(page.scan_id);
+                self.next += page.results().len() as u16;
+                true
+            }
+        }
+    }
+    /// True once the scan has ended and every record of it has been fetched
+    pub fn is_complete(&self) -> bool {
+        self.scan_id.is_some() && !self.more && !self.in_progress && !self.updated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serdes::{Rssi, ScanResult, Security};
+
+    fn page(results: &[ScanResult], start: u16, scan_id: u16, in_progress: bool) -> ScanResultPage {
+        let mut page = ScanResultPage::from_results(results, start);
+        page.scan_id = scan_id;
+        page.flags.set(SCAN_PAGE_FLAG_IN_PROGRESS, in_progress);
+        page
+    }
+
+    #[test]
+    fn fetch_follows_a_running_scan() {
+        let results = [ScanResult::new(b"ap", [0; 6], Rssi::from_dbm(-40), 1, Security::Open); 6];
+        let mut fetch = ScanFetch::new();
+        assert_eq!(fetch.begin_fetch(), Some(0));
+        assert!(fetch.on_page(&page(&results[..5], 0, 7, true)));
+        // more records are already waiting
+        assert_eq!(fetch.begin_fetch(), Some(4));
+        assert!(fetch.on_page(&page(&results[..5], 4, 7, true)));
+        assert_eq!(fetch.begin_fetch(), None);
+        assert!(!fetch.is_complete());
+
+        // the EC appended a record and finished
+        fetch.on_interrupt(InterruptFlags::WLAN_SSID_UPDATE | InterruptFlags::WLAN_SSID_FINISHED);
+        assert_eq!(fetch.begin_fetch(), Some(5));
+        assert!(fetch.on_page(&page(&results, 5, 7, false)));
+        assert_eq!(fetch.fetched(), 6);
+        assert!(fetch.is_complete());
+    }
+
+    #[test]
+    fn new_scan_restarts_the_fetch() {
+        let results = [ScanResult::EMPTY; 6];
+        let mut fetch = ScanFetch::new();
+        fetch.begin_fetch();
+        fetch.on_page(&page(&results, 0, 1, true));
+        fetch.on_interrupt(InterruptFlags::WLAN_SSID_UPDATE);
+        assert_eq!(fetch.begin_fetch(), Some(4));
+        assert!(!fetch.on_page(&page(&results, 4, 2, true)));
+        assert_eq!(fetch.begin_fetch(), Some(0));
+        assert!(fetch.on_page(&page(&results, 0, 2, true)));
+        assert_eq!(fetch.fetched(), 4);
+    }
+}
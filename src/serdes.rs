@@ -1,6 +1,7 @@
 #![forbid(unsafe_code)]
 
-use crate::{ComState, DhcpState};
+use crate::frame;
+use crate::{ComSpec, ComState, ConnectDetail, ConnectResult, DhcpState, InterruptFlags, LinkState, NET_FRAME_MAX_LEN};
 
 // These constants help with sending and receiving utf-8 string slices serialized as [u16]
 // across the COM bus for COM verbs that take string arguments.
@@ -13,10 +14,94 @@ pub const STR_32_U8_SIZE: usize = 2 * (STR_32_WORDS - 1);
 pub const STR_64_U8_SIZE: usize = 2 * (STR_64_WORDS - 1);
 
 /// Error codes related to COM bus protocol serialization/deserialization
-#[derive(Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u8)]
 pub enum SerdesError {
     StrLenTooBig = 1,
     Utf8Decode = 2,
+    /// a buffer holds fewer words than the encoding needs
+    BufferTooShort { expected: usize, actual: usize } = 3,
+    /// a byte buffer is longer than its BytesSer/BytesDes capacity
+    BytesLenTooBig = 4,
+    /// the checksum word of a crc::WireChecksum payload does not match its contents
+    ChecksumMismatch { expected: u16, actual: u16 } = 5,
+    /// a word in an enum field is none of the enum's values
+    InvalidEnumValue { field: &'static str, value: u16 } = 6,
+    /// a word in a numeric field is outside the field's range
+    OutOfRange { field: &'static str, value: u16 } = 7,
+}
+impl core::fmt::Display for SerdesError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SerdesError::StrLenTooBig => write!(f, "string too long for its buffer"),
+            SerdesError::Utf8Decode => write!(f, "string is not valid utf-8"),
+            SerdesError::BufferTooShort { expected, actual } => {
+                write!(f, "buffer too short: need {} words, have {}", expected, actual)
+            }
+            SerdesError::BytesLenTooBig => write!(f, "byte string too long for its buffer"),
+            SerdesError::ChecksumMismatch { expected, actual } => {
+                write!(f, "checksum mismatch: computed {:04x}, received {:04x}", expected, actual)
+            }
+            SerdesError::InvalidEnumValue { field, value } => write!(f, "invalid value {:#x} for {}", value, field),
+            SerdesError::OutOfRange { field, value } => write!(f, "value {:#x} out of range for {}", value, field),
+        }
+    }
+}
+
+/// A fieldless enum whose lenient `decode_u16` maps the values it does not know to a catch-all
+/// variant. Decoders that return a Result read these through `known_enum_value` instead.
+pub(crate) trait WireEnum: Copy {
+    fn decode_lenient(value: u16) -> Self;
+    fn wire_value(self) -> u16;
+}
+macro_rules! wire_enum {
+    ($($ty:ty),* $(,)?) => {$(
+        impl WireEnum for $ty {
+            fn decode_lenient(value: u16) -> Self {
+                <$ty>::decode_u16(value)
+            }
+            fn wire_value(self) -> u16 {
+                self as u16
+            }
+        }
+    )*};
+}
+wire_enum!(LinkState, DhcpState, ConnectResult, ConnectDetail, LogSeverity, AuditResult, Security);
+
+/// `value` of `field` as a T, or InvalidEnumValue if it is none of T's values: that is, if the
+/// lenient decode_u16 of T only maps it to its catch-all variant
+pub(crate) fn known_enum_value<T: WireEnum>(field: &'static str, value: u16) -> Result<T, SerdesError> {
+    let decoded = T::decode_lenient(value);
+    if decoded.wire_value() == value {
+        Ok(decoded)
+    } else {
+        Err(SerdesError::InvalidEnumValue { field, value })
+    }
+}
+
+/// `words[..len]`, or BufferTooShort
+pub(crate) fn need(words: &[u16], len: usize) -> Result<&[u16], SerdesError> {
+    words.get(..len).ok_or(SerdesError::BufferTooShort { expected: len, actual: words.len() })
+}
+
+// Values wider than a word cross the bus least significant word first. Every codec in this
+// crate uses these helpers, so there is exactly one word order on the wire.
+
+/// Split a u32 into words, low word first
+pub const fn u32_to_words(value: u32) -> [u16; 2] {
+    [value as u16, (value >> 16) as u16]
+}
+/// Join words from `u32_to_words`
+pub const fn words_to_u32(words: [u16; 2]) -> u32 {
+    words[0] as u32 | (words[1] as u32) << 16
+}
+/// Split a u64 into words, lowest word first
+pub const fn u64_to_words(value: u64) -> [u16; 4] {
+    [value as u16, (value >> 16) as u16, (value >> 32) as u16, (value >> 48) as u16]
+}
+/// Join words from `u64_to_words`
+pub const fn words_to_u64(words: [u16; 4]) -> u64 {
+    words[0] as u64 | (words[1] as u64) << 16 | (words[2] as u64) << 32 | (words[3] as u64) << 48
 }
 
 /// Serialized (convertable to &[u16]) string of max-length 2*(U16_LEN-1) bytes.
@@ -66,6 +151,47 @@ impl<const U16_LEN: usize> StringSer<U16_LEN> {
     pub fn as_u16_slice(&self) -> &[u16; U16_LEN] {
         &self.u16_buf
     }
+
+    /// Empty the string, e.g. before building it up again with `write!`
+    pub fn clear(&mut self) {
+        self.u16_buf = [0; U16_LEN];
+    }
+    /// Length of the serialized string in bytes
+    pub fn len(&self) -> usize {
+        self.u16_buf.first().map_or(0, |&len| len as usize)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+impl<const U16_LEN: usize> Default for StringSer<U16_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Appends to the serialized string, so status messages can be formatted with `write!` straight
+/// into the buffer that goes on the bus. A write_str that does not fit fails with fmt::Error and
+/// writes nothing, so the string stays valid utf-8; a failed `write!` keeps the pieces it
+/// formatted before the overflow.
+impl<const U16_LEN: usize> core::fmt::Write for StringSer<U16_LEN> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let start = self.len();
+        let end = start + s.len();
+        if end > 2 * U16_LEN.saturating_sub(1) {
+            return Err(core::fmt::Error);
+        }
+        let (length, data) = self.u16_buf.split_first_mut().ok_or(core::fmt::Error)?;
+        for (i, &b) in (start..end).zip(s.as_bytes()) {
+            let word = &mut data[i / 2];
+            if i % 2 == 0 {
+                *word = b as u16;
+            } else {
+                *word = (*word & 0x00FF) | (b as u16) << 8;
+            }
+        }
+        *length = end as u16;
+        Ok(())
+    }
 }
 
 /// Deserialized (convertable to &str) COM protocol string of max-length 32 bytes
@@ -117,6 +243,155 @@ impl<const U16_LEN: usize, const U8_LEN: usize> StringDes<U16_LEN, U8_LEN> {
             _ => Err(SerdesError::Utf8Decode),
         }
     }
+
+    /// Best-effort decode for diagnostics: each invalid utf-8 sequence becomes U+FFFD instead of
+    /// failing the whole string. Returns the string and the number of substitutions; a length word
+    /// beyond the buffer also counts as one, and is clamped. Substitutions are longer than the
+    /// bytes they replace, so the string is truncated if they do not all fit in U8_LEN bytes.
+    pub fn decode_u16_lossy(&mut self, u16_buf: &[u16; U16_LEN]) -> (&str, usize) {
+        let mut raw = [0u8; U8_LEN];
+        for (pair, word) in raw.chunks_mut(2).zip(u16_buf.iter().skip(1)) {
+            pair.copy_from_slice(&word.to_le_bytes()[..pair.len()]);
+        }
+        let max_len = U8_LEN.min(2 * U16_LEN.saturating_sub(1));
+        let len = u16_buf.first().map_or(0, |&len| len as usize);
+        let mut errors = (len > max_len) as usize;
+
+        let mut src = &raw[..len.min(max_len)];
+        self.len = 0;
+        loop {
+            match core::str::from_utf8(src) {
+                Ok(valid) => {
+                    self.push_lossy(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, rest) = src.split_at(e.valid_up_to());
+                    self.push_lossy(core::str::from_utf8(valid).unwrap_or_default());
+                    self.push_lossy(char::REPLACEMENT_CHARACTER.encode_utf8(&mut [0; 4]));
+                    errors += 1;
+                    // error_len is None only for a sequence cut short by the end of the string
+                    src = &rest[e.error_len().unwrap_or(rest.len())..];
+                }
+            }
+        }
+        (core::str::from_utf8(&self.u8_buf[..self.len]).unwrap_or_default(), errors)
+    }
+    /// Append as much of `s` as fits, up to a char boundary
+    fn push_lossy(&mut self, s: &str) {
+        let mut n = s.len().min(U8_LEN - self.len);
+        while !s.is_char_boundary(n) {
+            n -= 1;
+        }
+        self.u8_buf[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+    }
+}
+impl<const U16_LEN: usize, const U8_LEN: usize> Default for StringDes<U16_LEN, U8_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Serialized byte buffer of max-length 2*(U16_LEN-1) bytes, for binary blobs such as PDS lines.
+/// Same wire layout as StringSer: a length word in bytes, then the data packed little-endian,
+/// with an odd final byte padded with 0. Unused words are zero.
+pub struct BytesSer<const U16_LEN: usize> {
+    u16_buf: [u16; U16_LEN],
+}
+impl<const U16_LEN: usize> BytesSer<U16_LEN> {
+    /// Largest buffer that fits, in bytes
+    pub const MAX_LEN: usize = 2 * (U16_LEN - 1);
+
+    pub fn new() -> Self {
+        Self { u16_buf: [0; U16_LEN] }
+    }
+
+    /// Serialize a byte slice for sending over the COM bus.
+    pub fn encode(&mut self, bytes: &[u8]) -> Result<&[u16; U16_LEN], SerdesError> {
+        if bytes.len() > Self::MAX_LEN {
+            return Err(SerdesError::BytesLenTooBig);
+        }
+        self.u16_buf = [0; U16_LEN];
+        self.u16_buf[0] = bytes.len() as u16;
+        for (dest, src) in self.u16_buf[1..].iter_mut().zip(bytes.chunks(2)) {
+            *dest = u16::from_le_bytes([src[0], src.get(1).copied().unwrap_or(0)]);
+        }
+        Ok(self.as_u16_slice())
+    }
+
+    pub fn as_u16_slice(&self) -> &[u16; U16_LEN] {
+        &self.u16_buf
+    }
+}
+impl<const U16_LEN: usize> Default for BytesSer<U16_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Deserialized byte buffer of up to U8_LEN bytes, from the BytesSer<U16_LEN> layout
+pub struct BytesDes<const U16_LEN: usize, const U8_LEN: usize> {
+    len: usize,
+    u8_buf: [u8; U8_LEN],
+}
+impl<const U16_LEN: usize, const U8_LEN: usize> BytesDes<U16_LEN, U8_LEN> {
+    pub fn new() -> Self {
+        Self { len: 0, u8_buf: [0; U8_LEN] }
+    }
+
+    /// Deserialize a byte buffer packed as [u16]. The length word is checked against both the
+    /// packed capacity and U8_LEN.
+    pub fn decode_u16(&mut self, u16_buf: &[u16; U16_LEN]) -> Result<&[u8], SerdesError> {
+        let len = u16_buf[0] as usize;
+        if len > 2 * (U16_LEN - 1) || len > U8_LEN {
+            return Err(SerdesError::BytesLenTooBig);
+        }
+        for (dest, src) in self.u8_buf[..len].chunks_mut(2).zip(u16_buf[1..].iter()) {
+            dest.copy_from_slice(&src.to_le_bytes()[..dest.len()]);
+        }
+        self.len = len;
+        Ok(self.as_bytes())
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.u8_buf[..self.len]
+    }
+}
+impl<const U16_LEN: usize, const U8_LEN: usize> Default for BytesDes<U16_LEN, U8_LEN> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Conversions to and from the heapless collections both firmwares keep their strings and buffers in
+#[cfg(feature = "heapless")]
+impl<const U16_LEN: usize> StringSer<U16_LEN> {
+    pub fn encode_from<const N: usize>(&mut self, s: &heapless::String<N>) -> Result<&[u16; U16_LEN], SerdesError> {
+        self.encode(s.as_str())
+    }
+}
+#[cfg(feature = "heapless")]
+impl<const U16_LEN: usize, const U8_LEN: usize> StringDes<U16_LEN, U8_LEN> {
+    /// The decoded string, or StrLenTooBig if it does not fit in N bytes
+    pub fn to_heapless<const N: usize>(&self) -> Result<heapless::String<N>, SerdesError> {
+        let mut ret = heapless::String::new();
+        ret.push_str(self.as_str()?).map_err(|_| SerdesError::StrLenTooBig)?;
+        Ok(ret)
+    }
+}
+#[cfg(feature = "heapless")]
+impl<const U16_LEN: usize> BytesSer<U16_LEN> {
+    pub fn encode_from<const N: usize>(&mut self, bytes: &heapless::Vec<u8, N>) -> Result<&[u16; U16_LEN], SerdesError> {
+        self.encode(bytes)
+    }
+}
+#[cfg(feature = "heapless")]
+impl<const U16_LEN: usize, const U8_LEN: usize> BytesDes<U16_LEN, U8_LEN> {
+    /// The decoded bytes, or BytesLenTooBig if they do not fit in N bytes
+    pub fn to_heapless<const N: usize>(&self) -> Result<heapless::Vec<u8, N>, SerdesError> {
+        heapless::Vec::from_slice(self.as_bytes()).map_err(|_| SerdesError::BytesLenTooBig)
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -197,7 +472,47 @@ impl Ipv4Conf {
             ],
         }
     }
-    pub fn default() -> Ipv4Conf {
+}
+
+/// Borrowed view of an encoded Ipv4Conf, decoding each field only when it is asked for
+#[derive(Copy, Clone)]
+pub struct Ipv4ConfView<'a>(pub &'a [u16; ComState::WLAN_GET_IPV4_CONF.r_words as usize]);
+impl<'a> Ipv4ConfView<'a> {
+    fn bytes<const N: usize>(&self, first_word: usize) -> [u8; N] {
+        let mut ret = [0u8; N];
+        for (dest, word) in ret.chunks_exact_mut(2).zip(self.0[first_word..].iter()) {
+            dest.copy_from_slice(&word.to_le_bytes());
+        }
+        ret
+    }
+    pub fn dhcp(&self) -> DhcpState {
+        DhcpState::decode_u16(self.0[0])
+    }
+    pub fn mac(&self) -> [u8; 6] {
+        self.bytes(1)
+    }
+    pub fn addr(&self) -> [u8; 4] {
+        self.bytes(4)
+    }
+    pub fn gtwy(&self) -> [u8; 4] {
+        self.bytes(6)
+    }
+    pub fn mask(&self) -> [u8; 4] {
+        self.bytes(8)
+    }
+    pub fn dns1(&self) -> [u8; 4] {
+        self.bytes(10)
+    }
+    pub fn dns2(&self) -> [u8; 4] {
+        self.bytes(12)
+    }
+    /// Decode every field
+    pub fn to_conf(&self) -> Ipv4Conf {
+        Ipv4Conf::decode_u16(self.0)
+    }
+}
+impl Default for Ipv4Conf {
+    fn default() -> Ipv4Conf {
         Ipv4Conf {
             dhcp: DhcpState::Halted,
             mac: [0; 6],
@@ -210,49 +525,4911 @@ impl Ipv4Conf {
     }
 }
 
-/// Serdes Unit Tests.
-/// If you run this as a submodule of betrusted-ec, `cargo test` alone won't work right
-/// because ../.cargo/config sets an RV32 build target. The solution is to add a --target
-/// switch to cargo test, like:
-///   `cargo test --target="x86_64-unknown-linux-gnu"`
-/// To check targets, try:
-///   `rustc --print target-list`
+// Versioned payloads.
+//
+// A struct whose layout may grow starts its payload with a format word: FORMAT_TAG_MAGIC in the
+// high byte, layout version in the low byte. Payloads from before a struct was versioned carry no
+// format word and decode as version 0; their first word must never have the magic high byte.
+// Layouts only ever grow by appending words, so a decoder takes the prefix it knows from a newer
+// version, and fills in defaults for the fields an older version lacks.
+
+/// High byte of a format word
+pub const FORMAT_TAG_MAGIC: u16 = 0xFA00;
+
+/// Format word for layout `version`
+pub const fn format_tag(version: u8) -> u16 {
+    FORMAT_TAG_MAGIC | version as u16
+}
+
+/// Split the format word off a payload: (layout version, words after the format word).
+/// An untagged payload is version 0 and is returned whole.
+pub fn split_format_tag(words: &[u16]) -> (u8, &[u16]) {
+    match words.split_first() {
+        Some((&tag, body)) if tag & 0xFF00 == FORMAT_TAG_MAGIC => (tag as u8, body),
+        _ => (0, words),
+    }
+}
+
+/// A payload struct that decodes every layout version a peer may send
+pub trait VersionedDecode: Sized {
+    /// the layout version this revision writes
+    const FORMAT_VERSION: u8;
+    /// Decode `body`, the payload after its format word, written in layout `version`
+    fn decode_version(version: u8, body: &[u16]) -> Result<Self, SerdesError>;
+    /// Decode a payload, tagged or not, dispatching on its format word
+    fn decode_versioned(words: &[u16]) -> Result<Self, SerdesError> {
+        let (version, body) = split_format_tag(words);
+        Self::decode_version(version, body)
+    }
+}
+
+/// Words of a version 1 Ipv4Conf: format word, then the version 0 layout
+pub const IPV4_CONF_V1_WORDS: usize = 1 + ComState::WLAN_GET_IPV4_CONF.r_words as usize;
+const _: () = assert!(ComState::WLAN_GET_IPV4_CONF_TAGGED.r_words as usize == IPV4_CONF_V1_WORDS);
+
+impl Ipv4Conf {
+    /// Encode with a format word, for WLAN_GET_IPV4_CONF_TAGGED. WLAN_GET_IPV4_CONF carries the
+    /// untagged version 0 layout (encode_u16).
+    pub fn encode_versioned(&self) -> [u16; IPV4_CONF_V1_WORDS] {
+        let mut ret = [0u16; IPV4_CONF_V1_WORDS];
+        ret[0] = format_tag(Self::FORMAT_VERSION);
+        ret[1..].copy_from_slice(&self.encode_u16());
+        ret
+    }
+}
+impl VersionedDecode for Ipv4Conf {
+    const FORMAT_VERSION: u8 = 1;
+    fn decode_version(_version: u8, body: &[u16]) -> Result<Self, SerdesError> {
+        // versions 0 and 1 share a layout; later ones append to it
+        let mut data = [0u16; ComState::WLAN_GET_IPV4_CONF.r_words as usize];
+        let known = need(body, data.len())?;
+        data.copy_from_slice(known);
+        known_enum_value::<DhcpState>("Ipv4Conf.dhcp", data[0])?;
+        Ok(Ipv4Conf::decode_u16(&data))
+    }
+}
+
+/// Tracked values that the SoC can subscribe to with LINK_SUBSCRIBE
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum SubscriptionSource {
+    /// link RSSI, compared as a signed Rssi word (I8F8 dBm)
+    Rssi = 1,
+    /// battery state of charge in percent, as returned by GG_SOC
+    BatterySoc = 2,
+    /// EC temperature in whole degrees C, two's complement
+    Temperature = 3,
+    Invalid = 0xFF,
+}
+impl SubscriptionSource {
+    pub fn decode_u16(source: u16) -> Self {
+        match source {
+            1 => SubscriptionSource::Rssi,
+            2 => SubscriptionSource::BatterySoc,
+            3 => SubscriptionSource::Temperature,
+            _ => SubscriptionSource::Invalid,
+        }
+    }
+    /// True for sources whose values and thresholds are two's complement
+    pub fn is_signed(&self) -> bool {
+        matches!(self, SubscriptionSource::Rssi | SubscriptionSource::Temperature)
+    }
+}
+
+/// Condition under which a subscription raises INT_LINK_SUBSCRIPTION
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum SubscriptionTrigger {
+    /// cancels any subscription on the source
+    Off = 0,
+    Below = 1,
+    Above = 2,
+}
+impl SubscriptionTrigger {
+    pub fn decode_u16(trigger: u16) -> Self {
+        match trigger {
+            1 => SubscriptionTrigger::Below,
+            2 => SubscriptionTrigger::Above,
+            _ => SubscriptionTrigger::Off,
+        }
+    }
+}
+
+/// Payload of LINK_SUBSCRIBE.
+/// The EC keeps at most one subscription per source; subscribing again replaces it.
+/// Wire format:
+/// - word 0: source in bits 0-7, trigger in bits 8-9 (the "state-id")
+/// - word 1: threshold, in the units of the source
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub source: SubscriptionSource,
+    pub trigger: SubscriptionTrigger,
+    pub threshold: u16,
+}
+impl Subscription {
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_SUBSCRIBE.w_words as usize] {
+        [
+            (self.source as u16 & 0xFF) | (self.trigger as u16 & 0x3) << 8,
+            self.threshold,
+        ]
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_SUBSCRIBE.w_words as usize]) -> Self {
+        Subscription {
+            source: SubscriptionSource::decode_u16(data[0] & 0xFF),
+            trigger: SubscriptionTrigger::decode_u16((data[0] >> 8) & 0x3),
+            threshold: data[1],
+        }
+    }
+    /// Returns true if `value` satisfies this subscription's trigger condition.
+    /// Values of signed sources are compared as two's complement.
+    pub fn is_triggered_by(&self, value: u16) -> bool {
+        let ordering = if self.source.is_signed() {
+            (value as i16).cmp(&(self.threshold as i16))
+        } else {
+            value.cmp(&self.threshold)
+        };
+        match self.trigger {
+            SubscriptionTrigger::Off => false,
+            SubscriptionTrigger::Below => ordering.is_lt(),
+            SubscriptionTrigger::Above => ordering.is_gt(),
+        }
+    }
+}
+
+/// Number of entropy words returned by TRNG_STREAM_FETCH, after the header word
+pub const TRNG_STREAM_WORDS: usize = ComState::TRNG_STREAM_FETCH.r_words as usize - 1;
+// TRNG health flags, reported in the high byte of the TRNG_STREAM_FETCH header
+pub const TRNG_HEALTH_REPETITION_FAIL: u8 = 0b0000_0001; // repetition count test failed
+pub const TRNG_HEALTH_PROPORTION_FAIL: u8 = 0b0000_0010; // adaptive proportion test failed
+pub const TRNG_HEALTH_NOT_READY: u8       = 0b1000_0000; // source still warming up after reset
+
+/// Response of TRNG_STREAM_FETCH.
+/// Wire format:
+/// - word 0: number of valid entropy words in bits 0-7, health flags in bits 8-15
+/// - words 1..: entropy words; words past the valid count are zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TrngStream {
+    pub health: u8,
+    pub valid: u8,
+    pub words: [u16; TRNG_STREAM_WORDS],
+}
+impl TrngStream {
+    pub fn encode_u16(&self) -> [u16; ComState::TRNG_STREAM_FETCH.r_words as usize] {
+        let mut ret = [0u16; ComState::TRNG_STREAM_FETCH.r_words as usize];
+        ret[0] = self.valid as u16 | (self.health as u16) << 8;
+        let valid = (self.valid as usize).min(TRNG_STREAM_WORDS);
+        ret[1..1 + valid].copy_from_slice(&self.words[..valid]);
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::TRNG_STREAM_FETCH.r_words as usize]) -> Self {
+        let mut words = [0u16; TRNG_STREAM_WORDS];
+        words.copy_from_slice(&data[1..]);
+        TrngStream {
+            health: (data[0] >> 8) as u8,
+            valid: data[0] as u8,
+            words,
+        }
+    }
+    /// True if no health test failed and the source is warmed up
+    pub fn is_healthy(&self) -> bool {
+        self.health == 0
+    }
+    /// The entropy words that may be mixed in: empty if the health checks failed.
+    pub fn entropy(&self) -> &[u16] {
+        if self.is_healthy() {
+            &self.words[..(self.valid as usize).min(TRNG_STREAM_WORDS)]
+        } else {
+            &[]
+        }
+    }
+}
+
+/// Link health counters reported by LINK_GET_STATS. Counters are cumulative since EC reset and wrap.
+/// Wire format: each counter is 2 words, low word first, in field order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct LinkStats {
+    /// payloads whose CRC trailer did not match (only counted while CRC framing is on)
+    pub crc_errors: u32,
+    /// words lost because the FIFO was full or a transaction was cut short
+    pub dropped_words: u32,
+    /// unknown verbs or sequence mismatches that required a LINK_SYNC to recover
+    pub desyncs: u32,
+    /// LINK_READ pumps issued while the EC had nothing queued
+    pub spurious_reads: u32,
+}
+impl LinkStats {
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_GET_STATS.r_words as usize] {
+        let mut ret = [0u16; ComState::LINK_GET_STATS.r_words as usize];
+        let counters = [self.crc_errors, self.dropped_words, self.desyncs, self.spurious_reads];
+        for (dest, counter) in ret.chunks_exact_mut(2).zip(counters.iter()) {
+            dest.copy_from_slice(&u32_to_words(*counter));
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_GET_STATS.r_words as usize]) -> Self {
+        LinkStats {
+            crc_errors: words_to_u32([data[0], data[1]]),
+            dropped_words: words_to_u32([data[2], data[3]]),
+            desyncs: words_to_u32([data[4], data[5]]),
+            spurious_reads: words_to_u32([data[6], data[7]]),
+        }
+    }
+}
+
+// Optional protocol features advertised in ProtocolVersion::features
+pub const PROTO_FEATURE_CRC: u32                 = 0b0000_0000_0000_0001; // LINK_SET_CRC_MODE framing
+pub const PROTO_FEATURE_SEQ: u32                 = 0b0000_0000_0000_0010; // LINK_SEQ_* sequence numbers
+pub const PROTO_FEATURE_SUBSCRIBE: u32           = 0b0000_0000_0000_0100; // LINK_SUBSCRIBE
+pub const PROTO_FEATURE_LINK_STATS: u32          = 0b0000_0000_0000_1000; // LINK_GET_STATS
+pub const PROTO_FEATURE_TRNG_STREAM: u32         = 0b0000_0000_0001_0000; // TRNG_STREAM_FETCH
+pub const PROTO_FEATURE_NET_FRAGMENTS: u32       = 0b0000_0000_0010_0000; // NET_FRAME_FLAG_FRAGMENTS fragmented frames
+pub const PROTO_FEATURE_NET_RX_META: u32         = 0b0000_0000_0100_0000; // NET_FRAME_FLAG_RX_META frame metadata
+pub const PROTO_FEATURE_NET_CREDITS: u32         = 0b0000_0000_1000_0000; // NET_TX_CREDITS flow control
+// NET_RAW_SEND frame injection, factory and research builds only
+pub const PROTO_FEATURE_RAW_TX: u32              = 0b0000_0001_0000_0000;
+pub const PROTO_FEATURE_NET_PRIORITY: u32        = 0b0000_0010_0000_0000; // NET_FRAME_FLAG_TX_PRIORITY priority words
+// experimental vendor verbs are enabled (see the vendor module). Never advertised by upstream EC firmware.
+pub const PROTO_FEATURE_VENDOR_EXPERIMENTAL: u32 = 0b1000_0000_0000_0000_0000_0000_0000_0000;
+
+/// COM protocol revision reported by LINK_PROTOCOL_VERSION.
 ///
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// `major` changes when existing verbs change incompatibly, `minor` when verbs are added,
+/// `patch` for clarifications that do not change the wire format. Optional features are
+/// advertised separately in `features` so an EC may leave some of them out.
+/// EC firmware that predates LINK_PROTOCOL_VERSION implements `ProtocolVersion::V1_0`.
+/// Wire format: major, minor, patch, features (low word first).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ProtocolVersion {
+    pub major: u16,
+    pub minor: u16,
+    pub patch: u16,
+    pub features: u32,
+}
+impl ProtocolVersion {
+    /// the verb set of the initial public release (apilevel 0.9.8.8 and earlier)
+    pub const V1_0: ProtocolVersion = ProtocolVersion { major: 1, minor: 0, patch: 0, features: 0 };
+    /// every verb introduced at apilevel 0.9.9.0: link framing, sequencing, statistics and friends
+    pub const V1_1: ProtocolVersion = ProtocolVersion {
+        major: 1,
+        minor: 1,
+        patch: 0,
+        features: PROTO_FEATURE_CRC
+            | PROTO_FEATURE_SEQ
+            | PROTO_FEATURE_SUBSCRIBE
+            | PROTO_FEATURE_LINK_STATS
+            | PROTO_FEATURE_TRNG_STREAM,
+    };
+    /// apilevel 0.9.9.1: NET_FRAME_CONFIG fragments and receive metadata, transmit status and credits
+    pub const V1_2: ProtocolVersion = ProtocolVersion {
+        minor: 2,
+        features: ProtocolVersion::V1_1.features
+            | PROTO_FEATURE_NET_FRAGMENTS
+            | PROTO_FEATURE_NET_RX_META
+            | PROTO_FEATURE_NET_CREDITS,
+        ..ProtocolVersion::V1_1
+    };
+    /// apilevel 0.9.9.2: WLAN management: paged scans, known networks, roaming, enterprise and WPS
+    /// joins, soft AP, regulatory, MAC, multicast, monitor mode and radio power settings
+    pub const V1_3: ProtocolVersion = ProtocolVersion { minor: 3, ..ProtocolVersion::V1_2 };
+    /// apilevel 0.9.9.3: network offloads (MTU, IGMP, ARP, wake patterns, keepalives, mDNS), traffic
+    /// and connection diagnostics, RSSI history and filtering, and the tagged status formats
+    pub const V1_4: ProtocolVersion = ProtocolVersion {
+        minor: 4,
+        features: ProtocolVersion::V1_3.features | PROTO_FEATURE_NET_PRIORITY,
+        ..ProtocolVersion::V1_3
+    };
+    /// the protocol implemented by this revision of the crate
+    pub const CURRENT: ProtocolVersion = ProtocolVersion::V1_4;
 
-    /// Pack two ASCII chars as a u16
-    fn c2u16(c1: char, c2: char) -> u16 {
-        u16::from_le_bytes([c1 as u8, c2 as u8])
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_PROTOCOL_VERSION.r_words as usize] {
+        let [features_lo, features_hi] = u32_to_words(self.features);
+        [self.major, self.minor, self.patch, features_lo, features_hi]
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_PROTOCOL_VERSION.r_words as usize]) -> Self {
+        ProtocolVersion {
+            major: data[0],
+            minor: data[1],
+            patch: data[2],
+            features: words_to_u32([data[3], data[4]]),
+        }
+    }
+    /// Compare major/minor/patch only; feature bits are not ordered.
+    pub fn cmp_revision(&self, other: &ProtocolVersion) -> core::cmp::Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
     }
+    /// True if this revision is `other` or newer
+    pub fn at_least(&self, other: &ProtocolVersion) -> bool {
+        self.cmp_revision(other) != core::cmp::Ordering::Less
+    }
+    /// True if a peer at this version can serve a host built against `host`: same major,
+    /// and every verb the host may issue exists on this side.
+    pub fn is_compatible_with(&self, host: &ProtocolVersion) -> bool {
+        self.major == host.major && self.minor >= host.minor
+    }
+    /// True if all of the given feature bits are advertised
+    pub fn has_features(&self, features: u32) -> bool {
+        self.features & features == features
+    }
+}
 
-    #[test]
-    fn serialize_short_str() {
-        const U16_LEN: usize = 4;
-        let src = "short";
-        let encoded = &[5, c2u16('s', 'h'), c2u16('o', 'r'), 't' as u16];
-        let mut ser = StringSer::<U16_LEN>::new();
-        assert_eq!(encoded, ser.encode(&src).unwrap());
+/// Number of verbs per LINK_LIST_VERBS page
+pub const VERB_LIST_PAGE_LEN: usize = 16;
+
+/// One verb as reported by LINK_LIST_VERBS
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct VerbEntry {
+    pub verb: u16,
+    pub w_words: u16,
+    pub r_words: u16,
+}
+impl From<&ComSpec> for VerbEntry {
+    fn from(spec: &ComSpec) -> Self {
+        VerbEntry {
+            verb: spec.verb,
+            w_words: spec.w_words,
+            r_words: spec.r_words,
+        }
     }
+}
 
-    #[test]
-    fn deserialize_short_str() {
-        const U16_LEN: usize = 4;
-        const U8_LEN: usize = 2 * (U16_LEN - 1);
-        let src = "short";
-        let encoded = &[5, c2u16('s', 'h'), c2u16('o', 'r'), 't' as u16];
-        let mut des = StringDes::<U16_LEN, U8_LEN>::new();
-        assert_eq!(src, des.decode_u16(encoded).unwrap());
+/// Response of LINK_LIST_VERBS.
+/// Wire format:
+/// - word 0: total number of verbs implemented by the EC, across all pages
+/// - words 1..: VERB_LIST_PAGE_LEN entries of (verb, w_words, r_words); unused entries are all zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VerbListPage {
+    pub total: u16,
+    pub entries: [VerbEntry; VERB_LIST_PAGE_LEN],
+}
+impl VerbListPage {
+    /// Build page number `page` of a verb table, as done by the EC
+    pub fn from_table(table: &[ComSpec], page: u16) -> Self {
+        let mut entries = [VerbEntry::default(); VERB_LIST_PAGE_LEN];
+        let start = page as usize * VERB_LIST_PAGE_LEN;
+        for (entry, spec) in entries.iter_mut().zip(table.iter().skip(start)) {
+            *entry = VerbEntry::from(spec);
+        }
+        VerbListPage {
+            total: table.len() as u16,
+            entries,
+        }
+    }
+    /// Number of pages the host must fetch to see every verb
+    pub fn page_count(&self) -> u16 {
+        (self.total as usize).div_ceil(VERB_LIST_PAGE_LEN) as u16
+    }
+    /// The populated entries of this page
+    pub fn verbs(&self) -> impl Iterator<Item = &VerbEntry> {
+        self.entries.iter().filter(|e| e.verb != 0)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_LIST_VERBS.r_words as usize] {
+        let mut ret = [0u16; ComState::LINK_LIST_VERBS.r_words as usize];
+        ret[0] = self.total;
+        for (dest, entry) in ret[1..].chunks_exact_mut(3).zip(self.entries.iter()) {
+            dest[0] = entry.verb;
+            dest[1] = entry.w_words;
+            dest[2] = entry.r_words;
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_LIST_VERBS.r_words as usize]) -> Self {
+        let mut entries = [VerbEntry::default(); VERB_LIST_PAGE_LEN];
+        for (entry, src) in entries.iter_mut().zip(data[1..].chunks_exact(3)) {
+            *entry = VerbEntry {
+                verb: src[0],
+                w_words: src[1],
+                r_words: src[2],
+            };
+        }
+        VerbListPage {
+            total: data[0],
+            entries,
+        }
     }
+}
 
-    #[test]
-    fn round_trip_short_str() {
-        const U16_LEN: usize = 4;
-        const U8_LEN: usize = 2 * (U16_LEN - 1);
-        let src = "short";
-        let mut ser = StringSer::<U16_LEN>::new();
-        let mut des = StringDes::<U16_LEN, U8_LEN>::new();
-        assert_eq!(src, des.decode_u16(ser.encode(&src).unwrap()).unwrap());
+/// Signed 8.8 fixed-point value: 8 integer bits (two's complement), 8 fractional bits.
+/// Used for sensor readings such as RSSI in dBm and temperature in degrees C, so fractions
+/// survive the trip across the bus. Range is -128.0 to +127.996, in steps of 1/256.
+/// Arithmetic saturates at the ends of the range.
+/// Wire format: one word, the raw two's complement value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct I8F8(pub i16);
+impl I8F8 {
+    pub const FRAC_BITS: u32 = 8;
+    pub const ZERO: I8F8 = I8F8(0);
+    pub const MIN: I8F8 = I8F8(i16::MIN);
+    pub const MAX: I8F8 = I8F8(i16::MAX);
+
+    pub const fn from_int(value: i8) -> Self {
+        I8F8((value as i16) << Self::FRAC_BITS)
+    }
+    /// Convert from thousandths, saturating at the ends of the range
+    pub fn from_milli(milli: i32) -> Self {
+        let raw = (milli as i64 * 256).div_euclid(1000);
+        I8F8(raw.clamp(i16::MIN as i64, i16::MAX as i64) as i16)
+    }
+    /// Value in thousandths, truncated towards negative infinity
+    pub fn to_milli(self) -> i32 {
+        (self.0 as i32 * 1000).div_euclid(256)
+    }
+    /// Integer part, rounded towards negative infinity
+    pub fn floor(self) -> i8 {
+        (self.0 >> Self::FRAC_BITS) as i8
+    }
+    /// Integer part, rounded to nearest
+    pub fn round(self) -> i8 {
+        ((self.0 as i32 + 0x80) >> Self::FRAC_BITS).clamp(i8::MIN as i32, i8::MAX as i32) as i8
+    }
+    /// Exponentially weighted moving average step: moves `self` towards `sample` by 1/2^shift of the difference.
+    pub fn ewma(self, sample: I8F8, shift: u32) -> Self {
+        let diff = sample.0 as i32 - self.0 as i32;
+        I8F8((self.0 as i32 + (diff >> shift.min(15))) as i16)
+    }
+    pub fn encode_u16(&self) -> u16 {
+        self.0 as u16
+    }
+    pub fn decode_u16(word: u16) -> Self {
+        I8F8(word as i16)
+    }
+}
+impl core::ops::Add for I8F8 {
+    type Output = I8F8;
+    fn add(self, rhs: I8F8) -> I8F8 {
+        I8F8(self.0.saturating_add(rhs.0))
+    }
+}
+impl core::ops::Sub for I8F8 {
+    type Output = I8F8;
+    fn sub(self, rhs: I8F8) -> I8F8 {
+        I8F8(self.0.saturating_sub(rhs.0))
+    }
+}
+impl core::ops::Neg for I8F8 {
+    type Output = I8F8;
+    fn neg(self) -> I8F8 {
+        I8F8(self.0.saturating_neg())
+    }
+}
+impl core::ops::Mul for I8F8 {
+    type Output = I8F8;
+    fn mul(self, rhs: I8F8) -> I8F8 {
+        let product = (self.0 as i32 * rhs.0 as i32) >> Self::FRAC_BITS;
+        I8F8(product.clamp(i16::MIN as i32, i16::MAX as i32) as i16)
+    }
+}
+impl core::fmt::Display for I8F8 {
+    /// Formats with two decimal places, e.g. "-61.25"
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let hundredths = (self.0 as i32 * 100).div_euclid(256);
+        let sign = if hundredths < 0 { "-" } else { "" };
+        let abs = hundredths.abs();
+        write!(f, "{}{}.{:02}", sign, abs / 100, abs % 100)
+    }
+}
+
+/// Received signal strength in dBm.
+/// Wire format: one word, the signal strength as I8F8, e.g. 0xC200 for -62 dBm. Every RSSI carried
+/// by the COM uses this encoding, except the legacy WLAN_GET_RSSI and WLAN_BIN_STATUS word (see `Rcpi`).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Rssi(I8F8);
+impl Rssi {
+    pub const fn from_dbm(dbm: i8) -> Self {
+        Rssi(I8F8::from_int(dbm))
+    }
+    pub const fn from_i8f8(dbm: I8F8) -> Self {
+        Rssi(dbm)
+    }
+    /// Signal strength in dBm, rounded to nearest
+    pub fn dbm(&self) -> i8 {
+        self.0.round()
+    }
+    /// Signal strength in dBm, with the fraction when the EC reported one
+    pub fn as_i8f8(&self) -> I8F8 {
+        self.0
+    }
+    pub fn quality(&self) -> SignalQuality {
+        SignalQuality::from_rssi(*self)
+    }
+    pub fn encode_u16(&self) -> u16 {
+        self.0.encode_u16()
+    }
+    pub fn decode_u16(word: u16) -> Self {
+        Rssi(I8F8::decode_u16(word))
+    }
+}
+impl core::fmt::Display for Rssi {
+    /// Formats as whole dBm, e.g. "-61 dBm"
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} dBm", self.dbm())
+    }
+}
+
+/// Received channel power indicator, as reported by the WF200 (IEEE 802.11 RCPI): 0 is -110 dBm
+/// or less, each step is 0.5 dB, 220 is 0 dBm or more, and 255 means no measurement is available.
+/// Only the legacy WLAN_GET_RSSI verb and the rssi word of WLAN_BIN_STATUS use it; convert with
+/// `rssi()` and `from_rssi()`.
+/// Wire format: one word, the RCPI in the low byte; the high byte must be zero.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Rcpi(pub u8);
+impl Rcpi {
+    pub const UNAVAILABLE: Rcpi = Rcpi(255);
+    const MAX_VALID: u8 = 220;
+    const OFFSET_DBM: i16 = 110;
+
+    /// Signal strength in dBm, or None if the EC had no measurement
+    pub fn rssi(&self) -> Option<Rssi> {
+        if self.0 > Self::MAX_VALID {
+            return None;
+        }
+        Some(Rssi::from_i8f8(I8F8(self.0 as i16 * 128 - (Self::OFFSET_DBM << I8F8::FRAC_BITS))))
+    }
+    /// Nearest RCPI to `rssi`, clamped to -110 to 0 dBm
+    pub fn from_rssi(rssi: Rssi) -> Self {
+        let half_db = (rssi.as_i8f8().0 as i32 + ((Self::OFFSET_DBM as i32) << I8F8::FRAC_BITS) + 64).div_euclid(128);
+        Rcpi(half_db.clamp(0, Self::MAX_VALID as i32) as u8)
+    }
+    pub fn encode_u16(&self) -> u16 {
+        self.0 as u16
+    }
+    pub fn decode_u16(word: u16) -> Result<Self, SerdesError> {
+        if word > u8::MAX as u16 {
+            return Err(SerdesError::OutOfRange { field: "Rcpi", value: word });
+        }
+        Ok(Rcpi(word as u8))
+    }
+}
+
+/// Signal strength bucket, for bar icons
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SignalQuality {
+    /// below -85 dBm: expect the link to drop
+    Unusable,
+    /// -85 dBm and up
+    Poor,
+    /// -75 dBm and up
+    Fair,
+    /// -67 dBm and up
+    Good,
+    /// -55 dBm and up
+    Excellent,
+}
+impl SignalQuality {
+    pub fn from_rssi(rssi: Rssi) -> Self {
+        match rssi.dbm() {
+            dbm if dbm >= -55 => SignalQuality::Excellent,
+            dbm if dbm >= -67 => SignalQuality::Good,
+            dbm if dbm >= -75 => SignalQuality::Fair,
+            dbm if dbm >= -85 => SignalQuality::Poor,
+            _ => SignalQuality::Unusable,
+        }
+    }
+    /// Number of bars to draw, 0 to 4
+    pub fn bars(&self) -> u8 {
+        *self as u8
+    }
+}
+
+/// A flags word: up to 16 named boolean bits packed into one payload word.
+/// Payloads declare their bits as `Flags16::bit(n)` consts and check them with
+/// `const _: () = assert!(Flags16::disjoint(&[...]));`, so two flags assigned to the same bit
+/// fail to compile instead of silently aliasing on the wire.
+/// Wire format: one word, the raw bits.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Default, Debug)]
+pub struct Flags16(u16);
+impl Flags16 {
+    pub const fn empty() -> Self {
+        Flags16(0)
+    }
+    /// The flag at bit `n`. Fails const evaluation if `n` is out of range.
+    pub const fn bit(n: u32) -> Self {
+        assert!(n < 16, "Flags16 bit out of range");
+        Flags16(1 << n)
+    }
+    pub const fn from_bits(bits: u16) -> Self {
+        Flags16(bits)
+    }
+    pub const fn bits(&self) -> u16 {
+        self.0
+    }
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+    /// True if every flag in `other` is set
+    pub const fn contains(&self, other: Flags16) -> bool {
+        self.0 & other.0 == other.0
+    }
+    /// Builder: these flags plus `other`
+    pub const fn with(self, other: Flags16) -> Self {
+        Flags16(self.0 | other.0)
+    }
+    /// Builder: these flags minus `other`
+    pub const fn without(self, other: Flags16) -> Self {
+        Flags16(self.0 & !other.0)
+    }
+    pub fn set(&mut self, other: Flags16, value: bool) {
+        if value {
+            self.0 |= other.0;
+        } else {
+            self.0 &= !other.0;
+        }
+    }
+    /// True if no two entries of `flags` share a bit
+    pub const fn disjoint(flags: &[Flags16]) -> bool {
+        let mut seen = 0u16;
+        let mut i = 0;
+        while i < flags.len() {
+            if seen & flags[i].0 != 0 {
+                return false;
+            }
+            seen |= flags[i].0;
+            i += 1;
+        }
+        true
+    }
+    /// Iterate over the set flags, one bit at a time from bit 0 up
+    pub fn iter(&self) -> impl Iterator<Item = Flags16> {
+        let bits = self.0;
+        (0..16).map(|n| Flags16(1 << n)).filter(move |f| bits & f.0 != 0)
+    }
+    pub fn encode_u16(&self) -> u16 {
+        self.0
+    }
+    pub fn decode_u16(word: u16) -> Self {
+        Flags16(word)
+    }
+}
+impl core::ops::BitOr for Flags16 {
+    type Output = Flags16;
+    fn bitor(self, rhs: Flags16) -> Flags16 {
+        Flags16(self.0 | rhs.0)
+    }
+}
+
+// P2pConfig flags
+pub const P2P_FLAG_PERSISTENT: Flags16 = Flags16::bit(0); // keep the group credentials for reconnection
+pub const P2P_FLAG_LISTEN_ONLY: Flags16 = Flags16::bit(1); // wait for a peer to initiate instead of searching
+const _: () = assert!(Flags16::disjoint(&[P2P_FLAG_PERSISTENT, P2P_FLAG_LISTEN_ONLY]));
+
+/// Payload of WLAN_P2P_START.
+/// Wire format:
+/// - word 0: group owner intent (0-15, 15 = must be owner) in bits 0-7, channel in bits 8-15 (0 = EC picks)
+/// - word 1: P2P_FLAG_* bits
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct P2pConfig {
+    pub go_intent: u8,
+    pub channel: u8,
+    pub flags: Flags16,
+}
+impl P2pConfig {
+    pub const MAX_GO_INTENT: u8 = 15;
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_P2P_START.w_words as usize] {
+        [
+            self.go_intent.min(Self::MAX_GO_INTENT) as u16 | (self.channel as u16) << 8,
+            self.flags.encode_u16(),
+        ]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_P2P_START.w_words as usize]) -> Self {
+        P2pConfig {
+            go_intent: (data[0] as u8).min(Self::MAX_GO_INTENT),
+            channel: (data[0] >> 8) as u8,
+            flags: Flags16::decode_u16(data[1]),
+        }
+    }
+}
+
+/// P2P link states
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum P2pState {
+    Idle = 0,
+    Negotiating = 1,
+    Connected = 2,
+    Failed = 3,
+    /// the EC's WF200 firmware cannot do P2P
+    Unsupported = 4,
+}
+impl P2pState {
+    pub fn decode_u16(state: u16) -> Self {
+        match state {
+            0 => P2pState::Idle,
+            1 => P2pState::Negotiating,
+            2 => P2pState::Connected,
+            3 => P2pState::Failed,
+            _ => P2pState::Unsupported,
+        }
+    }
+}
+
+/// Role taken by this device once a P2P group is formed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum P2pRole {
+    None = 0,
+    GroupOwner = 1,
+    Client = 2,
+}
+impl P2pRole {
+    pub fn decode_u16(role: u16) -> Self {
+        match role {
+            1 => P2pRole::GroupOwner,
+            2 => P2pRole::Client,
+            _ => P2pRole::None,
+        }
+    }
+}
+
+/// Response of WLAN_P2P_STATUS.
+/// Wire format:
+/// - word 0: P2pState in bits 0-7, P2pRole in bits 8-15
+/// - word 1: operating channel (0 if not connected)
+/// - words 2-4: peer MAC address, packed like Ipv4Conf::mac
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct P2pStatus {
+    pub state: P2pState,
+    pub role: P2pRole,
+    pub channel: u16,
+    pub peer: [u8; 6],
+}
+impl P2pStatus {
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_P2P_STATUS.r_words as usize] {
+        [
+            self.state as u16 | (self.role as u16) << 8,
+            self.channel,
+            self.peer[0] as u16 | (self.peer[1] as u16) << 8,
+            self.peer[2] as u16 | (self.peer[3] as u16) << 8,
+            self.peer[4] as u16 | (self.peer[5] as u16) << 8,
+        ]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_P2P_STATUS.r_words as usize]) -> Self {
+        P2pStatus {
+            state: P2pState::decode_u16(data[0] & 0xFF),
+            role: P2pRole::decode_u16(data[0] >> 8),
+            channel: data[1],
+            peer: [
+                data[2] as u8,
+                (data[2] >> 8) as u8,
+                data[3] as u8,
+                (data[3] >> 8) as u8,
+                data[4] as u8,
+                (data[4] >> 8) as u8,
+            ],
+        }
+    }
+}
+
+/// Severity of EC log records
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u16)]
+pub enum LogSeverity {
+    Trace = 0,
+    Debug = 1,
+    Info = 2,
+    Warn = 3,
+    Error = 4,
+    /// above every record severity; as a threshold, matches nothing
+    Off = 0xFF,
+}
+impl LogSeverity {
+    pub fn decode_u16(severity: u16) -> Self {
+        match severity {
+            0 => LogSeverity::Trace,
+            1 => LogSeverity::Debug,
+            2 => LogSeverity::Info,
+            3 => LogSeverity::Warn,
+            4 => LogSeverity::Error,
+            _ => LogSeverity::Off,
+        }
+    }
+}
+
+/// Payload of EC_LOG_ESCALATION_SET.
+/// Wire format:
+/// - word 0: minimum LogSeverity that is escalated (LogSeverity::Off disables escalation)
+/// - word 1: maximum escalated interrupts per second, 0 for no limit
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LogEscalation {
+    pub min_severity: LogSeverity,
+    pub max_per_sec: u16,
+}
+impl LogEscalation {
+    /// escalation is off until configured
+    pub const DISABLED: LogEscalation = LogEscalation { min_severity: LogSeverity::Off, max_per_sec: 0 };
+
+    pub fn encode_u16(&self) -> [u16; ComState::EC_LOG_ESCALATION_SET.w_words as usize] {
+        [self.min_severity as u16, self.max_per_sec]
+    }
+    pub fn decode_u16(data: &[u16; ComState::EC_LOG_ESCALATION_SET.w_words as usize]) -> Self {
+        LogEscalation {
+            min_severity: LogSeverity::decode_u16(data[0]),
+            max_per_sec: data[1],
+        }
+    }
+    /// True if a record of this severity should raise INT_EC_LOG_READY right away.
+    /// `raised_this_sec` is the number of escalations already raised in the current one-second window.
+    pub fn should_escalate(&self, severity: LogSeverity, raised_this_sec: u16) -> bool {
+        severity != LogSeverity::Off
+            && severity >= self.min_severity
+            && (self.max_per_sec == 0 || raised_this_sec < self.max_per_sec)
+    }
+}
+
+/// Number of events per LINK_EVENT_FETCH page
+pub const EVENT_PAGE_LEN: usize = 8;
+
+/// One queued interrupt event.
+/// Wire format: 4 words
+/// - word 0: bit number of the interrupt source in the 32-bit scheme
+/// - words 1-2: EC uptime in ms when the event was raised, low word first (wraps after ~49 days)
+/// - word 3: the interrupt argument, as GET_INTERRUPT would have reported it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ComEvent {
+    pub source: InterruptFlags,
+    pub timestamp_ms: u32,
+    pub arg: u16,
+}
+impl ComEvent {
+    pub fn encode_u16(&self) -> [u16; 4] {
+        let [timestamp_lo, timestamp_hi] = u32_to_words(self.timestamp_ms);
+        [self.source.bits().trailing_zeros() as u16, timestamp_lo, timestamp_hi, self.arg]
+    }
+    pub fn decode_u16(data: &[u16; 4]) -> Self {
+        ComEvent {
+            source: InterruptFlags::from_bits(1u32.checked_shl(data[0] as u32).unwrap_or(0)),
+            timestamp_ms: words_to_u32([data[1], data[2]]),
+            arg: data[3],
+        }
+    }
+}
+
+/// Response of LINK_EVENT_FETCH.
+/// Wire format:
+/// - word 0: number of valid events in this page in bits 0-7, number still queued after it in bits 8-15 (saturating)
+/// - words 1..: EVENT_PAGE_LEN ComEvents, oldest first; unused slots are zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EventPage {
+    pub count: u8,
+    pub pending: u8,
+    pub events: [ComEvent; EVENT_PAGE_LEN],
+}
+impl EventPage {
+    pub const EMPTY_EVENT: ComEvent = ComEvent { source: InterruptFlags::empty(), timestamp_ms: 0, arg: 0 };
+
+    pub fn valid(&self) -> &[ComEvent] {
+        &self.events[..(self.count as usize).min(EVENT_PAGE_LEN)]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_EVENT_FETCH.r_words as usize] {
+        let mut ret = [0u16; ComState::LINK_EVENT_FETCH.r_words as usize];
+        ret[0] = self.count as u16 | (self.pending as u16) << 8;
+        for (dest, event) in ret[1..].chunks_exact_mut(4).zip(self.valid().iter()) {
+            dest.copy_from_slice(&event.encode_u16());
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_EVENT_FETCH.r_words as usize]) -> Self {
+        let count = (data[0] as u8).min(EVENT_PAGE_LEN as u8);
+        let mut events = [Self::EMPTY_EVENT; EVENT_PAGE_LEN];
+        for (event, src) in events.iter_mut().zip(data[1..].chunks_exact(4)).take(count as usize) {
+            *event = ComEvent::decode_u16(&[src[0], src[1], src[2], src[3]]);
+        }
+        EventPage {
+            count,
+            pending: (data[0] >> 8) as u8,
+            events,
+        }
+    }
+}
+
+/// Borrowed view of an encoded EventPage, decoding events one at a time
+#[derive(Copy, Clone)]
+pub struct EventPageView<'a>(pub &'a [u16; ComState::LINK_EVENT_FETCH.r_words as usize]);
+impl<'a> EventPageView<'a> {
+    /// Number of valid events in the page
+    pub fn count(&self) -> usize {
+        (self.0[0] as u8 as usize).min(EVENT_PAGE_LEN)
+    }
+    /// Number of events still queued on the EC
+    pub fn pending(&self) -> u8 {
+        (self.0[0] >> 8) as u8
+    }
+    /// The event at `index`, if it is valid
+    pub fn get(&self, index: usize) -> Option<ComEvent> {
+        if index >= self.count() {
+            return None;
+        }
+        let src = &self.0[1 + 4 * index..];
+        Some(ComEvent::decode_u16(&[src[0], src[1], src[2], src[3]]))
+    }
+    pub fn iter(&self) -> impl Iterator<Item = ComEvent> + 'a {
+        let view = *self;
+        (0..view.count()).filter_map(move |i| view.get(i))
+    }
+}
+
+/// Number of records per EC_LOG_FETCH response
+pub const EC_LOG_PAGE_LEN: usize = 16;
+/// Maximum length of an EC log message in bytes; longer messages are truncated by the EC
+pub const LOG_MSG_U8_SIZE: usize = STR_32_U8_SIZE;
+
+/// One EC log record, as returned by EC_LOG_FETCH.
+/// Wire format: 20 words
+/// - word 0: LogSeverity
+/// - words 1-2: EC uptime in ms when the record was logged, low word first
+/// - words 3-19: message, in the StringSer<STR_32_WORDS> format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LogRecord {
+    pub severity: LogSeverity,
+    pub timestamp_ms: u32,
+    msg_len: u8,
+    msg: [u8; LOG_MSG_U8_SIZE],
+}
+impl LogRecord {
+    pub const WORDS: usize = 3 + STR_32_WORDS;
+
+    /// Create a record, truncating the message to LOG_MSG_U8_SIZE bytes on a char boundary
+    pub fn new(severity: LogSeverity, timestamp_ms: u32, message: &str) -> Self {
+        let mut len = message.len().min(LOG_MSG_U8_SIZE);
+        while !message.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut msg = [0u8; LOG_MSG_U8_SIZE];
+        msg[..len].copy_from_slice(&message.as_bytes()[..len]);
+        LogRecord {
+            severity,
+            timestamp_ms,
+            msg_len: len as u8,
+            msg,
+        }
+    }
+    pub fn message(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.msg[..self.msg_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn encode_u16(&self) -> [u16; LogRecord::WORDS] {
+        let mut ret = [0u16; LogRecord::WORDS];
+        ret[0] = self.severity as u16;
+        ret[1..3].copy_from_slice(&u32_to_words(self.timestamp_ms));
+        ret[3] = self.msg_len as u16;
+        for (dest, src) in ret[4..].iter_mut().zip(self.msg.chunks_exact(2)) {
+            *dest = u16::from_le_bytes([src[0], src[1]]);
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; LogRecord::WORDS]) -> Result<Self, SerdesError> {
+        if data[3] as usize > LOG_MSG_U8_SIZE {
+            return Err(SerdesError::StrLenTooBig);
+        }
+        let mut msg = [0u8; LOG_MSG_U8_SIZE];
+        for (dest, src) in msg.chunks_exact_mut(2).zip(data[4..].iter()) {
+            dest.copy_from_slice(&src.to_le_bytes());
+        }
+        Ok(LogRecord {
+            severity: known_enum_value("LogRecord.severity", data[0])?,
+            timestamp_ms: words_to_u32([data[1], data[2]]),
+            msg_len: data[3] as u8,
+            msg,
+        })
+    }
+}
+
+// Coalescing defaults: every event asserts immediately
+pub const COALESCE_MIN_EVENTS_DEFAULT: u16 = 1;
+pub const COALESCE_MAX_DELAY_MS_DEFAULT: u16 = 0;
+pub const COALESCE_MIN_INTERVAL_MS_DEFAULT: u16 = 0;
+
+/// Payload of LINK_SET_COALESCE.
+/// Events of the source are still counted and queued as usual; only assertion of the
+/// interrupt line is held back. A source is asserted once `min_events` are pending, or once
+/// the oldest pending event is `max_delay_ms` old, but never sooner than `min_interval_ms`
+/// after the previous assertion.
+/// Wire format:
+/// - word 0: bit number of the interrupt source in the 32-bit scheme
+/// - word 1: min_events (0 or 1: assert on every event)
+/// - word 2: max_delay_ms (0: no timeout, wait for min_events)
+/// - word 3: min_interval_ms (0: no rate limit)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CoalesceConfig {
+    pub source: InterruptFlags,
+    pub min_events: u16,
+    pub max_delay_ms: u16,
+    pub min_interval_ms: u16,
+}
+impl CoalesceConfig {
+    /// The configuration that turns coalescing off for `source`
+    pub const fn disabled(source: InterruptFlags) -> Self {
+        CoalesceConfig {
+            source,
+            min_events: COALESCE_MIN_EVENTS_DEFAULT,
+            max_delay_ms: COALESCE_MAX_DELAY_MS_DEFAULT,
+            min_interval_ms: COALESCE_MIN_INTERVAL_MS_DEFAULT,
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_SET_COALESCE.w_words as usize] {
+        [
+            self.source.bits().trailing_zeros() as u16,
+            self.min_events,
+            self.max_delay_ms,
+            self.min_interval_ms,
+        ]
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_SET_COALESCE.w_words as usize]) -> Self {
+        CoalesceConfig {
+            source: InterruptFlags::from_bits(1u32.checked_shl(data[0] as u32).unwrap_or(0)),
+            min_events: data[1],
+            max_delay_ms: data[2],
+            min_interval_ms: data[3],
+        }
+    }
+    /// Decide whether the EC should assert the source now.
+    /// - `pending`: events raised since the last assertion
+    /// - `oldest_age_ms`: age of the oldest of those events
+    /// - `since_last_ms`: time since the source was last asserted
+    pub fn should_assert(&self, pending: u16, oldest_age_ms: u32, since_last_ms: u32) -> bool {
+        if pending == 0 || since_last_ms < self.min_interval_ms as u32 {
+            return false;
+        }
+        pending >= self.min_events.max(1) || (self.max_delay_ms != 0 && oldest_age_ms >= self.max_delay_ms as u32)
+    }
+}
+
+/// Privileged verbs that the EC records in its audit log
+pub const AUDITED_VERBS: &[ComSpec] = &[
+    ComState::FLASH_ERASE,
+    ComState::FLASH_PP,
+    ComState::FLASH_LOCK,
+    ComState::FLASH_UNLOCK,
+    ComState::CHG_START,
+    ComState::CHG_BOOST_ON,
+    ComState::CHG_BOOST_OFF,
+    ComState::GG_FACTORY_CAPACITY,
+    ComState::POWER_OFF,
+    ComState::POWER_SHIPMODE,
+    ComState::WF200_RESET,
+    ComState::WLAN_MONITOR_START,
+    ComState::WLAN_MONITOR_STOP,
+    ComState::NET_RAW_SEND_0,
+    ComState::NET_RAW_SEND_1,
+    ComState::NET_RAW_SEND_7FF,
+];
+/// Number of records per AUDIT_LOG_FETCH response
+pub const AUDIT_PAGE_LEN: usize = 16;
+
+/// True if the EC must record executions of `verb` in the audit log
+pub fn is_audited(verb: u16) -> bool {
+    // NET_RAW_SEND is a family, only a few lengths of which are in the table
+    AUDITED_VERBS.iter().any(|spec| spec.verb == verb) || ComState::is_net_raw_send(verb)
+}
+
+/// Outcome of an audited verb
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum AuditResult {
+    Ok = 0,
+    /// refused, e.g. because updates were not unlocked
+    Rejected = 1,
+    /// attempted but failed
+    Failed = 2,
+    Unknown = 0xFFFF,
+}
+impl AuditResult {
+    pub fn decode_u16(result: u16) -> Self {
+        match result {
+            0 => AuditResult::Ok,
+            1 => AuditResult::Rejected,
+            2 => AuditResult::Failed,
+            _ => AuditResult::Unknown,
+        }
+    }
+}
+
+/// One audit log entry, as returned by AUDIT_LOG_FETCH.
+/// Wire format: verb, EC uptime in ms (2 words, low word first), AuditResult
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AuditRecord {
+    pub verb: u16,
+    pub uptime_ms: u32,
+    pub result: AuditResult,
+}
+impl AuditRecord {
+    pub fn encode_u16(&self) -> [u16; 4] {
+        let [uptime_lo, uptime_hi] = u32_to_words(self.uptime_ms);
+        [self.verb, uptime_lo, uptime_hi, self.result as u16]
+    }
+    pub fn decode_u16(data: &[u16; 4]) -> Self {
+        AuditRecord {
+            verb: data[0],
+            uptime_ms: words_to_u32([data[1], data[2]]),
+            result: AuditResult::decode_u16(data[3]),
+        }
+    }
+}
+
+/// Decoded response of LINK_GET_INTERRUPT (2 words) or LINK_GET_INTERRUPT32 (3 words).
+/// Wire format: interrupt source (1 or 2 words, low word first), then the rx length argument in bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InterruptStatus {
+    pub flags: InterruptFlags,
+    pub rx_len_bytes: u16,
+}
+impl InterruptStatus {
+    pub fn encode_u16(&self) -> [u16; ComState::LINK_GET_INTERRUPT.r_words as usize] {
+        [self.flags.to_u16(), self.rx_len_bytes]
+    }
+    pub fn decode_u16(data: &[u16; ComState::LINK_GET_INTERRUPT.r_words as usize]) -> Self {
+        InterruptStatus {
+            flags: InterruptFlags::from_u16(data[0]),
+            rx_len_bytes: data[1],
+        }
+    }
+    pub fn encode32_u16(&self) -> [u16; ComState::LINK_GET_INTERRUPT32.r_words as usize] {
+        let flags = self.flags.encode_u16();
+        [flags[0], flags[1], self.rx_len_bytes]
+    }
+    pub fn decode32_u16(data: &[u16; ComState::LINK_GET_INTERRUPT32.r_words as usize]) -> Self {
+        InterruptStatus {
+            flags: InterruptFlags::decode_u16(&[data[0], data[1]]),
+            rx_len_bytes: data[2],
+        }
+    }
+    /// The NET_FRAME_FETCH verb that retrieves the pending rx frame, if INT_WLAN_RX_READY is set
+    /// and the length fits the length-encoded verb scheme.
+    /// The response is one confirmation word followed by the frame packed into words.
+    pub fn rx_fetch_spec(&self) -> Option<ComSpec> {
+        if !self.flags.contains(InterruptFlags::WLAN_RX_READY) || self.rx_len_bytes == 0 {
+            return None;
+        }
+        ComState::net_frame_fetch(self.rx_len_bytes).ok()
+    }
+}
+
+/// Number of entries in the credential map
+pub const CRED_MAP_LEN: usize = 8;
+/// Slot number marking an unused credential map entry
+pub const CRED_SLOT_NONE: u8 = 0xFF;
+
+/// 32-bit FNV-1a hash of an SSID's bytes, used as the credential map key.
+/// Collisions between nearby networks are unlikely, and harmless beyond a failed join attempt.
+pub fn ssid_hash(ssid: &str) -> u32 {
+    ssid.as_bytes()
+        .iter()
+        .fold(0x811C_9DC5u32, |hash, &b| (hash ^ b as u32).wrapping_mul(0x0100_0193))
+}
+
+/// One credential map entry.
+/// Wire format: SSID hash (2 words, low word first), then slot in bits 0-7 and priority in bits 8-15
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CredMapEntry {
+    pub ssid_hash: u32,
+    /// credential slot on the EC, or CRED_SLOT_NONE
+    pub slot: u8,
+    /// higher values are preferred when several mapped SSIDs are visible
+    pub priority: u8,
+}
+impl CredMapEntry {
+    pub const EMPTY: CredMapEntry = CredMapEntry { ssid_hash: 0, slot: CRED_SLOT_NONE, priority: 0 };
+    pub fn is_empty(&self) -> bool {
+        self.slot == CRED_SLOT_NONE
+    }
+}
+
+/// Payload of WLAN_CRED_MAP_SET and response of WLAN_CRED_MAP_GET
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CredMap {
+    pub entries: [CredMapEntry; CRED_MAP_LEN],
+}
+impl CredMap {
+    pub const fn new() -> Self {
+        CredMap { entries: [CredMapEntry::EMPTY; CRED_MAP_LEN] }
+    }
+    /// Map `ssid` to `slot`, replacing an existing entry for the SSID or taking a free one.
+    /// Returns false if the map is full.
+    pub fn insert(&mut self, ssid: &str, slot: u8, priority: u8) -> bool {
+        let hash = ssid_hash(ssid);
+        let target = match self.entries.iter().position(|e| !e.is_empty() && e.ssid_hash == hash) {
+            Some(i) => i,
+            None => match self.entries.iter().position(|e| e.is_empty()) {
+                Some(i) => i,
+                None => return false,
+            },
+        };
+        self.entries[target] = CredMapEntry { ssid_hash: hash, slot, priority };
+        true
+    }
+    pub fn remove(&mut self, ssid: &str) {
+        let hash = ssid_hash(ssid);
+        for entry in self.entries.iter_mut().filter(|e| e.ssid_hash == hash) {
+            *entry = CredMapEntry::EMPTY;
+        }
+    }
+    /// The credential slot mapped to `ssid`, if any
+    pub fn lookup(&self, ssid: &str) -> Option<u8> {
+        let hash = ssid_hash(ssid);
+        self.entries.iter().find(|e| !e.is_empty() && e.ssid_hash == hash).map(|e| e.slot)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_CRED_MAP_SET.w_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_CRED_MAP_SET.w_words as usize];
+        for (dest, entry) in ret.chunks_exact_mut(3).zip(self.entries.iter()) {
+            dest[..2].copy_from_slice(&u32_to_words(entry.ssid_hash));
+            dest[2] = entry.slot as u16 | (entry.priority as u16) << 8;
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_CRED_MAP_GET.r_words as usize]) -> Self {
+        let mut map = CredMap::new();
+        for (entry, src) in map.entries.iter_mut().zip(data.chunks_exact(3)) {
+            *entry = CredMapEntry {
+                ssid_hash: words_to_u32([src[0], src[1]]),
+                slot: src[2] as u8,
+                priority: (src[2] >> 8) as u8,
+            };
+        }
+        map
+    }
+}
+impl Default for CredMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Largest wake-up threshold the LSM6DS3 can express: 6 bits of 2000mg/64 at the EC's +/-2g full scale
+pub const IMU_WOM_MAX_THRESHOLD_MG: u16 = (63u32 * 2000 / 64) as u16;
+/// Largest wake-up duration the LSM6DS3 can express, in accelerometer samples (2 bits)
+pub const IMU_WOM_MAX_DURATION: u16 = 3;
+
+/// Reasons an ImuWomConfig cannot be programmed into the IMU
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImuWomError {
+    ThresholdTooHigh,
+    DurationTooLong,
+}
+
+/// Payload of IMU_WOM_CONFIG.
+/// Wire format:
+/// - word 0: wake-up threshold in mg, 0 to disarm wake on motion
+/// - word 1: number of consecutive samples above the threshold before INT_IMU_MOTION is raised
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ImuWomConfig {
+    pub threshold_mg: u16,
+    pub duration: u16,
+}
+impl ImuWomConfig {
+    pub const DISABLED: ImuWomConfig = ImuWomConfig { threshold_mg: 0, duration: 0 };
+
+    pub fn new(threshold_mg: u16, duration: u16) -> Result<Self, ImuWomError> {
+        let config = ImuWomConfig { threshold_mg, duration };
+        config.validate()?;
+        Ok(config)
+    }
+    /// Check the config against the limits of the LSM6DS3 wake-up registers.
+    /// The EC should call this on every received config, since decode_u16 does not.
+    pub fn validate(&self) -> Result<(), ImuWomError> {
+        if self.threshold_mg > IMU_WOM_MAX_THRESHOLD_MG {
+            Err(ImuWomError::ThresholdTooHigh)
+        } else if self.duration > IMU_WOM_MAX_DURATION {
+            Err(ImuWomError::DurationTooLong)
+        } else {
+            Ok(())
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.threshold_mg != 0
+    }
+    /// Threshold in WAKE_UP_THS.WK_THS units, rounded to nearest
+    pub fn wake_ths(&self) -> u8 {
+        ((self.threshold_mg.min(IMU_WOM_MAX_THRESHOLD_MG) as u32 * 64 + 1000) / 2000) as u8
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::IMU_WOM_CONFIG.w_words as usize] {
+        [self.threshold_mg, self.duration]
+    }
+    pub fn decode_u16(data: &[u16; ComState::IMU_WOM_CONFIG.w_words as usize]) -> Self {
+        ImuWomConfig { threshold_mg: data[0], duration: data[1] }
+    }
+}
+
+/// Channels the WF200 can scan: 1 to 14 in the 2.4GHz band
+pub const SCAN_CHANNELS_ALL: u16 = 0x3FFF;
+
+/// How a scan finds APs
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum ScanMode {
+    /// send probe requests on each channel; fast, and finds hidden networks
+    Active = 0,
+    /// only listen for beacons; slower, but transmits nothing
+    Passive = 1,
+}
+
+/// Reasons a ScanConfig cannot be applied
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScanConfigError {
+    /// the channel bitmap is empty
+    NoChannels,
+    /// the channel bitmap has bits above channel 14
+    InvalidChannel,
+}
+
+/// Payload of WLAN_SCAN_CONFIG.
+/// Wire format:
+/// - word 0: channel bitmap, bit 0 for channel 1 up to bit 13 for channel 14
+/// - word 1: dwell time per channel in ms, 0 for the EC's default
+/// - word 2: ScanMode
+/// - word 3: largest number of APs to keep records for, 0 for as many as the EC can
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScanConfig {
+    pub channels: u16,
+    pub dwell_ms: u16,
+    pub mode: ScanMode,
+    pub max_results: u16,
+}
+impl ScanConfig {
+    /// What the EC does before WLAN_SCAN_CONFIG is sent
+    pub const DEFAULT: ScanConfig =
+        ScanConfig { channels: SCAN_CHANNELS_ALL, dwell_ms: 0, mode: ScanMode::Active, max_results: 0 };
+
+    /// True if `channel` (1-14) is scanned
+    pub fn has_channel(&self, channel: u8) -> bool {
+        (1..=14).contains(&channel) && self.channels & 1 << (channel - 1) != 0
+    }
+    /// The EC should call this on every received config, since decode_u16 does not.
+    pub fn validate(&self) -> Result<(), ScanConfigError> {
+        if self.channels == 0 {
+            Err(ScanConfigError::NoChannels)
+        } else if self.channels & !SCAN_CHANNELS_ALL != 0 {
+            Err(ScanConfigError::InvalidChannel)
+        } else {
+            Ok(())
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SCAN_CONFIG.w_words as usize] {
+        [self.channels, self.dwell_ms, self.mode as u16, self.max_results]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_SCAN_CONFIG.w_words as usize]) -> Result<Self, SerdesError> {
+        let mode = match data[2] {
+            0 => ScanMode::Active,
+            1 => ScanMode::Passive,
+            value => return Err(SerdesError::InvalidEnumValue { field: "ScanConfig.mode", value }),
+        };
+        Ok(ScanConfig { channels: data[0], dwell_ms: data[1], mode, max_results: data[3] })
+    }
+}
+impl Default for ScanConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Payload of WLAN_ROAM_CONFIG.
+/// Wire format:
+/// - word 0: link Rssi below which the EC starts background scans
+/// - word 1: hysteresis in dB: how much stronger another AP must be to move to it
+/// - word 2: seconds between background scans, 0 to turn roaming off
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RoamConfig {
+    pub trigger_rssi: Rssi,
+    pub hysteresis_db: u16,
+    pub scan_interval_s: u16,
+}
+impl RoamConfig {
+    /// What the EC does before WLAN_ROAM_CONFIG is sent
+    pub const DISABLED: RoamConfig = RoamConfig { trigger_rssi: Rssi::from_dbm(-70), hysteresis_db: 8, scan_interval_s: 0 };
+
+    pub fn is_enabled(&self) -> bool {
+        self.scan_interval_s != 0
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_ROAM_CONFIG.w_words as usize] {
+        [self.trigger_rssi.encode_u16(), self.hysteresis_db, self.scan_interval_s]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_ROAM_CONFIG.w_words as usize]) -> Self {
+        RoamConfig { trigger_rssi: Rssi::decode_u16(data[0]), hysteresis_db: data[1], scan_interval_s: data[2] }
+    }
+}
+impl Default for RoamConfig {
+    fn default() -> Self {
+        Self::DISABLED
+    }
+}
+
+// ScanSummary flags
+pub const SCAN_FLAG_TRUNCATED: Flags16 = Flags16::bit(0); // more APs were seen than the EC could keep records for
+pub const SCAN_FLAG_ABORTED: Flags16 = Flags16::bit(1); // the scan was stopped early, e.g. by SSID_SCAN_OFF or a join
+const _: () = assert!(Flags16::disjoint(&[SCAN_FLAG_TRUNCATED, SCAN_FLAG_ABORTED]));
+
+/// Response of SSID_SCAN_RESULT_SUMMARY, describing the last completed scan.
+/// Wire format:
+/// - word 0: number of distinct APs seen
+/// - word 1: number of records available to fetch
+/// - word 2: SCAN_FLAG_* bits
+/// - word 3: scan duration in ms, saturating
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ScanSummary {
+    pub total_seen: u16,
+    pub available: u16,
+    pub flags: Flags16,
+    pub duration_ms: u16,
+}
+impl ScanSummary {
+    /// True if every AP seen can be fetched: the scan ran to the end and nothing was dropped.
+    /// When false, the UI should offer to scan again.
+    pub fn is_complete(&self) -> bool {
+        !self.flags.contains(SCAN_FLAG_TRUNCATED) && !self.flags.contains(SCAN_FLAG_ABORTED)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::SSID_SCAN_RESULT_SUMMARY.r_words as usize] {
+        [self.total_seen, self.available, self.flags.encode_u16(), self.duration_ms]
+    }
+    pub fn decode_u16(data: &[u16; ComState::SSID_SCAN_RESULT_SUMMARY.r_words as usize]) -> Self {
+        ScanSummary {
+            total_seen: data[0],
+            available: data[1],
+            flags: Flags16::decode_u16(data[2]),
+            duration_ms: data[3],
+        }
+    }
+}
+
+/// Security of an AP, as reported in scan results and WLAN_BIN_STATUS
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Security {
+    Open = 0,
+    Wep = 1,
+    Wpa = 2,
+    Wpa2Psk = 3,
+    Wpa3Sae = 4,
+    /// 802.1X; not supported by the WF200 firmware
+    Enterprise = 5,
+    Unknown = 0xFF,
+}
+impl Security {
+    pub fn decode_u16(security: u16) -> Self {
+        match security {
+            0 => Security::Open,
+            1 => Security::Wep,
+            2 => Security::Wpa,
+            3 => Security::Wpa2Psk,
+            4 => Security::Wpa3Sae,
+            5 => Security::Enterprise,
+            _ => Security::Unknown,
+        }
+    }
+    /// Traffic is unencrypted; the UI should warn before joining
+    pub fn is_open(&self) -> bool {
+        *self == Security::Open
+    }
+    /// The EC can join networks with this security
+    pub fn is_supported(&self) -> bool {
+        !matches!(self, Security::Enterprise | Security::Unknown)
+    }
+}
+
+/// Pack a string of `len` bytes into the StringSer layout
+fn pack_str<const N: usize>(len: u8, bytes: &[u8; N], dest: &mut [u16]) {
+    dest[0] = len as u16;
+    for (dest, src) in dest[1..=N / 2].iter_mut().zip(bytes.chunks_exact(2)) {
+        *dest = u16::from_le_bytes([src[0], src[1]]);
+    }
+}
+/// Unpack a string of up to N bytes from the StringSer layout, without requiring utf-8
+fn unpack_str<const N: usize>(src: &[u16]) -> Result<(u8, [u8; N]), SerdesError> {
+    if src[0] as usize > N {
+        return Err(SerdesError::StrLenTooBig);
+    }
+    let mut bytes = [0u8; N];
+    for (dest, word) in bytes.chunks_exact_mut(2).zip(src[1..=N / 2].iter()) {
+        dest.copy_from_slice(&word.to_le_bytes());
+    }
+    Ok((src[0] as u8, bytes))
+}
+
+/// Number of words in ScanResult
+pub const SCAN_RESULT_WORDS: usize = 22;
+/// Number of ScanResult records per SSID_SCAN_FETCH page
+pub const SCAN_PAGE_LEN: usize = 4;
+
+/// One AP seen by a scan, as returned by SSID_SCAN_FETCH.
+/// Wire format: SCAN_RESULT_WORDS words
+/// - words 0-16: SSID, in the StringSer<STR_32_WORDS> format. SSIDs are arbitrary bytes, not
+///   necessarily utf-8.
+/// - words 17-19: BSSID, packed low byte first
+/// - word 20: Rssi
+/// - word 21: channel number in bits 0-7, Security in bits 8-15
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScanResult {
+    ssid_len: u8,
+    ssid: [u8; STR_32_U8_SIZE],
+    pub bssid: [u8; 6],
+    pub rssi: Rssi,
+    pub channel: u8,
+    pub security: Security,
+}
+impl ScanResult {
+    pub const EMPTY: ScanResult =
+        ScanResult { ssid_len: 0, ssid: [0; STR_32_U8_SIZE], bssid: [0; 6], rssi: Rssi::from_dbm(0), channel: 0, security: Security::Open };
+
+    /// Create a record, truncating the SSID to STR_32_U8_SIZE bytes
+    pub fn new(ssid: &[u8], bssid: [u8; 6], rssi: Rssi, channel: u8, security: Security) -> Self {
+        let len = ssid.len().min(STR_32_U8_SIZE);
+        let mut ret = ScanResult { ssid_len: len as u8, bssid, rssi, channel, security, ..ScanResult::EMPTY };
+        ret.ssid[..len].copy_from_slice(&ssid[..len]);
+        ret
+    }
+    pub fn ssid_bytes(&self) -> &[u8] {
+        &self.ssid[..self.ssid_len as usize]
+    }
+    pub fn ssid(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(self.ssid_bytes()).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn encode_u16(&self) -> [u16; SCAN_RESULT_WORDS] {
+        let mut ret = [0u16; SCAN_RESULT_WORDS];
+        pack_str(self.ssid_len, &self.ssid, &mut ret);
+        for (dest, src) in ret[17..20].iter_mut().zip(self.bssid.chunks_exact(2)) {
+            *dest = u16::from_le_bytes([src[0], src[1]]);
+        }
+        ret[20] = self.rssi.encode_u16();
+        ret[21] = self.channel as u16 | (self.security as u16) << 8;
+        ret
+    }
+    pub fn decode_u16(data: &[u16; SCAN_RESULT_WORDS]) -> Result<Self, SerdesError> {
+        let (ssid_len, ssid) = unpack_str(data)?;
+        let mut ret = ScanResult {
+            ssid_len,
+            ssid,
+            rssi: Rssi::decode_u16(data[20]),
+            channel: data[21] as u8,
+            security: known_enum_value("ScanResult.security", data[21] >> 8)?,
+            ..ScanResult::EMPTY
+        };
+        for (dest, src) in ret.bssid.chunks_exact_mut(2).zip(data[17..20].iter()) {
+            dest.copy_from_slice(&src.to_le_bytes());
+        }
+        Ok(ret)
+    }
+}
+
+// ScanResultPage flags
+pub const SCAN_PAGE_FLAG_MORE: Flags16 = Flags16::bit(0); // records after this page can be fetched now
+pub const SCAN_PAGE_FLAG_IN_PROGRESS: Flags16 = Flags16::bit(1); // the scan is still running, more records may be appended
+const _: () = assert!(Flags16::disjoint(&[SCAN_PAGE_FLAG_MORE, SCAN_PAGE_FLAG_IN_PROGRESS]));
+
+/// Response of SSID_SCAN_FETCH.
+/// Wire format:
+/// - word 0: number of APs in the EC's list so far, across all pages
+/// - word 1: number of records in this page
+/// - word 2: SCAN_PAGE_FLAG_* bits
+/// - word 3: scan id, incremented by the EC each time a new scan replaces the list
+/// - words 4..: SCAN_PAGE_LEN ScanResult records, starting at the requested index; unused records are all zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScanResultPage {
+    pub total: u16,
+    pub count: u16,
+    pub flags: Flags16,
+    pub scan_id: u16,
+    pub records: [ScanResult; SCAN_PAGE_LEN],
+}
+impl ScanResultPage {
+    /// Build the page of `results` starting at record `start`, as done by the EC. SCAN_PAGE_FLAG_MORE
+    /// is set from `results`; the EC adds SCAN_PAGE_FLAG_IN_PROGRESS and the scan id itself.
+    pub fn from_results(results: &[ScanResult], start: u16) -> Self {
+        let mut records = [ScanResult::EMPTY; SCAN_PAGE_LEN];
+        let mut count = 0;
+        for (dest, result) in records.iter_mut().zip(results.iter().skip(start as usize)) {
+            *dest = *result;
+            count += 1;
+        }
+        let mut flags = Flags16::empty();
+        flags.set(SCAN_PAGE_FLAG_MORE, start as usize + (count as usize) < results.len());
+        ScanResultPage { total: results.len() as u16, count, flags, scan_id: 0, records }
+    }
+    /// The populated records of this page
+    pub fn results(&self) -> &[ScanResult] {
+        &self.records[..(self.count as usize).min(SCAN_PAGE_LEN)]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::SSID_SCAN_FETCH.r_words as usize] {
+        let mut ret = [0u16; ComState::SSID_SCAN_FETCH.r_words as usize];
+        ret[0] = self.total;
+        ret[1] = self.count;
+        ret[2] = self.flags.encode_u16();
+        ret[3] = self.scan_id;
+        for (dest, record) in ret[4..].chunks_exact_mut(SCAN_RESULT_WORDS).zip(self.results()) {
+            dest.copy_from_slice(&record.encode_u16());
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::SSID_SCAN_FETCH.r_words as usize]) -> Result<Self, SerdesError> {
+        let mut records = [ScanResult::EMPTY; SCAN_PAGE_LEN];
+        for (record, src) in records.iter_mut().zip(data[4..].chunks_exact(SCAN_RESULT_WORDS)).take(data[1] as usize) {
+            let mut words = [0u16; SCAN_RESULT_WORDS];
+            words.copy_from_slice(src);
+            *record = ScanResult::decode_u16(&words)?;
+        }
+        Ok(ScanResultPage {
+            total: data[0],
+            count: data[1].min(SCAN_PAGE_LEN as u16),
+            flags: Flags16::decode_u16(data[2]),
+            scan_id: data[3],
+            records,
+        })
+    }
+}
+const _: () = assert!(ComState::SSID_SCAN_FETCH.r_words as usize == 4 + SCAN_PAGE_LEN * SCAN_RESULT_WORDS);
+
+/// Response of WLAN_BIN_STATUS_TAGGED, a versioned payload.
+/// Wire format, version 1:
+/// - word 0: format word
+/// - word 1: signal strength of the link, as Rcpi
+/// - word 2: LinkState
+/// - words 3-16: Ipv4Conf
+/// - words 17-33: SSID of the current network, in the StringSer<STR_32_WORDS> format
+/// - word 34: Security of the current network
+///
+/// Version 0 is words 1-33 alone: the response of WLAN_BIN_STATUS, which has no security word.
+#[derive(Debug, Copy, Clone)]
+pub struct WlanBinStatus {
+    pub rcpi: Rcpi,
+    pub link_state: LinkState,
+    pub ipv4: Ipv4Conf,
+    ssid_len: u8,
+    ssid: [u8; STR_32_U8_SIZE],
+    pub security: Security,
+}
+/// Words of a version 0 WlanBinStatus
+const WLAN_BIN_STATUS_V0_WORDS: usize = 2 + 14 + STR_32_WORDS;
+impl WlanBinStatus {
+    pub fn new(rcpi: Rcpi, link_state: LinkState, ipv4: Ipv4Conf, ssid: &[u8], security: Security) -> Self {
+        let len = ssid.len().min(STR_32_U8_SIZE);
+        let mut ret = WlanBinStatus { rcpi, link_state, ipv4, ssid_len: len as u8, ssid: [0; STR_32_U8_SIZE], security };
+        ret.ssid[..len].copy_from_slice(&ssid[..len]);
+        ret
+    }
+    pub fn ssid_bytes(&self) -> &[u8] {
+        &self.ssid[..self.ssid_len as usize]
+    }
+    pub fn ssid(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(self.ssid_bytes()).map_err(|_| SerdesError::Utf8Decode)
+    }
+    /// Encode the untagged version 0 layout of WLAN_BIN_STATUS, which drops the security
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_BIN_STATUS.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_BIN_STATUS.r_words as usize];
+        ret[0] = self.rcpi.encode_u16();
+        ret[1] = self.link_state as u16;
+        ret[2..16].copy_from_slice(&self.ipv4.encode_u16());
+        pack_str(self.ssid_len, &self.ssid, &mut ret[16..33]);
+        ret
+    }
+    /// Encode with a format word, for WLAN_BIN_STATUS_TAGGED
+    pub fn encode_versioned(&self) -> [u16; ComState::WLAN_BIN_STATUS_TAGGED.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_BIN_STATUS_TAGGED.r_words as usize];
+        ret[0] = format_tag(Self::FORMAT_VERSION);
+        ret[1..=WLAN_BIN_STATUS_V0_WORDS].copy_from_slice(&self.encode_u16());
+        ret[1 + WLAN_BIN_STATUS_V0_WORDS] = self.security as u16;
+        ret
+    }
+}
+impl VersionedDecode for WlanBinStatus {
+    const FORMAT_VERSION: u8 = 1;
+    fn decode_version(version: u8, body: &[u16]) -> Result<Self, SerdesError> {
+        // version 1 appended the security word to the version 0 layout
+        let data = need(body, WLAN_BIN_STATUS_V0_WORDS + (version >= 1) as usize)?;
+        let mut ipv4 = [0u16; 14];
+        ipv4.copy_from_slice(&data[2..16]);
+        let (ssid_len, ssid) = unpack_str(&data[16..])?;
+        let security = match data.get(WLAN_BIN_STATUS_V0_WORDS) {
+            Some(&word) => known_enum_value("WlanBinStatus.security", word)?,
+            None => Security::Unknown,
+        };
+        Ok(WlanBinStatus {
+            rcpi: Rcpi::decode_u16(data[0])?,
+            link_state: known_enum_value("WlanBinStatus.link_state", data[1])?,
+            ipv4: Ipv4Conf::decode_version(Ipv4Conf::FORMAT_VERSION, &ipv4)?,
+            ssid_len,
+            ssid,
+            security,
+        })
+    }
+}
+const _: () = assert!(ComState::WLAN_BIN_STATUS.r_words as usize == WLAN_BIN_STATUS_V0_WORDS);
+const _: () = assert!(ComState::WLAN_BIN_STATUS_TAGGED.r_words as usize == 1 + WLAN_BIN_STATUS_V0_WORDS + 1);
+
+/// Response of WLAN_GET_ERRCOUNTS: error counters of the WF200 driver, cumulative since the EC booted.
+/// Counters saturate at 0xFFFF.
+/// Wire format: one word per counter, in field order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct WlanErrCounts {
+    /// frames the WF200 failed to send
+    pub tx_errors: u16,
+    /// frames received but dropped, because they were malformed or the receive queue was full
+    pub rx_drops: u16,
+    /// times the driver reset the WF200 after it stopped responding
+    pub driver_resets: u16,
+    /// frame buffers the EC failed to allocate
+    pub alloc_fails: u16,
+}
+impl WlanErrCounts {
+    /// True if no error was counted
+    pub fn is_clean(&self) -> bool {
+        *self == WlanErrCounts::default()
+    }
+    /// The errors counted between `earlier` and this snapshot; saturated counters count as unchanged
+    pub fn since(&self, earlier: &WlanErrCounts) -> WlanErrCounts {
+        WlanErrCounts {
+            tx_errors: self.tx_errors.saturating_sub(earlier.tx_errors),
+            rx_drops: self.rx_drops.saturating_sub(earlier.rx_drops),
+            driver_resets: self.driver_resets.saturating_sub(earlier.driver_resets),
+            alloc_fails: self.alloc_fails.saturating_sub(earlier.alloc_fails),
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_GET_ERRCOUNTS.r_words as usize] {
+        [self.tx_errors, self.rx_drops, self.driver_resets, self.alloc_fails]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_ERRCOUNTS.r_words as usize]) -> Self {
+        WlanErrCounts { tx_errors: data[0], rx_drops: data[1], driver_resets: data[2], alloc_fails: data[3] }
+    }
+}
+
+/// Per-frame receive metadata, prefixed to fetched frames when NET_FRAME_FLAG_RX_META is set.
+/// Wire format: RX_META_WORDS words
+/// - word 0: Rssi of the frame
+/// - word 1: channel number in bits 0-7; bits 8-15 are reserved and zero
+/// - words 2-3: EC uptime in microseconds when the frame was received, low word first (wraps after ~71 minutes)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct RxMeta {
+    pub rssi: Rssi,
+    pub channel: u8,
+    pub timestamp_us: u32,
+}
+/// Number of words in RxMeta
+pub const RX_META_WORDS: usize = 4;
+impl RxMeta {
+    pub fn encode_u16(&self) -> [u16; RX_META_WORDS] {
+        let [timestamp_lo, timestamp_hi] = u32_to_words(self.timestamp_us);
+        [self.rssi.encode_u16(), self.channel as u16, timestamp_lo, timestamp_hi]
+    }
+    pub fn decode_u16(data: &[u16; RX_META_WORDS]) -> Self {
+        RxMeta {
+            rssi: Rssi::decode_u16(data[0]),
+            channel: data[1] as u8,
+            timestamp_us: words_to_u32([data[2], data[3]]),
+        }
+    }
+}
+
+/// 802.11 access category of a sent frame, carried in the word that starts each NET_FRAME_SEND
+/// frame when NET_FRAME_FLAG_TX_PRIORITY is set. The EC queues frames per category and serves
+/// higher categories first, so e.g. audio is not held up behind a bulk transfer.
+/// Wire format: one word, the WMM access category index
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[repr(u16)]
+pub enum Priority {
+    #[default]
+    BestEffort = 0,
+    Background = 1,
+    Video = 2,
+    Voice = 3,
+}
+impl Priority {
+    /// The category of an 802.1D user priority (0-7), as mapped by WMM
+    pub fn from_user_priority(up: u8) -> Self {
+        match up & 7 {
+            1 | 2 => Priority::Background,
+            0 | 3 => Priority::BestEffort,
+            4 | 5 => Priority::Video,
+            _ => Priority::Voice,
+        }
+    }
+    pub fn encode_u16(&self) -> u16 {
+        *self as u16
+    }
+    pub fn decode_u16(value: u16) -> Result<Self, SerdesError> {
+        match value {
+            0 => Ok(Priority::BestEffort),
+            1 => Ok(Priority::Background),
+            2 => Ok(Priority::Video),
+            3 => Ok(Priority::Voice),
+            _ => Err(SerdesError::InvalidEnumValue { field: "Priority", value }),
+        }
+    }
+}
+
+/// Outcome of a transmitted frame
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[repr(u16)]
+pub enum TxStatus {
+    /// acknowledged by the peer, or sent if no ack is expected
+    Ok = 0,
+    /// no ack after the maximum number of retries
+    RetryLimit = 1,
+    /// queued for longer than the frame lifetime
+    Expired = 2,
+    /// dropped because the WF200 queue was full
+    QueueFull = 3,
+    /// dropped because the link went down
+    NotConnected = 4,
+    /// any other failure reported by the WF200
+    Other = 0xFF,
+}
+impl TxStatus {
+    pub fn decode_u16(status: u16) -> Self {
+        match status {
+            0 => TxStatus::Ok,
+            1 => TxStatus::RetryLimit,
+            2 => TxStatus::Expired,
+            3 => TxStatus::QueueFull,
+            4 => TxStatus::NotConnected,
+            _ => TxStatus::Other,
+        }
+    }
+}
+
+/// Number of records per NET_TX_STATUS page
+pub const TX_STATUS_PAGE_LEN: usize = 8;
+
+/// Completion record of one transmitted frame.
+/// Wire format: 2 words
+/// - word 0: frame handle (see NET_TX_STATUS)
+/// - word 1: TxStatus in bits 0-7, number of retries in bits 8-15 (saturating)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TxCompletion {
+    pub handle: u16,
+    pub status: TxStatus,
+    pub retries: u8,
+}
+impl TxCompletion {
+    pub fn encode_u16(&self) -> [u16; 2] {
+        [self.handle, (self.status as u16 & 0xFF) | (self.retries as u16) << 8]
+    }
+    pub fn decode_u16(data: &[u16; 2]) -> Self {
+        TxCompletion {
+            handle: data[0],
+            status: TxStatus::decode_u16(data[1] & 0xFF),
+            retries: (data[1] >> 8) as u8,
+        }
+    }
+}
+
+/// Response of NET_TX_STATUS.
+/// Wire format:
+/// - word 0: number of valid records in this page in bits 0-7, number still queued after it in bits 8-15 (saturating)
+/// - words 1..: TX_STATUS_PAGE_LEN TxCompletions, oldest first; unused slots are zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TxStatusPage {
+    pub count: u8,
+    pub pending: u8,
+    pub records: [TxCompletion; TX_STATUS_PAGE_LEN],
+}
+impl TxStatusPage {
+    pub const EMPTY_RECORD: TxCompletion = TxCompletion { handle: 0, status: TxStatus::Ok, retries: 0 };
+
+    pub fn valid(&self) -> &[TxCompletion] {
+        &self.records[..(self.count as usize).min(TX_STATUS_PAGE_LEN)]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_TX_STATUS.r_words as usize] {
+        let mut ret = [0u16; ComState::NET_TX_STATUS.r_words as usize];
+        ret[0] = self.count as u16 | (self.pending as u16) << 8;
+        for (dest, record) in ret[1..].chunks_exact_mut(2).zip(self.valid().iter()) {
+            dest.copy_from_slice(&record.encode_u16());
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_TX_STATUS.r_words as usize]) -> Self {
+        let count = (data[0] as u8).min(TX_STATUS_PAGE_LEN as u8);
+        let mut records = [Self::EMPTY_RECORD; TX_STATUS_PAGE_LEN];
+        for (record, src) in records.iter_mut().zip(data[1..].chunks_exact(2)).take(count as usize) {
+            *record = TxCompletion::decode_u16(&[src[0], src[1]]);
+        }
+        TxStatusPage {
+            count,
+            pending: (data[0] >> 8) as u8,
+            records,
+        }
+    }
+}
+
+/// Pack NET_FRAME bytes into words, MSB first: byte 0 goes in the high byte of word 0, and an odd
+/// final byte is padded with 0 in the low byte. Note this is the opposite of the string packing
+/// used by StringSer. Returns the number of words written. This is the one MSB-first packer;
+/// WordWriter::put_bytes_be and frame::FrameWriter are built on it.
+pub fn pack_frame_words(bytes: &[u8], words: &mut [u16]) -> Result<usize, SerdesError> {
+    let len = bytes.len().div_ceil(2);
+    let actual = words.len();
+    let words = words.get_mut(..len).ok_or(SerdesError::BufferTooShort { expected: len, actual })?;
+    for (word, pair) in words.iter_mut().zip(bytes.chunks(2)) {
+        *word = u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]);
+    }
+    Ok(len)
+}
+
+/// Unpack `len` bytes of a NET_FRAME from MSB-first words, dropping the padding byte of an odd
+/// length. Returns the number of bytes written; fails if `words` holds fewer than `len` bytes
+/// or `bytes` is too short.
+pub fn unpack_frame_bytes(words: &[u16], len: usize, bytes: &mut [u8]) -> Result<usize, SerdesError> {
+    let (expected, actual) = (len.div_ceil(2), words.len());
+    let words = words.get(..expected).ok_or(SerdesError::BufferTooShort { expected, actual })?;
+    let actual = bytes.len();
+    let bytes = bytes.get_mut(..len).ok_or(SerdesError::BufferTooShort { expected: len, actual })?;
+    for (pair, word) in bytes.chunks_mut(2).zip(words.iter()) {
+        let be = word.to_be_bytes();
+        pair.copy_from_slice(&be[..pair.len()]);
+    }
+    Ok(len)
+}
+
+/// Cursor for building a payload field by field, instead of by index arithmetic.
+/// u32 values are written in the canonical order of `u32_to_words`. Byte strings are packed two per word, either low byte
+/// first (`put_bytes_le`, as StringSer does) or high byte first (`put_bytes_be`, as NET_FRAME
+/// data is); an odd final byte is padded with 0.
+pub struct WordWriter<'a> {
+    buf: &'a mut [u16],
+    pos: usize,
+}
+impl<'a> WordWriter<'a> {
+    pub fn new(buf: &'a mut [u16]) -> Self {
+        WordWriter { buf, pos: 0 }
+    }
+    /// Number of words written so far
+    pub fn len(&self) -> usize {
+        self.pos
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pos == 0
+    }
+    /// The words written so far
+    pub fn written(&self) -> &[u16] {
+        &self.buf[..self.pos]
+    }
+    fn take(&mut self, n: usize) -> Result<&mut [u16], SerdesError> {
+        let (expected, actual) = (self.pos + n, self.buf.len());
+        let dest = self.buf.get_mut(self.pos..expected).ok_or(SerdesError::BufferTooShort { expected, actual })?;
+        self.pos += n;
+        Ok(dest)
+    }
+    pub fn put_u16(&mut self, value: u16) -> Result<(), SerdesError> {
+        self.take(1)?[0] = value;
+        Ok(())
+    }
+    pub fn put_u32(&mut self, value: u32) -> Result<(), SerdesError> {
+        self.take(2)?.copy_from_slice(&u32_to_words(value));
+        Ok(())
+    }
+    pub fn put_bytes_le(&mut self, bytes: &[u8]) -> Result<(), SerdesError> {
+        for (word, pair) in self.take(bytes.len().div_ceil(2))?.iter_mut().zip(bytes.chunks(2)) {
+            *word = u16::from_le_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]);
+        }
+        Ok(())
+    }
+    pub fn put_bytes_be(&mut self, bytes: &[u8]) -> Result<(), SerdesError> {
+        pack_frame_words(bytes, self.take(bytes.len().div_ceil(2))?)?;
+        Ok(())
+    }
+}
+
+/// Cursor for decoding a payload field by field; the counterpart of WordWriter.
+pub struct WordReader<'a> {
+    buf: &'a [u16],
+    pos: usize,
+}
+impl<'a> WordReader<'a> {
+    pub fn new(buf: &'a [u16]) -> Self {
+        WordReader { buf, pos: 0 }
+    }
+    /// Number of words not yet read
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+    fn take(&mut self, n: usize) -> Result<&'a [u16], SerdesError> {
+        let expected = self.pos + n;
+        let src = self.buf.get(self.pos..expected).ok_or(SerdesError::BufferTooShort { expected, actual: self.buf.len() })?;
+        self.pos += n;
+        Ok(src)
+    }
+    pub fn get_u16(&mut self) -> Result<u16, SerdesError> {
+        Ok(self.take(1)?[0])
+    }
+    pub fn get_u32(&mut self) -> Result<u32, SerdesError> {
+        let src = self.take(2)?;
+        Ok(words_to_u32([src[0], src[1]]))
+    }
+    /// Fill `bytes` from words packed low byte first
+    pub fn get_bytes_le(&mut self, bytes: &mut [u8]) -> Result<(), SerdesError> {
+        let src = self.take(bytes.len().div_ceil(2))?;
+        for (pair, word) in bytes.chunks_mut(2).zip(src) {
+            pair.copy_from_slice(&word.to_le_bytes()[..pair.len()]);
+        }
+        Ok(())
+    }
+    /// Fill `bytes` from words packed high byte first
+    pub fn get_bytes_be(&mut self, bytes: &mut [u8]) -> Result<(), SerdesError> {
+        let src = self.take(bytes.len().div_ceil(2))?;
+        for (pair, word) in bytes.chunks_mut(2).zip(src) {
+            pair.copy_from_slice(&word.to_be_bytes()[..pair.len()]);
+        }
+        Ok(())
+    }
+}
+
+/// Payload of FLASH_ERASE: erase `len` bytes of EC flash starting at `addr`.
+/// Wire format: addr, then len, each 2 words low word first
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FlashRange {
+    pub addr: u32,
+    pub len: u32,
+}
+impl FlashRange {
+    pub fn encode_u16(&self) -> [u16; ComState::FLASH_ERASE.w_words as usize] {
+        let [a0, a1] = u32_to_words(self.addr);
+        let [l0, l1] = u32_to_words(self.len);
+        [a0, a1, l0, l1]
+    }
+    pub fn decode_u16(data: &[u16; ComState::FLASH_ERASE.w_words as usize]) -> Self {
+        FlashRange { addr: words_to_u32([data[0], data[1]]), len: words_to_u32([data[2], data[3]]) }
+    }
+}
+
+/// Words of an encoded MacAddress
+pub const MAC_ADDRESS_WORDS: usize = 3;
+
+/// A 48-bit IEEE MAC address, e.g. a BSSID or the address of this device.
+/// Wire format: 3 words, bytes packed low byte first, as the BSSID of ScanResult
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct MacAddress(pub [u8; 6]);
+impl MacAddress {
+    /// A locally administered unicast address made from random bytes, e.g. from the TRNG, for a
+    /// per-network address that cannot be tracked back to the device
+    pub fn random_local(random: [u8; 6]) -> Self {
+        let mut ret = MacAddress(random);
+        ret.0[0] = (ret.0[0] | 0b10) & !0b01;
+        ret
+    }
+    /// False for the all-zero address and for group addresses, which no station or AP uses
+    pub fn is_valid(&self) -> bool {
+        self.0 != [0; 6] && self.0[0] & 1 == 0
+    }
+    /// The group address an IPv4 multicast group is received on, e.g. 01:00:5e:00:00:fb for
+    /// mDNS on 224.0.0.251
+    pub fn from_ipv4_multicast(group: [u8; 4]) -> Self {
+        MacAddress([0x01, 0x00, 0x5E, group[1] & 0x7F, group[2], group[3]])
+    }
+    /// True for group addresses, including broadcast
+    pub fn is_multicast(&self) -> bool {
+        self.0[0] & 1 != 0
+    }
+    /// True for addresses not assigned by the IEEE to a manufacturer, such as random_local ones
+    pub fn is_locally_administered(&self) -> bool {
+        self.0[0] & 0b10 != 0
+    }
+    pub fn encode_u16(&self) -> [u16; MAC_ADDRESS_WORDS] {
+        let mut ret = [0u16; MAC_ADDRESS_WORDS];
+        for (dest, src) in ret.iter_mut().zip(self.0.chunks_exact(2)) {
+            *dest = u16::from_le_bytes([src[0], src[1]]);
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; MAC_ADDRESS_WORDS]) -> Self {
+        let mut ret = MacAddress([0; 6]);
+        for (dest, src) in ret.0.chunks_exact_mut(2).zip(data.iter()) {
+            dest.copy_from_slice(&src.to_le_bytes());
+        }
+        ret
+    }
+}
+impl From<[u8; 6]> for MacAddress {
+    fn from(mac: [u8; 6]) -> Self {
+        MacAddress(mac)
+    }
+}
+impl core::fmt::Display for MacAddress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let [a, b, c, d, e, g] = self.0;
+        write!(f, "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}", a, b, c, d, e, g)
+    }
+}
+const _: () = assert!(ComState::WLAN_SET_BSSID.w_words as usize == MAC_ADDRESS_WORDS);
+
+/// Number of entries in the multicast filter table
+pub const MCAST_FILTER_LEN: usize = 8;
+
+/// Response of WLAN_MCAST_LIST.
+/// Wire format: number of entries, then MCAST_FILTER_LEN MacAddresses, unused ones zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct McastFilterTable {
+    pub count: u16,
+    pub groups: [MacAddress; MCAST_FILTER_LEN],
+}
+impl McastFilterTable {
+    pub const fn new() -> Self {
+        McastFilterTable { count: 0, groups: [MacAddress([0; 6]); MCAST_FILTER_LEN] }
+    }
+    /// The populated entries
+    pub fn entries(&self) -> &[MacAddress] {
+        &self.groups[..(self.count as usize).min(MCAST_FILTER_LEN)]
+    }
+    pub fn contains(&self, group: &MacAddress) -> bool {
+        self.entries().contains(group)
+    }
+    /// Add a group as MCAST_ADD does on the EC. Returns false if the table is full or `group` is
+    /// not a group address.
+    pub fn insert(&mut self, group: MacAddress) -> bool {
+        if self.contains(&group) {
+            return true;
+        }
+        if !group.is_multicast() || self.entries().len() == MCAST_FILTER_LEN {
+            return false;
+        }
+        self.groups[self.entries().len()] = group;
+        self.count = self.entries().len() as u16 + 1;
+        true
+    }
+    /// Remove a group as MCAST_REMOVE does on the EC, keeping the other entries in order
+    pub fn remove(&mut self, group: &MacAddress) {
+        let len = self.entries().len();
+        if let Some(i) = self.entries().iter().position(|g| g == group) {
+            self.groups.copy_within(i + 1..len, i);
+            self.groups[len - 1] = MacAddress([0; 6]);
+            self.count = len as u16 - 1;
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_MCAST_LIST.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_MCAST_LIST.r_words as usize];
+        ret[0] = self.entries().len() as u16;
+        for (dest, group) in ret[1..].chunks_exact_mut(MAC_ADDRESS_WORDS).zip(self.entries()) {
+            dest.copy_from_slice(&group.encode_u16());
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_MCAST_LIST.r_words as usize]) -> Self {
+        let mut table = McastFilterTable::new();
+        table.count = data[0].min(MCAST_FILTER_LEN as u16);
+        for (group, src) in table.groups.iter_mut().zip(data[1..].chunks_exact(MAC_ADDRESS_WORDS)).take(table.count as usize) {
+            *group = MacAddress::decode_u16(&[src[0], src[1], src[2]]);
+        }
+        table
+    }
+}
+impl Default for McastFilterTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+const _: () = assert!(ComState::WLAN_MCAST_LIST.r_words as usize == 1 + MCAST_FILTER_LEN * MAC_ADDRESS_WORDS);
+
+// MacConfig flags
+pub const MAC_FLAG_FACTORY: Flags16 = Flags16::bit(0); // the factory-programmed address, whatever the MAC words say
+const _: () = assert!(Flags16::disjoint(&[MAC_FLAG_FACTORY]));
+
+/// Payload of WLAN_SET_MAC and response of WLAN_GET_MAC.
+/// Wire format:
+/// - word 0: MAC_FLAG_* bits
+/// - words 1-3: MacAddress
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MacConfig {
+    pub flags: Flags16,
+    pub mac: MacAddress,
+}
+impl MacConfig {
+    /// Restore the factory-programmed address
+    pub const FACTORY: MacConfig = MacConfig { flags: MAC_FLAG_FACTORY, mac: MacAddress([0; 6]) };
+
+    /// Use `mac` instead of the factory-programmed address
+    pub fn with_address(mac: MacAddress) -> Self {
+        MacConfig { flags: Flags16::empty(), mac }
+    }
+    pub fn is_factory(&self) -> bool {
+        self.flags.contains(MAC_FLAG_FACTORY)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_MAC.w_words as usize] {
+        let [m0, m1, m2] = self.mac.encode_u16();
+        [self.flags.encode_u16(), m0, m1, m2]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_MAC.r_words as usize]) -> Self {
+        MacConfig { flags: Flags16::decode_u16(data[0]), mac: MacAddress::decode_u16(&[data[1], data[2], data[3]]) }
+    }
+}
+
+/// Number of known-network slots on the EC
+pub const KNOWN_NET_SLOTS: usize = 8;
+/// Slot argument of WLAN_KNOWN_NET_DELETE that deletes every known network
+pub const KNOWN_NET_DELETE_ALL: u16 = 0xFFFF;
+
+// KnownNetwork flags
+pub const KNOWN_NET_FLAG_AUTOJOIN: Flags16 = Flags16::bit(0); // the EC may join this network without being asked
+pub const KNOWN_NET_FLAG_HIDDEN: Flags16 = Flags16::bit(1); // the SSID is not broadcast; probe for it by name
+const _: () = assert!(Flags16::disjoint(&[KNOWN_NET_FLAG_AUTOJOIN, KNOWN_NET_FLAG_HIDDEN]));
+
+/// Payload of WLAN_KNOWN_NET_STORE.
+/// Wire format:
+/// - word 0: slot, below KNOWN_NET_SLOTS
+/// - word 1: KNOWN_NET_FLAG_* bits
+/// - words 2-18: SSID, in the StringSer<STR_32_WORDS> format
+/// - words 19-51: passphrase, in the StringSer<STR_64_WORDS> format
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct KnownNetwork {
+    pub slot: u8,
+    pub flags: Flags16,
+    ssid_len: u8,
+    ssid: [u8; STR_32_U8_SIZE],
+    pass_len: u8,
+    pass: [u8; STR_64_U8_SIZE],
+}
+impl KnownNetwork {
+    pub fn new(slot: u8, ssid: &str, pass: &str, flags: Flags16) -> Result<Self, SerdesError> {
+        if ssid.len() > STR_32_U8_SIZE || pass.len() > STR_64_U8_SIZE {
+            return Err(SerdesError::StrLenTooBig);
+        }
+        let mut ret = KnownNetwork {
+            slot,
+            flags,
+            ssid_len: ssid.len() as u8,
+            ssid: [0; STR_32_U8_SIZE],
+            pass_len: pass.len() as u8,
+            pass: [0; STR_64_U8_SIZE],
+        };
+        ret.ssid[..ssid.len()].copy_from_slice(ssid.as_bytes());
+        ret.pass[..pass.len()].copy_from_slice(pass.as_bytes());
+        Ok(ret)
+    }
+    pub fn ssid(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.ssid[..self.ssid_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn pass(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.pass[..self.pass_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    /// The WLAN_KNOWN_NET_GET view of this network
+    pub fn info(&self) -> KnownNetworkInfo {
+        KnownNetworkInfo { slot: self.slot, flags: self.flags, ssid_len: self.ssid_len, ssid: self.ssid }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_KNOWN_NET_STORE.w_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_KNOWN_NET_STORE.w_words as usize];
+        ret[0] = self.slot as u16;
+        ret[1] = self.flags.bits();
+        pack_str(self.ssid_len, &self.ssid, &mut ret[2..19]);
+        pack_str(self.pass_len, &self.pass, &mut ret[19..]);
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_KNOWN_NET_STORE.w_words as usize]) -> Result<Self, SerdesError> {
+        let (ssid_len, ssid) = unpack_str(&data[2..19])?;
+        let (pass_len, pass) = unpack_str(&data[19..])?;
+        Ok(KnownNetwork { slot: data[0] as u8, flags: Flags16::from_bits(data[1]), ssid_len, ssid, pass_len, pass })
+    }
+}
+
+// keeps the passphrase out of logs
+impl core::fmt::Debug for KnownNetwork {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KnownNetwork")
+            .field("slot", &self.slot)
+            .field("flags", &self.flags)
+            .field("ssid", &self.ssid())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Response of WLAN_KNOWN_NET_GET.
+/// Wire format:
+/// - word 0: slot, or CRED_SLOT_NONE if the slot is empty
+/// - word 1: KNOWN_NET_FLAG_* bits
+/// - words 2-18: SSID, in the StringSer<STR_32_WORDS> format
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KnownNetworkInfo {
+    pub slot: u8,
+    pub flags: Flags16,
+    ssid_len: u8,
+    ssid: [u8; STR_32_U8_SIZE],
+}
+impl KnownNetworkInfo {
+    pub const EMPTY: KnownNetworkInfo =
+        KnownNetworkInfo { slot: CRED_SLOT_NONE, flags: Flags16::empty(), ssid_len: 0, ssid: [0; STR_32_U8_SIZE] };
+
+    pub fn is_empty(&self) -> bool {
+        self.slot == CRED_SLOT_NONE
+    }
+    pub fn ssid(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.ssid[..self.ssid_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_KNOWN_NET_GET.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_KNOWN_NET_GET.r_words as usize];
+        ret[0] = self.slot as u16;
+        ret[1] = self.flags.bits();
+        pack_str(self.ssid_len, &self.ssid, &mut ret[2..]);
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_KNOWN_NET_GET.r_words as usize]) -> Result<Self, SerdesError> {
+        let (ssid_len, ssid) = unpack_str(&data[2..])?;
+        Ok(KnownNetworkInfo { slot: data[0] as u8, flags: Flags16::from_bits(data[1]), ssid_len, ssid })
+    }
+}
+
+/// Outcome of a join the EC started on its own, the argument of INT_WLAN_AUTOJOIN.
+/// Wire format: slot of the known network in bits 0-7, ConnectResult in bits 8-15
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AutojoinResult {
+    pub slot: u8,
+    pub result: ConnectResult,
+}
+impl AutojoinResult {
+    pub fn encode_u16(&self) -> u16 {
+        self.slot as u16 | (self.result as u16) << 8
+    }
+    pub fn decode_u16(arg: u16) -> Self {
+        AutojoinResult { slot: arg as u8, result: ConnectResult::decode_u16(arg >> 8) }
+    }
+}
+
+/// Response of WLAN_GET_CONNECT_RESULT.
+/// Wire format:
+/// - word 0: ConnectResult, in the one-word form used everywhere else
+/// - word 1: ConnectDetail
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ConnectStatus {
+    pub result: ConnectResult,
+    pub detail: ConnectDetail,
+}
+impl ConnectStatus {
+    /// A status from a one-word ConnectResult, such as the one in AutojoinResult, which carries no detail
+    pub fn from_result(result: ConnectResult) -> Self {
+        ConnectStatus { result, detail: ConnectDetail::None }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_GET_CONNECT_RESULT.r_words as usize] {
+        [self.result as u16, self.detail as u16]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_CONNECT_RESULT.r_words as usize]) -> Result<Self, SerdesError> {
+        Ok(ConnectStatus {
+            result: known_enum_value("ConnectStatus.result", data[0])?,
+            detail: known_enum_value("ConnectStatus.detail", data[1])?,
+        })
+    }
+}
+
+/// Bytes of credential data carried by one WLAN_EAP_FRAGMENT
+pub const EAP_FRAGMENT_LEN: usize = 128;
+/// Largest credential item the EC accepts, in bytes. Fits a CA certificate in DER form.
+pub const EAP_ITEM_MAX_LEN: usize = 4096;
+/// Words of a WLAN_EAP_FRAGMENT payload after the item and sequence words
+const EAP_FRAGMENT_DATA_WORDS: usize = 1 + EAP_FRAGMENT_LEN / 2;
+const _: () = assert!(ComState::WLAN_EAP_FRAGMENT.w_words as usize == 2 + EAP_FRAGMENT_DATA_WORDS);
+
+/// Outer EAP method. Both run MSCHAPv2 inside the tunnel.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum EapMethod {
+    Peap = 0,
+    Ttls = 1,
+}
+
+/// The credential items of an EAP upload, in upload order
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum EapItem {
+    Identity = 0,
+    Password = 1,
+    /// DER certificate of the CA that signed the RADIUS server's certificate
+    CaCert = 2,
+}
+impl EapItem {
+    pub const ALL: [EapItem; 3] = [EapItem::Identity, EapItem::Password, EapItem::CaCert];
+
+    pub fn decode_u16(value: u16) -> Result<Self, SerdesError> {
+        EapItem::ALL
+            .iter()
+            .copied()
+            .find(|item| *item as u16 == value)
+            .ok_or(SerdesError::InvalidEnumValue { field: "EapItem", value })
+    }
+}
+
+// EapConfig flags
+pub const EAP_FLAG_VERIFY_SERVER: Flags16 = Flags16::bit(0); // refuse servers whose certificate does not chain to the CA cert
+const _: () = assert!(Flags16::disjoint(&[EAP_FLAG_VERIFY_SERVER]));
+
+/// Payload of WLAN_EAP_CONFIG.
+/// Wire format:
+/// - word 0: known-network slot the credentials are for
+/// - word 1: EapMethod
+/// - word 2: EAP_FLAG_* bits
+/// - words 3-5: length in bytes of the identity, password and CA cert, in EapItem order
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EapConfig {
+    pub slot: u8,
+    pub method: EapMethod,
+    pub flags: Flags16,
+    pub identity_len: u16,
+    pub password_len: u16,
+    pub ca_cert_len: u16,
+}
+impl EapConfig {
+    /// Length of `item` in bytes
+    pub fn len_of(&self, item: EapItem) -> u16 {
+        match item {
+            EapItem::Identity => self.identity_len,
+            EapItem::Password => self.password_len,
+            EapItem::CaCert => self.ca_cert_len,
+        }
+    }
+    /// Number of fragments `item` is sent in
+    pub fn fragments_of(&self, item: EapItem) -> u16 {
+        (self.len_of(item) as usize).div_ceil(EAP_FRAGMENT_LEN) as u16
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_EAP_CONFIG.w_words as usize] {
+        [
+            self.slot as u16,
+            self.method as u16,
+            self.flags.bits(),
+            self.identity_len,
+            self.password_len,
+            self.ca_cert_len,
+        ]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_EAP_CONFIG.w_words as usize]) -> Result<Self, SerdesError> {
+        let method = match data[1] {
+            0 => EapMethod::Peap,
+            1 => EapMethod::Ttls,
+            value => return Err(SerdesError::InvalidEnumValue { field: "EapConfig.method", value }),
+        };
+        Ok(EapConfig {
+            slot: data[0] as u8,
+            method,
+            flags: Flags16::from_bits(data[2]),
+            identity_len: data[3],
+            password_len: data[4],
+            ca_cert_len: data[5],
+        })
+    }
+}
+
+/// Payload of WLAN_EAP_FRAGMENT: up to EAP_FRAGMENT_LEN bytes of one credential item.
+/// Wire format:
+/// - word 0: EapItem
+/// - word 1: sequence number of the fragment within its item, from 0
+/// - words 2-66: the bytes, in the BytesSer layout
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct EapFragment {
+    pub item: EapItem,
+    pub seq: u16,
+    len: u8,
+    data: [u8; EAP_FRAGMENT_LEN],
+}
+impl EapFragment {
+    /// Create a fragment, truncating `bytes` to EAP_FRAGMENT_LEN
+    pub fn new(item: EapItem, seq: u16, bytes: &[u8]) -> Self {
+        let len = bytes.len().min(EAP_FRAGMENT_LEN);
+        let mut ret = EapFragment { item, seq, len: len as u8, data: [0; EAP_FRAGMENT_LEN] };
+        ret.data[..len].copy_from_slice(&bytes[..len]);
+        ret
+    }
+    pub fn bytes(&self) -> &[u8] {
+        &self.data[..self.len as usize]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_EAP_FRAGMENT.w_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_EAP_FRAGMENT.w_words as usize];
+        ret[0] = self.item as u16;
+        ret[1] = self.seq;
+        pack_str(self.len, &self.data, &mut ret[2..]);
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_EAP_FRAGMENT.w_words as usize]) -> Result<Self, SerdesError> {
+        let (len, bytes) = unpack_str(&data[2..]).map_err(|_| SerdesError::BytesLenTooBig)?;
+        Ok(EapFragment { item: EapItem::decode_u16(data[0])?, seq: data[1], len, data: bytes })
+    }
+}
+// fragments may hold the password
+impl core::fmt::Debug for EapFragment {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EapFragment")
+            .field("item", &self.item)
+            .field("seq", &self.seq)
+            .field("len", &self.len)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Response of WLAN_EAP_COMMIT
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum EapCommitStatus {
+    /// the credentials are stored in the slot
+    Ok = 0,
+    /// no WLAN_EAP_CONFIG since the last commit
+    NoConfig = 1,
+    /// a fragment is missing, or an item's length does not match the config
+    Incomplete = 2,
+    /// a fragment arrived out of sequence
+    BadSequence = 3,
+    /// an item is longer than EAP_ITEM_MAX_LEN, or the slot is out of range
+    TooLarge = 4,
+    /// the CA cert does not parse as DER
+    BadCert = 5,
+}
+impl EapCommitStatus {
+    pub fn decode_u16(value: u16) -> Result<Self, SerdesError> {
+        Ok(match value {
+            0 => EapCommitStatus::Ok,
+            1 => EapCommitStatus::NoConfig,
+            2 => EapCommitStatus::Incomplete,
+            3 => EapCommitStatus::BadSequence,
+            4 => EapCommitStatus::TooLarge,
+            5 => EapCommitStatus::BadCert,
+            value => return Err(SerdesError::InvalidEnumValue { field: "EapCommitStatus", value }),
+        })
+    }
+}
+
+/// An 8-digit WPS PIN. The last digit is a checksum of the first seven.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WpsPin(pub u32);
+impl WpsPin {
+    /// The PIN for seven digits (0-9999999), with their checksum appended
+    pub fn from_digits(digits: u32) -> Self {
+        let digits = digits % 10_000_000;
+        WpsPin(digits * 10 + Self::checksum(digits))
+    }
+    fn checksum(digits: u32) -> u32 {
+        let mut rest = digits;
+        let mut sum = 0;
+        while rest != 0 {
+            sum += 3 * (rest % 10);
+            rest /= 10;
+            sum += rest % 10;
+            rest /= 10;
+        }
+        (10 - sum % 10) % 10
+    }
+    /// Eight digits at most, with a correct checksum
+    pub fn is_valid(&self) -> bool {
+        self.0 < 100_000_000 && self.0 % 10 == Self::checksum(self.0 / 10)
+    }
+}
+impl core::fmt::Display for WpsPin {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:08}", self.0)
+    }
+}
+
+/// Payload of WLAN_WPS_PIN_START.
+/// Wire format: known-network slot to store the credentials in, then the PIN (2 words, low word first)
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WpsPinStart {
+    pub slot: u8,
+    pub pin: WpsPin,
+}
+impl WpsPinStart {
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_WPS_PIN_START.w_words as usize] {
+        let [p0, p1] = u32_to_words(self.pin.0);
+        [self.slot as u16, p0, p1]
+    }
+    /// The PIN is not checked; the EC should refuse a PIN that is not `is_valid`
+    pub fn decode_u16(data: &[u16; ComState::WLAN_WPS_PIN_START.w_words as usize]) -> Self {
+        WpsPinStart { slot: data[0] as u8, pin: WpsPin(words_to_u32([data[1], data[2]])) }
+    }
+}
+
+/// How a WPS session ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u8)]
+pub enum WpsStatus {
+    /// the credentials are stored in the slot
+    Success = 0,
+    /// no AP answered within the walk time
+    Timeout = 1,
+    /// several APs were in push-button mode at once; the user should try again later
+    Overlap = 2,
+    /// the AP did not accept the PIN
+    PinRejected = 3,
+    /// the PIN fails its checksum, or the slot is out of range
+    BadRequest = 4,
+    /// the session failed for any other reason
+    Failed = 0xFF,
+}
+impl WpsStatus {
+    pub fn decode_u16(value: u16) -> Self {
+        match value {
+            0 => WpsStatus::Success,
+            1 => WpsStatus::Timeout,
+            2 => WpsStatus::Overlap,
+            3 => WpsStatus::PinRejected,
+            4 => WpsStatus::BadRequest,
+            _ => WpsStatus::Failed,
+        }
+    }
+}
+
+/// Outcome of a WPS session, the argument of INT_WLAN_WPS.
+/// Wire format: slot in bits 0-7, WpsStatus in bits 8-15
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WpsResult {
+    pub slot: u8,
+    pub status: WpsStatus,
+}
+impl WpsResult {
+    pub fn encode_u16(&self) -> u16 {
+        self.slot as u16 | (self.status as u16) << 8
+    }
+    pub fn decode_u16(arg: u16) -> Self {
+        WpsResult { slot: arg as u8, status: WpsStatus::decode_u16(arg >> 8) }
+    }
+}
+
+/// Largest number of stations the SoftAP accepts
+pub const AP_MAX_CLIENTS: u8 = 8;
+
+// ApConfig flags
+pub const AP_FLAG_HIDDEN: Flags16 = Flags16::bit(0); // do not broadcast the SSID
+pub const AP_FLAG_ISOLATE: Flags16 = Flags16::bit(1); // do not forward frames between stations; all go to the SoC
+const _: () = assert!(Flags16::disjoint(&[AP_FLAG_HIDDEN, AP_FLAG_ISOLATE]));
+
+/// Problems with an ApConfig, found by ApConfig::validate
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ApConfigError {
+    EmptySsid,
+    /// WPA2 passphrases are 8 to 63 bytes; an empty passphrase makes an open network
+    PassLength,
+    /// the channel is not in 1-14
+    InvalidChannel,
+    /// max_clients is 0 or above AP_MAX_CLIENTS
+    InvalidMaxClients,
+}
+
+/// Payload of WLAN_AP_START.
+/// Wire format:
+/// - words 0-16: SSID, in the StringSer<STR_32_WORDS> format
+/// - words 17-49: WPA2 passphrase, in the StringSer<STR_64_WORDS> format; empty for an open network
+/// - word 50: channel in bits 0-7, largest number of stations in bits 8-15
+/// - word 51: AP_FLAG_* bits
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct ApConfig {
+    ssid_len: u8,
+    ssid: [u8; STR_32_U8_SIZE],
+    pass_len: u8,
+    pass: [u8; STR_64_U8_SIZE],
+    pub channel: u8,
+    pub max_clients: u8,
+    pub flags: Flags16,
+}
+impl ApConfig {
+    /// An AP on `channel` that accepts up to AP_MAX_CLIENTS stations
+    pub fn new(ssid: &str, pass: &str, channel: u8) -> Result<Self, SerdesError> {
+        if ssid.len() > STR_32_U8_SIZE || pass.len() > STR_64_U8_SIZE {
+            return Err(SerdesError::StrLenTooBig);
+        }
+        let mut ret = ApConfig {
+            ssid_len: ssid.len() as u8,
+            ssid: [0; STR_32_U8_SIZE],
+            pass_len: pass.len() as u8,
+            pass: [0; STR_64_U8_SIZE],
+            channel,
+            max_clients: AP_MAX_CLIENTS,
+            flags: Flags16::empty(),
+        };
+        ret.ssid[..ssid.len()].copy_from_slice(ssid.as_bytes());
+        ret.pass[..pass.len()].copy_from_slice(pass.as_bytes());
+        Ok(ret)
+    }
+    pub fn ssid(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.ssid[..self.ssid_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn pass(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.pass[..self.pass_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn is_open(&self) -> bool {
+        self.pass_len == 0
+    }
+    /// The EC should call this on every received config, since decode_u16 does not.
+    pub fn validate(&self) -> Result<(), ApConfigError> {
+        if self.ssid_len == 0 {
+            Err(ApConfigError::EmptySsid)
+        } else if self.pass_len != 0 && !(8..=63).contains(&self.pass_len) {
+            Err(ApConfigError::PassLength)
+        } else if !(1..=14).contains(&self.channel) {
+            Err(ApConfigError::InvalidChannel)
+        } else if self.max_clients == 0 || self.max_clients > AP_MAX_CLIENTS {
+            Err(ApConfigError::InvalidMaxClients)
+        } else {
+            Ok(())
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_AP_START.w_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_AP_START.w_words as usize];
+        pack_str(self.ssid_len, &self.ssid, &mut ret[..17]);
+        pack_str(self.pass_len, &self.pass, &mut ret[17..50]);
+        ret[50] = self.channel as u16 | (self.max_clients as u16) << 8;
+        ret[51] = self.flags.encode_u16();
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_AP_START.w_words as usize]) -> Result<Self, SerdesError> {
+        let (ssid_len, ssid) = unpack_str(&data[..17])?;
+        let (pass_len, pass) = unpack_str(&data[17..50])?;
+        Ok(ApConfig {
+            ssid_len,
+            ssid,
+            pass_len,
+            pass,
+            channel: data[50] as u8,
+            max_clients: (data[50] >> 8) as u8,
+            flags: Flags16::decode_u16(data[51]),
+        })
+    }
+}
+// keeps the passphrase out of logs
+impl core::fmt::Debug for ApConfig {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ApConfig")
+            .field("ssid", &self.ssid())
+            .field("channel", &self.channel)
+            .field("max_clients", &self.max_clients)
+            .field("flags", &self.flags)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Number of stations per WLAN_AP_STATIONS page
+pub const AP_STATION_PAGE_LEN: usize = 4;
+
+/// A station associated with the SoftAP.
+/// Wire format: MacAddress (3 words), then Rssi
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ApStation {
+    pub mac: MacAddress,
+    pub rssi: Rssi,
+}
+impl ApStation {
+    pub const EMPTY: ApStation = ApStation { mac: MacAddress([0; 6]), rssi: Rssi::from_dbm(0) };
+}
+
+/// Response of WLAN_AP_STATIONS.
+/// Wire format:
+/// - word 0: number of stations associated, across all pages
+/// - word 1: number of stations in this page
+/// - words 2-17: AP_STATION_PAGE_LEN ApStations of 4 words, unused ones zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ApStationPage {
+    pub total: u16,
+    pub count: u16,
+    pub entries: [ApStation; AP_STATION_PAGE_LEN],
+}
+impl ApStationPage {
+    /// Build the page of `stations` starting at station `start`, as done by the EC
+    pub fn from_stations(stations: &[ApStation], start: u16) -> Self {
+        let mut entries = [ApStation::EMPTY; AP_STATION_PAGE_LEN];
+        let mut count = 0;
+        for (dest, station) in entries.iter_mut().zip(stations.iter().skip(start as usize)) {
+            *dest = *station;
+            count += 1;
+        }
+        ApStationPage { total: stations.len() as u16, count, entries }
+    }
+    /// The populated entries of this page
+    pub fn stations(&self) -> &[ApStation] {
+        &self.entries[..(self.count as usize).min(AP_STATION_PAGE_LEN)]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_AP_STATIONS.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_AP_STATIONS.r_words as usize];
+        ret[0] = self.total;
+        ret[1] = self.count;
+        for (dest, station) in ret[2..].chunks_exact_mut(4).zip(self.stations()) {
+            dest[..3].copy_from_slice(&station.mac.encode_u16());
+            dest[3] = station.rssi.encode_u16();
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_AP_STATIONS.r_words as usize]) -> Self {
+        let count = data[1].min(AP_STATION_PAGE_LEN as u16);
+        let mut entries = [ApStation::EMPTY; AP_STATION_PAGE_LEN];
+        for (station, src) in entries.iter_mut().zip(data[2..].chunks_exact(4)).take(count as usize) {
+            let mac = MacAddress::decode_u16(&[src[0], src[1], src[2]]);
+            *station = ApStation { mac, rssi: Rssi::decode_u16(src[3]) };
+        }
+        ApStationPage { total: data[0], count, entries }
+    }
+}
+const _: () = assert!(ComState::WLAN_AP_STATIONS.r_words as usize == 2 + AP_STATION_PAGE_LEN * 4);
+
+/// An ISO 3166-1 alpha-2 country code naming a regulatory domain, e.g. "CH".
+/// Wire format: one word, first letter in the low byte
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct CountryCode([u8; 2]);
+impl CountryCode {
+    /// The world domain: only what every country allows
+    pub const WORLD: CountryCode = CountryCode(*b"00");
+
+    /// Two ASCII letters, in either case, or "00" for the world domain
+    pub fn new(code: &str) -> Option<Self> {
+        match *code.as_bytes() {
+            [a, b] if a.is_ascii_alphabetic() && b.is_ascii_alphabetic() => {
+                Some(CountryCode([a.to_ascii_uppercase(), b.to_ascii_uppercase()]))
+            }
+            [b'0', b'0'] => Some(CountryCode::WORLD),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> &str {
+        // always two ASCII bytes
+        core::str::from_utf8(&self.0).unwrap_or("00")
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_COUNTRY.w_words as usize] {
+        [u16::from_le_bytes(self.0)]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_COUNTRY.r_words as usize]) -> Result<Self, SerdesError> {
+        let bytes = data[0].to_le_bytes();
+        core::str::from_utf8(&bytes)
+            .ok()
+            .and_then(CountryCode::new)
+            .filter(|code| code.0 == bytes)
+            .ok_or(SerdesError::InvalidEnumValue { field: "CountryCode", value: data[0] })
+    }
+}
+impl core::fmt::Display for CountryCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A transmit power in quarter dBm, within TxPower::MIN..=TxPower::MAX.
+/// Wire format: one word, the quarter dBm as a signed integer
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TxPower(i16);
+impl TxPower {
+    /// 0 dBm
+    pub const MIN: TxPower = TxPower(0);
+    /// 20 dBm, above what the WF200 can put out
+    pub const MAX: TxPower = TxPower(20 * 4);
+
+    pub const fn new(quarter_dbm: i16) -> Option<Self> {
+        if quarter_dbm >= Self::MIN.0 && quarter_dbm <= Self::MAX.0 {
+            Some(TxPower(quarter_dbm))
+        } else {
+            None
+        }
+    }
+    pub const fn from_dbm(dbm: i8) -> Option<Self> {
+        Self::new(dbm as i16 * 4)
+    }
+    pub const fn quarter_dbm(&self) -> i16 {
+        self.0
+    }
+    pub fn to_dbm(&self) -> I8F8 {
+        I8F8(self.0 << (I8F8::FRAC_BITS - 2))
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_TX_POWER.w_words as usize] {
+        [self.0 as u16]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_TX_POWER.r_words as usize]) -> Result<Self, SerdesError> {
+        TxPower::new(data[0] as i16).ok_or(SerdesError::OutOfRange { field: "TxPower", value: data[0] })
+    }
+}
+
+/// Response of WLAN_GET_CHANNELS: the 2.4GHz channels allowed in the current regulatory domain.
+/// Wire format:
+/// - word 0: channel bitmap, as ScanConfig::channels
+/// - words 1-14: TxPower limit of channels 1 to 14, 0 for channels that are not allowed
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ChannelList {
+    pub channels: u16,
+    pub max_power: [TxPower; 14],
+}
+impl ChannelList {
+    /// True if `channel` (1-14) is allowed
+    pub fn is_allowed(&self, channel: u8) -> bool {
+        (1..=14).contains(&channel) && self.channels & 1 << (channel - 1) != 0
+    }
+    /// True if every channel of a bitmap such as ScanConfig::channels is allowed
+    pub fn covers(&self, channels: u16) -> bool {
+        channels & !self.channels == 0
+    }
+    /// Transmit power limit on `channel`, if it is allowed
+    pub fn max_power(&self, channel: u8) -> Option<TxPower> {
+        if self.is_allowed(channel) {
+            Some(self.max_power[channel as usize - 1])
+        } else {
+            None
+        }
+    }
+    /// The allowed channels, in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = u8> + '_ {
+        (1..=14).filter(move |channel| self.is_allowed(*channel))
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_GET_CHANNELS.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_GET_CHANNELS.r_words as usize];
+        ret[0] = self.channels & SCAN_CHANNELS_ALL;
+        for (channel, dest) in (1..=14).zip(ret[1..].iter_mut()) {
+            *dest = self.max_power(channel).map_or(0, |power| power.quarter_dbm() as u16);
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_CHANNELS.r_words as usize]) -> Result<Self, SerdesError> {
+        if data[0] & !SCAN_CHANNELS_ALL != 0 {
+            return Err(SerdesError::OutOfRange { field: "ChannelList.channels", value: data[0] });
+        }
+        let mut max_power = [TxPower::MIN; 14];
+        for (dest, src) in max_power.iter_mut().zip(data[1..].iter()) {
+            *dest = TxPower::decode_u16(&[*src])?;
+        }
+        Ok(ChannelList { channels: data[0], max_power })
+    }
+}
+
+// MonitorConfig flags
+pub const MONITOR_FLAG_MGMT: Flags16 = Flags16::bit(0); // forward management frames (beacons, probes, ...) as well
+const _: () = assert!(Flags16::disjoint(&[MONITOR_FLAG_MGMT]));
+
+/// Payload of WLAN_MONITOR_START.
+/// Wire format:
+/// - word 0: channel to listen on, 1-14
+/// - word 1: MONITOR_FLAG_* bits
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MonitorConfig {
+    pub channel: u8,
+    pub flags: Flags16,
+}
+impl MonitorConfig {
+    pub fn is_valid(&self) -> bool {
+        (1..=14).contains(&self.channel)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_MONITOR_START.w_words as usize] {
+        [self.channel as u16, self.flags.encode_u16()]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_MONITOR_START.w_words as usize]) -> Self {
+        MonitorConfig { channel: data[0] as u8, flags: Flags16::decode_u16(data[1]) }
+    }
+}
+
+/// WF200 power save mode
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum PowerSaveMode {
+    /// the radio stays awake
+    Off = 0,
+    /// dozes between beacons, and stays awake for a while after traffic; little added latency
+    Fast = 1,
+    /// dozes whenever it can and polls the AP for buffered frames; lowest power, highest latency
+    Max = 2,
+}
+
+/// Payload of WLAN_SET_POWER_SAVE and WLAN_GET_POWER_SAVE.
+/// Wire format:
+/// - word 0: PowerSaveMode
+/// - word 1: listen interval in beacon intervals, how often the radio wakes for beacons; 0 for every
+///   DTIM beacon
+/// - word 2: DTIMs to skip: the radio wakes for one DTIM beacon in `dtim_skip + 1`
+///
+/// Broadcast and multicast frames are only delivered after DTIM beacons, so skipping DTIMs saves
+/// power but delays or loses them. Words 1 and 2 are ignored when the mode is Off.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PowerSaveConfig {
+    pub mode: PowerSaveMode,
+    pub listen_interval: u16,
+    pub dtim_skip: u16,
+}
+impl PowerSaveConfig {
+    /// What the EC does before WLAN_SET_POWER_SAVE is sent
+    pub const OFF: PowerSaveConfig = PowerSaveConfig { mode: PowerSaveMode::Off, listen_interval: 0, dtim_skip: 0 };
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != PowerSaveMode::Off
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_POWER_SAVE.w_words as usize] {
+        [self.mode as u16, self.listen_interval, self.dtim_skip]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_SET_POWER_SAVE.w_words as usize]) -> Result<Self, SerdesError> {
+        let mode = match data[0] {
+            0 => PowerSaveMode::Off,
+            1 => PowerSaveMode::Fast,
+            2 => PowerSaveMode::Max,
+            value => return Err(SerdesError::InvalidEnumValue { field: "PowerSaveConfig.mode", value }),
+        };
+        Ok(PowerSaveConfig { mode, listen_interval: data[1], dtim_skip: data[2] })
+    }
+}
+impl Default for PowerSaveConfig {
+    fn default() -> Self {
+        Self::OFF
+    }
+}
+
+/// An 802.11 PHY standard
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PhyStandard {
+    /// 802.11b, DSSS/CCK
+    B,
+    /// 802.11g, OFDM
+    G,
+    /// 802.11n, HT
+    N,
+}
+
+/// Standards the EC may use on the station link, the payload of WLAN_SET_PHY_MODE
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[repr(u16)]
+pub enum PhyMode {
+    #[default]
+    Bgn = 0,
+    Bg = 1,
+    B = 2,
+    G = 3,
+    Gn = 4,
+    N = 5,
+}
+impl PhyMode {
+    pub const ALL: [PhyMode; 6] = [PhyMode::Bgn, PhyMode::Bg, PhyMode::B, PhyMode::G, PhyMode::Gn, PhyMode::N];
+
+    pub fn allows(&self, standard: PhyStandard) -> bool {
+        match standard {
+            PhyStandard::B => matches!(self, PhyMode::Bgn | PhyMode::Bg | PhyMode::B),
+            PhyStandard::G => matches!(self, PhyMode::Bgn | PhyMode::Bg | PhyMode::G | PhyMode::Gn),
+            PhyStandard::N => matches!(self, PhyMode::Bgn | PhyMode::Gn | PhyMode::N),
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_PHY_MODE.w_words as usize] {
+        [*self as u16]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_SET_PHY_MODE.w_words as usize]) -> Result<Self, SerdesError> {
+        PhyMode::ALL
+            .iter()
+            .copied()
+            .find(|mode| *mode as u16 == data[0])
+            .ok_or(SerdesError::InvalidEnumValue { field: "PhyMode", value: data[0] })
+    }
+}
+
+/// A transmit rate, numbered as in the WF200 API. 11n rates are for 20 MHz channels with the
+/// long guard interval, the only ones the WF200 uses.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum PhyRate {
+    B1Mbps = 0,
+    B2Mbps = 1,
+    B5_5Mbps = 2,
+    B11Mbps = 3,
+    G6Mbps = 6,
+    G9Mbps = 7,
+    G12Mbps = 8,
+    G18Mbps = 9,
+    G24Mbps = 10,
+    G36Mbps = 11,
+    G48Mbps = 12,
+    G54Mbps = 13,
+    Mcs0 = 14,
+    Mcs1 = 15,
+    Mcs2 = 16,
+    Mcs3 = 17,
+    Mcs4 = 18,
+    Mcs5 = 19,
+    Mcs6 = 20,
+    Mcs7 = 21,
+}
+impl PhyRate {
+    /// Every rate, slowest first within each standard
+    pub const ALL: [PhyRate; 20] = [
+        PhyRate::B1Mbps,
+        PhyRate::B2Mbps,
+        PhyRate::B5_5Mbps,
+        PhyRate::B11Mbps,
+        PhyRate::G6Mbps,
+        PhyRate::G9Mbps,
+        PhyRate::G12Mbps,
+        PhyRate::G18Mbps,
+        PhyRate::G24Mbps,
+        PhyRate::G36Mbps,
+        PhyRate::G48Mbps,
+        PhyRate::G54Mbps,
+        PhyRate::Mcs0,
+        PhyRate::Mcs1,
+        PhyRate::Mcs2,
+        PhyRate::Mcs3,
+        PhyRate::Mcs4,
+        PhyRate::Mcs5,
+        PhyRate::Mcs6,
+        PhyRate::Mcs7,
+    ];
+
+    pub fn standard(&self) -> PhyStandard {
+        match *self as u16 {
+            0..=3 => PhyStandard::B,
+            6..=13 => PhyStandard::G,
+            _ => PhyStandard::N,
+        }
+    }
+    /// Data rate in kbit/s
+    pub fn kbps(&self) -> u32 {
+        const KBPS: [u32; 22] = [
+            1000, 2000, 5500, 11000, 0, 0, 6000, 9000, 12000, 18000, 24000, 36000, 48000, 54000, 6500, 13000, 19500,
+            26000, 39000, 52000, 58500, 65000,
+        ];
+        KBPS[*self as usize]
+    }
+    pub fn decode_u16(value: u16) -> Result<Self, SerdesError> {
+        PhyRate::ALL
+            .iter()
+            .copied()
+            .find(|rate| *rate as u16 == value)
+            .ok_or(SerdesError::InvalidEnumValue { field: "PhyRate", value })
+    }
+}
+
+/// A set of PhyRates, bit n for the rate numbered n
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct RateSet(pub u32);
+impl RateSet {
+    pub fn contains(&self, rate: PhyRate) -> bool {
+        self.0 & 1 << rate as u16 != 0
+    }
+    pub fn insert(&mut self, rate: PhyRate) {
+        self.0 |= 1 << rate as u16;
+    }
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+    /// The rates of the set, in PhyRate::ALL order
+    pub fn iter(&self) -> impl Iterator<Item = PhyRate> {
+        let set = *self;
+        PhyRate::ALL.iter().copied().filter(move |rate| set.contains(*rate))
+    }
+    /// The most recent standard with a rate in the set
+    pub fn standard(&self) -> Option<PhyStandard> {
+        self.iter().map(|rate| rate.standard()).max()
+    }
+}
+
+/// Response of WLAN_GET_PHY_STATUS.
+/// Wire format:
+/// - word 0: the configured PhyMode
+/// - word 1: PhyRate of the last frame sent, PHY_RATE_NONE if nothing was sent on this link yet
+/// - words 2-3: RateSet negotiated with the AP, low word first; empty while not connected
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PhyStatus {
+    pub mode: PhyMode,
+    pub tx_rate: Option<PhyRate>,
+    pub rates: RateSet,
+}
+/// PhyStatus::tx_rate on the wire when there is none
+pub const PHY_RATE_NONE: u16 = 0xFFFF;
+impl PhyStatus {
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_GET_PHY_STATUS.r_words as usize] {
+        let rates = u32_to_words(self.rates.0);
+        [self.mode as u16, self.tx_rate.map_or(PHY_RATE_NONE, |rate| rate as u16), rates[0], rates[1]]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_PHY_STATUS.r_words as usize]) -> Result<Self, SerdesError> {
+        let tx_rate = match data[1] {
+            PHY_RATE_NONE => None,
+            rate => Some(PhyRate::decode_u16(rate)?),
+        };
+        Ok(PhyStatus { mode: PhyMode::decode_u16(&[data[0]])?, tx_rate, rates: RateSet(words_to_u32([data[2], data[3]])) })
+    }
+}
+
+/// Bytes of the Ethernet header at the start of every NET_FRAME frame
+pub const ETH_HEADER_LEN: u16 = 14;
+
+/// The largest IP packet a NET_FRAME frame carries, in bytes, within Mtu::MIN..=Mtu::MAX.
+/// Wire format: one word, the MTU
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Mtu(u16);
+impl Mtu {
+    /// the smallest MTU IPv6 allows
+    pub const MIN: Mtu = Mtu(1280);
+    /// the largest MTU whose frames fit one length-encoded NET_FRAME verb, whatever prefixes
+    /// NET_FRAME_CONFIG adds to them
+    pub const MAX: Mtu = Mtu(NET_FRAME_MAX_LEN - ETH_HEADER_LEN - frame::frame_prefix_len(frame::NET_FRAME_FLAGS_ALL));
+    /// Ethernet's MTU
+    pub const DEFAULT: Mtu = Mtu(1500);
+
+    pub const fn new(mtu: u16) -> Option<Self> {
+        if mtu >= Self::MIN.0 && mtu <= Self::MAX.0 {
+            Some(Mtu(mtu))
+        } else {
+            None
+        }
+    }
+    pub const fn get(&self) -> u16 {
+        self.0
+    }
+    /// Length of the largest frame, Ethernet header included
+    pub const fn max_frame_len(&self) -> u16 {
+        self.0 + ETH_HEADER_LEN
+    }
+    /// Length of the largest frame with the prefixes of the NET_FRAME_CONFIG `flags`: the
+    /// `len_bytes` to size ComState::net_frame_send and net_frame_fetch buffers with
+    pub const fn max_verb_len(&self, flags: Flags16) -> u16 {
+        self.max_frame_len() + frame::frame_prefix_len(flags)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_SET_MTU.w_words as usize] {
+        [self.0]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_GET_MTU.r_words as usize]) -> Result<Self, SerdesError> {
+        Mtu::new(data[0]).ok_or(SerdesError::OutOfRange { field: "Mtu", value: data[0] })
+    }
+}
+impl Default for Mtu {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// An IPv4 multicast group, the payload of NET_IGMP_JOIN and NET_IGMP_LEAVE.
+/// Wire format: 2 words, the address packed as in Ipv4Conf
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MulticastGroup([u8; 4]);
+impl MulticastGroup {
+    /// 224.0.0.1, which every host is a member of without joining
+    pub const ALL_HOSTS: [u8; 4] = [224, 0, 0, 1];
+
+    /// None unless `addr` is in 224.0.0.0/4 and not ALL_HOSTS
+    pub const fn new(addr: [u8; 4]) -> Option<Self> {
+        if addr[0] & 0xF0 == 224 && !(addr[0] == 224 && addr[1] == 0 && addr[2] == 0 && addr[3] == 1) {
+            Some(MulticastGroup(addr))
+        } else {
+            None
+        }
+    }
+    pub const fn addr(&self) -> [u8; 4] {
+        self.0
+    }
+    /// True for groups in 224.0.0.0/24, e.g. mDNS, which are filtered for but never reported
+    pub fn is_link_local(&self) -> bool {
+        self.0[..3] == [224, 0, 0]
+    }
+    /// The MAC address the group's frames are sent to
+    pub fn mac(&self) -> MacAddress {
+        MacAddress::from_ipv4_multicast(self.0)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_IGMP_JOIN.w_words as usize] {
+        [self.0[0] as u16 | (self.0[1] as u16) << 8, self.0[2] as u16 | (self.0[3] as u16) << 8]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_IGMP_JOIN.w_words as usize]) -> Result<Self, SerdesError> {
+        let addr = [data[0] as u8, (data[0] >> 8) as u8, data[1] as u8, (data[1] >> 8) as u8];
+        MulticastGroup::new(addr).ok_or(SerdesError::OutOfRange { field: "MulticastGroup", value: data[0] })
+    }
+}
+impl core::fmt::Display for MulticastGroup {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}.{}.{}.{}", self.0[0], self.0[1], self.0[2], self.0[3])
+    }
+}
+
+// ArpOffloadConfig flags
+pub const ARP_OFFLOAD_FLAG_ENABLE: Flags16 = Flags16::bit(0); // answer ARP requests for the address
+const _: () = assert!(Flags16::disjoint(&[ARP_OFFLOAD_FLAG_ENABLE]));
+
+/// Payload of NET_ARP_OFFLOAD_CONFIG.
+/// Wire format:
+/// - word 0: ARP_OFFLOAD_FLAG_* bits
+/// - words 1-2: the SoC's IPv4 address, packed as in Ipv4Conf
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ArpOffloadConfig {
+    pub flags: Flags16,
+    pub addr: [u8; 4],
+}
+impl ArpOffloadConfig {
+    /// Answer ARP requests for `addr`, e.g. Ipv4Conf::addr once DHCP is bound
+    pub fn enabled(addr: [u8; 4]) -> Self {
+        ArpOffloadConfig { flags: Flags16::empty().with(ARP_OFFLOAD_FLAG_ENABLE), addr }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.flags.contains(ARP_OFFLOAD_FLAG_ENABLE)
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_ARP_OFFLOAD_CONFIG.w_words as usize] {
+        [
+            self.flags.encode_u16(),
+            self.addr[0] as u16 | (self.addr[1] as u16) << 8,
+            self.addr[2] as u16 | (self.addr[3] as u16) << 8,
+        ]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_ARP_OFFLOAD_CONFIG.w_words as usize]) -> Self {
+        ArpOffloadConfig {
+            flags: Flags16::decode_u16(data[0]),
+            addr: [data[1] as u8, (data[1] >> 8) as u8, data[2] as u8, (data[2] >> 8) as u8],
+        }
+    }
+}
+
+/// Response of NET_ARP_OFFLOAD_STATUS.
+/// Wire format:
+/// - words 0-2: the ArpOffloadConfig in effect
+/// - words 3-4: ARP requests the EC has answered since the config was set, low word first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ArpOffloadStatus {
+    pub config: ArpOffloadConfig,
+    pub answered: u32,
+}
+impl ArpOffloadStatus {
+    pub fn encode_u16(&self) -> [u16; ComState::NET_ARP_OFFLOAD_STATUS.r_words as usize] {
+        let [c0, c1, c2] = self.config.encode_u16();
+        let [a0, a1] = u32_to_words(self.answered);
+        [c0, c1, c2, a0, a1]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_ARP_OFFLOAD_STATUS.r_words as usize]) -> Self {
+        ArpOffloadStatus {
+            config: ArpOffloadConfig::decode_u16(&[data[0], data[1], data[2]]),
+            answered: words_to_u32([data[3], data[4]]),
+        }
+    }
+}
+
+/// Number of wake pattern slots
+pub const WAKE_PATTERN_SLOTS: usize = 8;
+/// Words of one pattern in WakePatternEntry and WakePatternTable
+pub const WAKE_PATTERN_WORDS: usize = 3;
+
+/// A received frame that wakes the SoC.
+/// Wire format: WAKE_PATTERN_WORDS words, the kind (0 for an empty slot), then its argument:
+/// - 1, MagicPacket: unused
+/// - 2, TcpSyn: the port in word 1
+/// - 3, SourceIp: the address in words 1-2, packed as in Ipv4Conf
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WakePattern {
+    /// a Wake-on-LAN magic packet for the station's MAC address
+    MagicPacket,
+    /// a TCP SYN to the device's IPv4 address on this port
+    TcpSyn { port: u16 },
+    /// any IPv4 packet from this address
+    SourceIp([u8; 4]),
+}
+impl WakePattern {
+    pub fn encode_u16(pattern: Option<&WakePattern>) -> [u16; WAKE_PATTERN_WORDS] {
+        match pattern {
+            None => [0, 0, 0],
+            Some(WakePattern::MagicPacket) => [1, 0, 0],
+            Some(WakePattern::TcpSyn { port }) => [2, *port, 0],
+            Some(WakePattern::SourceIp(addr)) => {
+                [3, addr[0] as u16 | (addr[1] as u16) << 8, addr[2] as u16 | (addr[3] as u16) << 8]
+            }
+        }
+    }
+    pub fn decode_u16(data: &[u16; WAKE_PATTERN_WORDS]) -> Result<Option<Self>, SerdesError> {
+        match data[0] {
+            0 => Ok(None),
+            1 => Ok(Some(WakePattern::MagicPacket)),
+            2 => Ok(Some(WakePattern::TcpSyn { port: data[1] })),
+            3 => Ok(Some(WakePattern::SourceIp([
+                data[1] as u8,
+                (data[1] >> 8) as u8,
+                data[2] as u8,
+                (data[2] >> 8) as u8,
+            ]))),
+            value => Err(SerdesError::InvalidEnumValue { field: "WakePattern", value }),
+        }
+    }
+}
+
+/// Payload of NET_WAKE_SET: a pattern for a slot, or None to clear it.
+/// Wire format: the slot, then the pattern as in WakePattern
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WakePatternEntry {
+    pub slot: u8,
+    pub pattern: Option<WakePattern>,
+}
+impl WakePatternEntry {
+    pub fn encode_u16(&self) -> [u16; ComState::NET_WAKE_SET.w_words as usize] {
+        let [kind, arg0, arg1] = WakePattern::encode_u16(self.pattern.as_ref());
+        [self.slot as u16, kind, arg0, arg1]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_WAKE_SET.w_words as usize]) -> Result<Self, SerdesError> {
+        if data[0] as usize >= WAKE_PATTERN_SLOTS {
+            return Err(SerdesError::OutOfRange { field: "WakePatternEntry.slot", value: data[0] });
+        }
+        Ok(WakePatternEntry { slot: data[0] as u8, pattern: WakePattern::decode_u16(&[data[1], data[2], data[3]])? })
+    }
+}
+
+/// Response of NET_WAKE_LIST: the pattern of every slot.
+/// Wire format: WAKE_PATTERN_SLOTS patterns as in WakePattern, in slot order
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct WakePatternTable {
+    pub patterns: [Option<WakePattern>; WAKE_PATTERN_SLOTS],
+}
+impl WakePatternTable {
+    /// The slot of the first installed pattern that is equal to `pattern`
+    pub fn find(&self, pattern: &WakePattern) -> Option<u8> {
+        self.patterns.iter().position(|p| p.as_ref() == Some(pattern)).map(|slot| slot as u8)
+    }
+    /// One NET_WAKE_SET entry per slot, to install the whole table
+    pub fn entries(&self) -> impl Iterator<Item = WakePatternEntry> + '_ {
+        self.patterns.iter().enumerate().map(|(slot, pattern)| WakePatternEntry { slot: slot as u8, pattern: *pattern })
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_WAKE_LIST.r_words as usize] {
+        let mut ret = [0u16; ComState::NET_WAKE_LIST.r_words as usize];
+        for (dest, pattern) in ret.chunks_exact_mut(WAKE_PATTERN_WORDS).zip(self.patterns.iter()) {
+            dest.copy_from_slice(&WakePattern::encode_u16(pattern.as_ref()));
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_WAKE_LIST.r_words as usize]) -> Result<Self, SerdesError> {
+        let mut patterns = [None; WAKE_PATTERN_SLOTS];
+        for (dest, src) in patterns.iter_mut().zip(data.chunks_exact(WAKE_PATTERN_WORDS)) {
+            *dest = WakePattern::decode_u16(&[src[0], src[1], src[2]])?;
+        }
+        Ok(WakePatternTable { patterns })
+    }
+}
+const _: () = assert!(ComState::NET_WAKE_LIST.r_words as usize == WAKE_PATTERN_SLOTS * WAKE_PATTERN_WORDS);
+
+/// Number of TCP keepalive slots
+pub const KEEPALIVE_SLOTS: usize = 4;
+/// Largest keepalive payload, in bytes
+pub const KEEPALIVE_PAYLOAD_LEN: usize = 32;
+
+/// The connection a keepalive is sent on, from the device's point of view
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct TcpTuple {
+    pub local_port: u16,
+    pub remote_addr: [u8; 4],
+    pub remote_port: u16,
+}
+
+/// Payload of NET_KEEPALIVE_SET.
+/// Wire format:
+/// - word 0: slot, below KEEPALIVE_SLOTS
+/// - word 1: seconds between keepalives, 0 to clear the slot
+/// - word 2: local port
+/// - words 3-4: remote IPv4 address, packed as in Ipv4Conf
+/// - word 5: remote port
+/// - words 6-7: sequence number of the segment, low word first
+/// - words 8-9: acknowledgment number of the segment, low word first
+/// - word 10: window of the segment
+/// - words 11-27: payload of the segment, in the BytesSer layout; a probe usually carries none
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeepaliveTemplate {
+    pub slot: u8,
+    pub interval_s: u16,
+    pub tuple: TcpTuple,
+    pub seq: u32,
+    pub ack: u32,
+    pub window: u16,
+    payload_len: u8,
+    payload: [u8; KEEPALIVE_PAYLOAD_LEN],
+}
+impl KeepaliveTemplate {
+    /// A template with no payload
+    pub fn new(slot: u8, tuple: TcpTuple, interval_s: u16, seq: u32, ack: u32, window: u16) -> Self {
+        KeepaliveTemplate {
+            slot,
+            interval_s,
+            tuple,
+            seq,
+            ack,
+            window,
+            payload_len: 0,
+            payload: [0; KEEPALIVE_PAYLOAD_LEN],
+        }
+    }
+    /// The template that clears `slot`
+    pub fn clear(slot: u8) -> Self {
+        Self::new(slot, TcpTuple::default(), 0, 0, 0, 0)
+    }
+    pub fn is_clear(&self) -> bool {
+        self.interval_s == 0
+    }
+    /// Set the payload, e.g. an application-level ping, of up to KEEPALIVE_PAYLOAD_LEN bytes
+    pub fn with_payload(mut self, payload: &[u8]) -> Result<Self, SerdesError> {
+        if payload.len() > KEEPALIVE_PAYLOAD_LEN {
+            return Err(SerdesError::BytesLenTooBig);
+        }
+        self.payload = [0; KEEPALIVE_PAYLOAD_LEN];
+        self.payload[..payload.len()].copy_from_slice(payload);
+        self.payload_len = payload.len() as u8;
+        Ok(self)
+    }
+    pub fn payload(&self) -> &[u8] {
+        &self.payload[..self.payload_len as usize]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_KEEPALIVE_SET.w_words as usize] {
+        let mut ret = [0u16; ComState::NET_KEEPALIVE_SET.w_words as usize];
+        let addr = self.tuple.remote_addr;
+        ret[0] = self.slot as u16;
+        ret[1] = self.interval_s;
+        ret[2] = self.tuple.local_port;
+        ret[3] = addr[0] as u16 | (addr[1] as u16) << 8;
+        ret[4] = addr[2] as u16 | (addr[3] as u16) << 8;
+        ret[5] = self.tuple.remote_port;
+        ret[6..8].copy_from_slice(&u32_to_words(self.seq));
+        ret[8..10].copy_from_slice(&u32_to_words(self.ack));
+        ret[10] = self.window;
+        pack_str(self.payload_len, &self.payload, &mut ret[11..]);
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_KEEPALIVE_SET.w_words as usize]) -> Result<Self, SerdesError> {
+        if data[0] as usize >= KEEPALIVE_SLOTS {
+            return Err(SerdesError::OutOfRange { field: "KeepaliveTemplate.slot", value: data[0] });
+        }
+        let (payload_len, payload) = unpack_str(&data[11..]).map_err(|_| SerdesError::BytesLenTooBig)?;
+        Ok(KeepaliveTemplate {
+            slot: data[0] as u8,
+            interval_s: data[1],
+            tuple: TcpTuple {
+                local_port: data[2],
+                remote_addr: [data[3] as u8, (data[3] >> 8) as u8, data[4] as u8, (data[4] >> 8) as u8],
+                remote_port: data[5],
+            },
+            seq: words_to_u32([data[6], data[7]]),
+            ack: words_to_u32([data[8], data[9]]),
+            window: data[10],
+            payload_len,
+            payload,
+        })
+    }
+}
+
+/// State of a keepalive slot
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(u16)]
+pub enum KeepaliveState {
+    /// no template installed
+    Empty = 0,
+    /// keepalives are being sent
+    Active = 1,
+    /// stopped: the peer reset the connection
+    Reset = 2,
+    /// stopped: the peer stopped acknowledging keepalives
+    Timeout = 3,
+}
+
+/// Response of NET_KEEPALIVE_STATUS.
+/// Wire format:
+/// - word 0: slot
+/// - word 1: KeepaliveState
+/// - words 2-3: keepalives sent since the slot was set, low word first
+/// - words 4-5: of those, the ones the peer acknowledged, low word first
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeepaliveStatus {
+    pub slot: u8,
+    pub state: KeepaliveState,
+    pub sent: u32,
+    pub acked: u32,
+}
+impl KeepaliveStatus {
+    pub fn encode_u16(&self) -> [u16; ComState::NET_KEEPALIVE_STATUS.r_words as usize] {
+        let [sent_lo, sent_hi] = u32_to_words(self.sent);
+        let [acked_lo, acked_hi] = u32_to_words(self.acked);
+        [self.slot as u16, self.state as u16, sent_lo, sent_hi, acked_lo, acked_hi]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_KEEPALIVE_STATUS.r_words as usize]) -> Result<Self, SerdesError> {
+        let state = match data[1] {
+            0 => KeepaliveState::Empty,
+            1 => KeepaliveState::Active,
+            2 => KeepaliveState::Reset,
+            3 => KeepaliveState::Timeout,
+            value => return Err(SerdesError::InvalidEnumValue { field: "KeepaliveStatus.state", value }),
+        };
+        Ok(KeepaliveStatus {
+            slot: data[0] as u8,
+            state,
+            sent: words_to_u32([data[2], data[3]]),
+            acked: words_to_u32([data[4], data[5]]),
+        })
+    }
+}
+
+/// Number of mDNS service slots
+pub const MDNS_SERVICE_SLOTS: usize = 4;
+
+/// Payload of NET_MDNS_SET_SERVICE: a service the EC advertises as
+/// `<instance>.<service>.local`, on the given port of the device.
+/// Wire format:
+/// - word 0: slot, below MDNS_SERVICE_SLOTS
+/// - word 1: port, 0 to clear the slot
+/// - words 2-18: service type, e.g. "_http._tcp", in the StringSer<STR_32_WORDS> format
+/// - words 19-51: instance name, in the StringSer<STR_64_WORDS> format
+/// - words 52-84: TXT record data, length-prefixed strings as in RFC 6763, in the BytesSer layout
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MdnsService {
+    pub slot: u8,
+    pub port: u16,
+    service_len: u8,
+    service: [u8; STR_32_U8_SIZE],
+    instance_len: u8,
+    instance: [u8; STR_64_U8_SIZE],
+    txt_len: u8,
+    txt: [u8; STR_64_U8_SIZE],
+}
+impl MdnsService {
+    /// A service with an empty TXT record
+    pub fn new(slot: u8, service: &str, instance: &str, port: u16) -> Result<Self, SerdesError> {
+        if service.len() > STR_32_U8_SIZE || instance.len() > STR_64_U8_SIZE {
+            return Err(SerdesError::StrLenTooBig);
+        }
+        let mut ret = MdnsService {
+            slot,
+            port,
+            service_len: service.len() as u8,
+            service: [0; STR_32_U8_SIZE],
+            instance_len: instance.len() as u8,
+            instance: [0; STR_64_U8_SIZE],
+            txt_len: 0,
+            txt: [0; STR_64_U8_SIZE],
+        };
+        ret.service[..service.len()].copy_from_slice(service.as_bytes());
+        ret.instance[..instance.len()].copy_from_slice(instance.as_bytes());
+        Ok(ret)
+    }
+    /// The entry that clears `slot`
+    pub fn clear(slot: u8) -> Self {
+        MdnsService {
+            slot,
+            port: 0,
+            service_len: 0,
+            service: [0; STR_32_U8_SIZE],
+            instance_len: 0,
+            instance: [0; STR_64_U8_SIZE],
+            txt_len: 0,
+            txt: [0; STR_64_U8_SIZE],
+        }
+    }
+    pub fn is_clear(&self) -> bool {
+        self.port == 0
+    }
+    /// Set the TXT record data, of up to STR_64_U8_SIZE bytes
+    pub fn with_txt(mut self, txt: &[u8]) -> Result<Self, SerdesError> {
+        if txt.len() > STR_64_U8_SIZE {
+            return Err(SerdesError::BytesLenTooBig);
+        }
+        self.txt = [0; STR_64_U8_SIZE];
+        self.txt[..txt.len()].copy_from_slice(txt);
+        self.txt_len = txt.len() as u8;
+        Ok(self)
+    }
+    pub fn service(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.service[..self.service_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn instance(&self) -> Result<&str, SerdesError> {
+        core::str::from_utf8(&self.instance[..self.instance_len as usize]).map_err(|_| SerdesError::Utf8Decode)
+    }
+    pub fn txt(&self) -> &[u8] {
+        &self.txt[..self.txt_len as usize]
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_MDNS_SET_SERVICE.w_words as usize] {
+        let mut ret = [0u16; ComState::NET_MDNS_SET_SERVICE.w_words as usize];
+        ret[0] = self.slot as u16;
+        ret[1] = self.port;
+        pack_str(self.service_len, &self.service, &mut ret[2..19]);
+        pack_str(self.instance_len, &self.instance, &mut ret[19..52]);
+        pack_str(self.txt_len, &self.txt, &mut ret[52..]);
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_MDNS_SET_SERVICE.w_words as usize]) -> Result<Self, SerdesError> {
+        if data[0] as usize >= MDNS_SERVICE_SLOTS {
+            return Err(SerdesError::OutOfRange { field: "MdnsService.slot", value: data[0] });
+        }
+        let (service_len, service) = unpack_str(&data[2..19])?;
+        let (instance_len, instance) = unpack_str(&data[19..52])?;
+        let (txt_len, txt) = unpack_str(&data[52..]).map_err(|_| SerdesError::BytesLenTooBig)?;
+        Ok(MdnsService {
+            slot: data[0] as u8,
+            port: data[1],
+            service_len,
+            service,
+            instance_len,
+            instance,
+            txt_len,
+            txt,
+        })
+    }
+}
+
+/// Response of NET_MDNS_STATS, counted since the hostname was last set.
+/// Wire format:
+/// - words 0-1: mDNS queries received, low word first
+/// - words 2-3: of those, the ones the EC answered, low word first
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct MdnsStats {
+    pub queries: u32,
+    pub answered: u32,
+}
+impl MdnsStats {
+    pub fn encode_u16(&self) -> [u16; ComState::NET_MDNS_STATS.r_words as usize] {
+        let [q0, q1] = u32_to_words(self.queries);
+        let [a0, a1] = u32_to_words(self.answered);
+        [q0, q1, a0, a1]
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_MDNS_STATS.r_words as usize]) -> Self {
+        MdnsStats { queries: words_to_u32([data[0], data[1]]), answered: words_to_u32([data[2], data[3]]) }
+    }
+}
+
+/// Traffic counters reported by NET_TRAFFIC_STATS. Counters are cumulative since WLAN_ON and wrap.
+/// Wire format: each counter is 2 words, low word first, in field order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct TrafficStats {
+    /// bytes of the frames sent, as passed to NET_FRAME_SEND
+    pub tx_bytes: u32,
+    pub tx_packets: u32,
+    /// bytes of the frames received, as returned by NET_FRAME_FETCH
+    pub rx_bytes: u32,
+    pub rx_packets: u32,
+    /// frames the EC gave up on sending: no buffer, no link, or out of retries
+    pub tx_dropped: u32,
+    /// frames the EC received but discarded because the host did not fetch them in time
+    pub rx_dropped: u32,
+    /// 802.11 retransmissions, a sign of a poor link when high relative to tx_packets
+    pub tx_retries: u32,
+}
+impl TrafficStats {
+    /// The traffic counted between `earlier` and this snapshot, allowing for wrapped counters
+    pub fn since(&self, earlier: &TrafficStats) -> TrafficStats {
+        TrafficStats {
+            tx_bytes: self.tx_bytes.wrapping_sub(earlier.tx_bytes),
+            tx_packets: self.tx_packets.wrapping_sub(earlier.tx_packets),
+            rx_bytes: self.rx_bytes.wrapping_sub(earlier.rx_bytes),
+            rx_packets: self.rx_packets.wrapping_sub(earlier.rx_packets),
+            tx_dropped: self.tx_dropped.wrapping_sub(earlier.tx_dropped),
+            rx_dropped: self.rx_dropped.wrapping_sub(earlier.rx_dropped),
+            tx_retries: self.tx_retries.wrapping_sub(earlier.tx_retries),
+        }
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::NET_TRAFFIC_STATS.r_words as usize] {
+        let mut ret = [0u16; ComState::NET_TRAFFIC_STATS.r_words as usize];
+        let counters = [
+            self.tx_bytes,
+            self.tx_packets,
+            self.rx_bytes,
+            self.rx_packets,
+            self.tx_dropped,
+            self.rx_dropped,
+            self.tx_retries,
+        ];
+        for (dest, counter) in ret.chunks_exact_mut(2).zip(counters.iter()) {
+            dest.copy_from_slice(&u32_to_words(*counter));
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::NET_TRAFFIC_STATS.r_words as usize]) -> Self {
+        TrafficStats {
+            tx_bytes: words_to_u32([data[0], data[1]]),
+            tx_packets: words_to_u32([data[2], data[3]]),
+            rx_bytes: words_to_u32([data[4], data[5]]),
+            rx_packets: words_to_u32([data[6], data[7]]),
+            tx_dropped: words_to_u32([data[8], data[9]]),
+            rx_dropped: words_to_u32([data[10], data[11]]),
+            tx_retries: words_to_u32([data[12], data[13]]),
+        }
+    }
+}
+
+/// Why a link was torn down: the 802.11 reason code of a deauthentication or disassociation,
+/// sent by the AP or by the EC itself
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// 1
+    Unspecified,
+    /// 2: the previous authentication is no longer valid
+    AuthExpired,
+    /// 3: the sender is leaving the network, e.g. the AP is shutting down
+    DeauthLeaving,
+    /// 4: idle for too long
+    Inactivity,
+    /// 5: the AP cannot handle all the associated stations
+    ApBusy,
+    /// 6, 7: a frame arrived in the wrong state, e.g. after the AP forgot the station
+    NotAuthenticated,
+    NotAssociated,
+    /// 8: the sender is leaving the BSS
+    DisassocLeaving,
+    /// 14: Michael MIC failure
+    MicFailure,
+    /// 15: the 4-way handshake timed out, usually a wrong passphrase
+    HandshakeTimeout,
+    /// 16: the group key handshake timed out
+    GroupKeyTimeout,
+    /// 23: IEEE 802.1X authentication failed
+    Ieee8021xFailed,
+    /// 34: too many frames went unacknowledged, e.g. on a poor channel
+    PoorChannel,
+    /// any other reason code
+    Other(u16),
+}
+impl DisconnectReason {
+    /// The reason for an 802.11 reason code, None for 0, which means no reason
+    pub fn from_code(code: u16) -> Option<Self> {
+        Some(match code {
+            0 => return None,
+            1 => DisconnectReason::Unspecified,
+            2 => DisconnectReason::AuthExpired,
+            3 => DisconnectReason::DeauthLeaving,
+            4 => DisconnectReason::Inactivity,
+            5 => DisconnectReason::ApBusy,
+            6 => DisconnectReason::NotAuthenticated,
+            7 => DisconnectReason::NotAssociated,
+            8 => DisconnectReason::DisassocLeaving,
+            14 => DisconnectReason::MicFailure,
+            15 => DisconnectReason::HandshakeTimeout,
+            16 => DisconnectReason::GroupKeyTimeout,
+            23 => DisconnectReason::Ieee8021xFailed,
+            34 => DisconnectReason::PoorChannel,
+            code => DisconnectReason::Other(code),
+        })
+    }
+    /// The 802.11 reason code
+    pub fn code(&self) -> u16 {
+        match self {
+            DisconnectReason::Unspecified => 1,
+            DisconnectReason::AuthExpired => 2,
+            DisconnectReason::DeauthLeaving => 3,
+            DisconnectReason::Inactivity => 4,
+            DisconnectReason::ApBusy => 5,
+            DisconnectReason::NotAuthenticated => 6,
+            DisconnectReason::NotAssociated => 7,
+            DisconnectReason::DisassocLeaving => 8,
+            DisconnectReason::MicFailure => 14,
+            DisconnectReason::HandshakeTimeout => 15,
+            DisconnectReason::GroupKeyTimeout => 16,
+            DisconnectReason::Ieee8021xFailed => 23,
+            DisconnectReason::PoorChannel => 34,
+            DisconnectReason::Other(code) => *code,
+        }
+    }
+    /// True for reasons that point at the credentials rather than the radio link
+    pub fn is_auth_failure(&self) -> bool {
+        matches!(
+            self,
+            DisconnectReason::MicFailure | DisconnectReason::HandshakeTimeout | DisconnectReason::Ieee8021xFailed
+        )
+    }
+}
+
+// ConnDiagnostics flags
+pub const DIAG_FLAG_LOCAL: Flags16 = Flags16::bit(0); // the EC ended the link, not the AP
+pub const DIAG_FLAG_DISASSOC: Flags16 = Flags16::bit(1); // ended by disassociation rather than deauthentication
+pub const DIAG_FLAG_BEACON_LOSS: Flags16 = Flags16::bit(2); // the EC gave up after missing beacons; no reason code
+const _: () = assert!(Flags16::disjoint(&[DIAG_FLAG_LOCAL, DIAG_FLAG_DISASSOC, DIAG_FLAG_BEACON_LOSS]));
+
+/// Response of WLAN_GET_DIAGNOSTICS.
+/// Wire format:
+/// - word 0: 802.11 reason code of the last disconnection, 0 if none or on beacon loss
+/// - word 1: DIAG_FLAG_* bits describing the last disconnection
+/// - words 2-3: beacons missed on the link, low word first
+/// - word 4: share of sent frames that needed at least one retry, in parts per thousand
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ConnDiagnostics {
+    pub reason: Option<DisconnectReason>,
+    pub flags: Flags16,
+    pub beacon_misses: u32,
+    pub retry_permille: u16,
+}
+impl ConnDiagnostics {
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_GET_DIAGNOSTICS.r_words as usize] {
+        let [misses_lo, misses_hi] = u32_to_words(self.beacon_misses);
+        let reason = self.reason.map_or(0, |reason| reason.code());
+        [reason, self.flags.encode_u16(), misses_lo, misses_hi, self.retry_permille]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_DIAGNOSTICS.r_words as usize]) -> Self {
+        ConnDiagnostics {
+            reason: DisconnectReason::from_code(data[0]),
+            flags: Flags16::decode_u16(data[1]),
+            beacon_misses: words_to_u32([data[2], data[3]]),
+            retry_permille: data[4],
+        }
+    }
+}
+
+/// Maximum number of samples in the RSSI history
+pub const RSSI_HISTORY_MAX: usize = 32;
+
+/// Payload of WLAN_SET_RSSI_HISTORY.
+/// Wire format:
+/// - word 0: sampling interval in milliseconds, 0 to stop sampling
+/// - word 1: number of samples to keep, at most RSSI_HISTORY_MAX
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RssiHistoryConfig {
+    pub interval_ms: u16,
+    pub depth: u16,
+}
+impl RssiHistoryConfig {
+    pub const DISABLED: RssiHistoryConfig = RssiHistoryConfig { interval_ms: 0, depth: RSSI_HISTORY_MAX as u16 };
+
+    /// Sample every `interval_ms`, keeping up to `depth` samples; depth is capped to RSSI_HISTORY_MAX
+    pub fn new(interval_ms: u16, depth: u16) -> Self {
+        RssiHistoryConfig { interval_ms, depth: depth.min(RSSI_HISTORY_MAX as u16) }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.interval_ms != 0 && self.depth != 0
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_RSSI_HISTORY.w_words as usize] {
+        [self.interval_ms, self.depth]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_SET_RSSI_HISTORY.w_words as usize]) -> Result<Self, SerdesError> {
+        if data[1] as usize > RSSI_HISTORY_MAX {
+            return Err(SerdesError::OutOfRange { field: "RssiHistoryConfig.depth", value: data[1] });
+        }
+        Ok(RssiHistoryConfig { interval_ms: data[0], depth: data[1] })
+    }
+}
+
+/// Response of WLAN_GET_RSSI_HISTORY.
+/// Wire format:
+/// - word 0: sampling interval in milliseconds, 0 if sampling is stopped
+/// - word 1: number of samples
+/// - words 2-33: RSSI_HISTORY_MAX samples as Rssi, oldest first, unused ones zero
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RssiHistory {
+    pub interval_ms: u16,
+    pub count: u16,
+    pub samples: [Rssi; RSSI_HISTORY_MAX],
+}
+impl RssiHistory {
+    /// Build the response from `samples`, oldest first, as done by the EC; only the newest
+    /// RSSI_HISTORY_MAX are kept
+    pub fn from_samples(interval_ms: u16, samples: &[Rssi]) -> Self {
+        let newest = &samples[samples.len().saturating_sub(RSSI_HISTORY_MAX)..];
+        let mut ret = RssiHistory { interval_ms, count: newest.len() as u16, samples: [Rssi::from_dbm(0); RSSI_HISTORY_MAX] };
+        ret.samples[..newest.len()].copy_from_slice(newest);
+        ret
+    }
+    /// The samples, oldest first
+    pub fn samples(&self) -> &[Rssi] {
+        &self.samples[..(self.count as usize).min(RSSI_HISTORY_MAX)]
+    }
+    pub fn latest(&self) -> Option<Rssi> {
+        self.samples().last().copied()
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_GET_RSSI_HISTORY.r_words as usize] {
+        let mut ret = [0u16; ComState::WLAN_GET_RSSI_HISTORY.r_words as usize];
+        ret[0] = self.interval_ms;
+        ret[1] = self.count;
+        for (dest, sample) in ret[2..].iter_mut().zip(self.samples()) {
+            *dest = sample.encode_u16();
+        }
+        ret
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_GET_RSSI_HISTORY.r_words as usize]) -> Self {
+        let count = data[1].min(RSSI_HISTORY_MAX as u16);
+        let mut samples = [Rssi::from_dbm(0); RSSI_HISTORY_MAX];
+        for (sample, src) in samples.iter_mut().zip(data[2..].iter()).take(count as usize) {
+            *sample = Rssi::decode_u16(*src);
+        }
+        RssiHistory { interval_ms: data[0], count, samples }
+    }
+}
+const _: () = assert!(ComState::WLAN_GET_RSSI_HISTORY.r_words as usize == 2 + RSSI_HISTORY_MAX);
+
+/// Largest ewma_shift accepted by WLAN_SET_RSSI_FILTER
+pub const RSSI_EWMA_SHIFT_MAX: u16 = 8;
+
+/// Payload of WLAN_SET_RSSI_FILTER and response of WLAN_GET_RSSI_FILTER.
+/// Wire format:
+/// - word 0: weight of each new sample in the moving average, as 1/2^ewma_shift; 0 follows the raw RSSI
+/// - word 1: change in the average in dB that raises INT_WLAN_RSSI_UPDATE, as I8F8; 0 never raises it
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RssiFilterConfig {
+    pub ewma_shift: u16,
+    pub min_delta: I8F8,
+}
+impl RssiFilterConfig {
+    /// Average over roughly the last 8 samples and report changes of 3 dB
+    pub const DEFAULT: RssiFilterConfig = RssiFilterConfig { ewma_shift: 3, min_delta: I8F8::from_int(3) };
+
+    /// Fold `sample` into the average `avg`, as done by the EC
+    pub fn filter(&self, avg: Rssi, sample: Rssi) -> Rssi {
+        Rssi::from_i8f8(avg.as_i8f8().ewma(sample.as_i8f8(), self.ewma_shift as u32))
+    }
+    /// True if the average has moved far enough from the last `reported` value to raise INT_WLAN_RSSI_UPDATE
+    pub fn should_report(&self, reported: Rssi, avg: Rssi) -> bool {
+        let delta = avg.as_i8f8().0 as i32 - reported.as_i8f8().0 as i32;
+        self.min_delta > I8F8::ZERO && delta.abs() >= self.min_delta.0 as i32
+    }
+    pub fn encode_u16(&self) -> [u16; ComState::WLAN_SET_RSSI_FILTER.w_words as usize] {
+        [self.ewma_shift, self.min_delta.encode_u16()]
+    }
+    pub fn decode_u16(data: &[u16; ComState::WLAN_SET_RSSI_FILTER.w_words as usize]) -> Result<Self, SerdesError> {
+        if data[0] > RSSI_EWMA_SHIFT_MAX {
+            return Err(SerdesError::OutOfRange { field: "RssiFilterConfig.ewma_shift", value: data[0] });
+        }
+        let min_delta = I8F8::decode_u16(data[1]);
+        if min_delta < I8F8::ZERO {
+            return Err(SerdesError::OutOfRange { field: "RssiFilterConfig.min_delta", value: data[1] });
+        }
+        Ok(RssiFilterConfig { ewma_shift: data[0], min_delta })
+    }
+}
+impl Default for RssiFilterConfig {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// Serdes Unit Tests.
+/// If you run this as a submodule of betrusted-ec, `cargo test` alone won't work right
+/// because ../.cargo/config sets an RV32 build target. The solution is to add a --target
+/// switch to cargo test, like:
+///   `cargo test --target="x86_64-unknown-linux-gnu"`
+/// To check targets, try:
+///   `rustc --print target-list`
+///
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{INT_WLAN_DISCONNECT, INT_WLAN_RX_READY};
+
+    /// Pack two ASCII chars as a u16
+    fn c2u16(c1: char, c2: char) -> u16 {
+        u16::from_le_bytes([c1 as u8, c2 as u8])
+    }
+
+    #[test]
+    fn serialize_short_str() {
+        const U16_LEN: usize = 4;
+        let src = "short";
+        let encoded = &[5, c2u16('s', 'h'), c2u16('o', 'r'), 't' as u16];
+        let mut ser = StringSer::<U16_LEN>::new();
+        assert_eq!(encoded, ser.encode(src).unwrap());
+    }
+
+    #[test]
+    fn deserialize_short_str() {
+        const U16_LEN: usize = 4;
+        const U8_LEN: usize = 2 * (U16_LEN - 1);
+        let src = "short";
+        let encoded = &[5, c2u16('s', 'h'), c2u16('o', 'r'), 't' as u16];
+        let mut des = StringDes::<U16_LEN, U8_LEN>::new();
+        assert_eq!(src, des.decode_u16(encoded).unwrap());
+    }
+
+    #[test]
+    fn round_trip_short_str() {
+        const U16_LEN: usize = 4;
+        const U8_LEN: usize = 2 * (U16_LEN - 1);
+        let src = "short";
+        let mut ser = StringSer::<U16_LEN>::new();
+        let mut des = StringDes::<U16_LEN, U8_LEN>::new();
+        assert_eq!(src, des.decode_u16(ser.encode(src).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn round_trip_subscription() {
+        let sub = Subscription {
+            source: SubscriptionSource::BatterySoc,
+            trigger: SubscriptionTrigger::Below,
+            threshold: 15,
+        };
+        let encoded = sub.encode_u16();
+        assert_eq!(encoded, [0x0102, 15]);
+        assert_eq!(sub, Subscription::decode_u16(&encoded));
+        assert!(sub.is_triggered_by(14));
+        assert!(!sub.is_triggered_by(15));
+
+        // temperatures are signed: -5 C is not above 10 C
+        let warm = Subscription { source: SubscriptionSource::Temperature, trigger: SubscriptionTrigger::Above, threshold: 10 };
+        assert!(!warm.is_triggered_by(-5i16 as u16));
+        assert!(warm.is_triggered_by(11));
+        let cold = Subscription { trigger: SubscriptionTrigger::Below, threshold: 0, ..warm };
+        assert!(cold.is_triggered_by(-5i16 as u16));
+    }
+
+    #[test]
+    fn trng_stream_health_gates_entropy() {
+        let mut words = [0u16; TRNG_STREAM_WORDS];
+        words[0] = 0xA5A5;
+        words[1] = 0x5A5A;
+        let stream = TrngStream { health: 0, valid: 2, words };
+        let decoded = TrngStream::decode_u16(&stream.encode_u16());
+        assert_eq!(decoded.entropy(), &[0xA5A5, 0x5A5A]);
+        let sick = TrngStream { health: TRNG_HEALTH_REPETITION_FAIL, ..decoded };
+        assert!(TrngStream::decode_u16(&sick.encode_u16()).entropy().is_empty());
+    }
+
+    #[test]
+    fn protocol_version_ordering() {
+        let v = ProtocolVersion::decode_u16(&ProtocolVersion::CURRENT.encode_u16());
+        assert_eq!(v, ProtocolVersion::CURRENT);
+        assert!(v.at_least(&ProtocolVersion::V1_0));
+        assert!(!ProtocolVersion::V1_0.at_least(&v));
+        assert!(v.is_compatible_with(&ProtocolVersion::V1_0));
+        assert!(!ProtocolVersion::V1_0.is_compatible_with(&v));
+        assert!(v.has_features(PROTO_FEATURE_CRC | PROTO_FEATURE_SEQ));
+        assert!(!ProtocolVersion::V1_0.has_features(PROTO_FEATURE_CRC));
+    }
+
+    #[test]
+    fn verb_list_pages() {
+        let last = (ComState::ALL.len() - 1) / VERB_LIST_PAGE_LEN;
+        let page = VerbListPage::decode_u16(&VerbListPage::from_table(ComState::ALL, last as u16).encode_u16());
+        assert_eq!(page.page_count() as usize, last + 1);
+        assert_eq!(page.verbs().count(), ComState::ALL.len() - last * VERB_LIST_PAGE_LEN);
+        assert_eq!(page.verbs().last(), Some(&VerbEntry::from(ComState::ALL.last().unwrap())));
+    }
+
+    #[test]
+    fn i8f8_arithmetic() {
+        let a = I8F8::from_milli(-61_250);
+        assert_eq!(a, I8F8(-61 * 256 - 64));
+        assert_eq!(a.to_milli(), -61_250);
+        assert_eq!(a.floor(), -62);
+        assert_eq!(a.round(), -61);
+        assert_eq!(I8F8::decode_u16(a.encode_u16()), a);
+        assert_eq!(I8F8::MAX + I8F8::from_int(1), I8F8::MAX);
+        assert_eq!(I8F8::from_int(3) * I8F8::from_milli(500), I8F8::from_milli(1_500));
+        assert_eq!(I8F8::from_int(-60).ewma(I8F8::from_int(-70), 1), I8F8::from_int(-65));
+    }
+
+    #[test]
+    fn log_escalation_policy() {
+        let policy = LogEscalation::decode_u16(&LogEscalation { min_severity: LogSeverity::Warn, max_per_sec: 2 }.encode_u16());
+        assert!(policy.should_escalate(LogSeverity::Error, 0));
+        assert!(!policy.should_escalate(LogSeverity::Info, 0));
+        assert!(!policy.should_escalate(LogSeverity::Error, 2));
+        assert!(!LogEscalation::DISABLED.should_escalate(LogSeverity::Error, 0));
+    }
+
+    #[test]
+    fn coalesce_policy() {
+        let rx = CoalesceConfig { source: InterruptFlags::WLAN_RX_READY, min_events: 4, max_delay_ms: 5, min_interval_ms: 2 };
+        let rx = CoalesceConfig::decode_u16(&rx.encode_u16());
+        assert_eq!(rx.source, InterruptFlags::WLAN_RX_READY);
+        assert!(!rx.should_assert(3, 1, 100));
+        assert!(rx.should_assert(4, 1, 100));
+        assert!(rx.should_assert(1, 5, 100));
+        assert!(!rx.should_assert(4, 5, 1));
+        assert!(CoalesceConfig::disabled(InterruptFlags::WLAN_RX_READY).should_assert(1, 0, 0));
+    }
+
+    #[test]
+    fn interrupt_status_rx_fetch() {
+        let status = InterruptStatus::decode_u16(&[INT_WLAN_RX_READY | INT_WLAN_DISCONNECT, 61]);
+        let fetch = status.rx_fetch_spec().unwrap();
+        assert_eq!(fetch.verb, 0xC83D);
+        assert_eq!(fetch.r_words, 1 + 31);
+        assert_eq!(InterruptStatus::decode32_u16(&status.encode32_u16()), status);
+        let idle = InterruptStatus { flags: InterruptFlags::WLAN_DISCONNECT, rx_len_bytes: 61 };
+        assert_eq!(idle.rx_fetch_spec(), None);
+    }
+
+    #[test]
+    fn cred_map_lookup() {
+        let mut map = CredMap::new();
+        assert!(map.insert("home", 0, 10));
+        assert!(map.insert("office", 1, 5));
+        assert!(map.insert("home", 2, 10));
+        let map = CredMap::decode_u16(&map.encode_u16());
+        assert_eq!(map.lookup("home"), Some(2));
+        assert_eq!(map.lookup("office"), Some(1));
+        assert_eq!(map.lookup("cafe"), None);
+        assert_eq!(map.entries.iter().filter(|e| !e.is_empty()).count(), 2);
+    }
+
+    #[test]
+    fn imu_wom_limits() {
+        assert_eq!(ImuWomConfig::new(IMU_WOM_MAX_THRESHOLD_MG + 1, 0), Err(ImuWomError::ThresholdTooHigh));
+        assert_eq!(ImuWomConfig::new(250, 4), Err(ImuWomError::DurationTooLong));
+        let config = ImuWomConfig::new(250, 2).unwrap();
+        assert_eq!(config.wake_ths(), 8);
+        assert_eq!(ImuWomConfig::new(IMU_WOM_MAX_THRESHOLD_MG, 0).unwrap().wake_ths(), 63);
+        assert!(!ImuWomConfig::DISABLED.is_enabled());
+        assert_eq!(ImuWomConfig::decode_u16(&config.encode_u16()), config);
+    }
+
+    #[test]
+    fn flags16() {
+        const A: Flags16 = Flags16::bit(0);
+        const B: Flags16 = Flags16::bit(5);
+        assert!(Flags16::disjoint(&[A, B]));
+        assert!(!Flags16::disjoint(&[A, B, Flags16::from_bits(0x0021)]));
+        let mut flags = Flags16::empty().with(A).with(B);
+        assert_eq!(flags.bits(), 0x0021);
+        let mut iter = flags.iter();
+        assert_eq!((iter.next(), iter.next(), iter.next()), (Some(A), Some(B), None));
+        flags.set(A, false);
+        assert_eq!(flags, B);
+        assert_eq!((A | B).without(B), A);
+    }
+
+    #[test]
+    fn rx_meta_round_trip() {
+        let meta = RxMeta { rssi: Rssi::from_i8f8(I8F8::from_milli(-61_500)), channel: 11, timestamp_us: 0x0012_3456 };
+        let words = meta.encode_u16();
+        assert_eq!(words[2..], [0x3456, 0x0012]);
+        assert_eq!(RxMeta::decode_u16(&words), meta);
+    }
+
+    #[test]
+    fn tx_status_page_round_trip() {
+        let mut page = TxStatusPage { count: 2, pending: 3, records: [TxStatusPage::EMPTY_RECORD; TX_STATUS_PAGE_LEN] };
+        page.records[0] = TxCompletion { handle: 41, status: TxStatus::Ok, retries: 0 };
+        page.records[1] = TxCompletion { handle: 42, status: TxStatus::RetryLimit, retries: 15 };
+        let words = page.encode_u16();
+        assert_eq!(words[..5], [0x0302, 41, 0x0000, 42, 0x0F01]);
+        assert_eq!(TxStatusPage::decode_u16(&words), page);
+        assert_eq!(TxCompletion::decode_u16(&[0, 0x0107]).status, TxStatus::Other);
+    }
+
+    #[test]
+    fn frame_packing_is_msb_first() {
+        let mut words = [0xFFFFu16; 3];
+        assert_eq!(pack_frame_words(&[0x12, 0x34, 0x56], &mut words), Ok(2));
+        assert_eq!(words, [0x1234, 0x5600, 0xFFFF]);
+        assert_eq!(pack_frame_words(&[1, 2, 3], &mut words[..1]), Err(SerdesError::BufferTooShort { expected: 2, actual: 1 }));
+        let mut bytes = [0u8; 4];
+        assert_eq!(unpack_frame_bytes(&[0x1234, 0x5600], 4, &mut bytes), Ok(4));
+        assert_eq!(unpack_frame_bytes(&[0x1234, 0x5600], 5, &mut bytes), Err(SerdesError::BufferTooShort { expected: 3, actual: 2 }));
+        assert_eq!(unpack_frame_bytes(&[0x1234, 0x5600], 3, &mut bytes[..2]), Err(SerdesError::BufferTooShort { expected: 3, actual: 2 }));
+    }
+
+    #[test]
+    fn frame_packing_round_trips_every_length() {
+        let mut src = [0u8; 33];
+        for (i, b) in src.iter_mut().enumerate() {
+            *b = 0xA0 ^ i as u8;
+        }
+        for len in 0..=src.len() {
+            let mut words = [0u16; 17];
+            assert_eq!(pack_frame_words(&src[..len], &mut words), Ok(len.div_ceil(2)));
+            if len % 2 == 1 {
+                assert_eq!(words[len / 2] & 0xFF, 0, "odd length {} not zero-padded", len);
+            }
+            let mut out = [0u8; 33];
+            assert_eq!(unpack_frame_bytes(&words, len, &mut out), Ok(len));
+            assert_eq!(out[..len], src[..len], "length {}", len);
+            assert!(out[len..].iter().all(|&b| b == 0), "length {} wrote past the end", len);
+        }
+    }
+
+    #[test]
+    fn word_cursors_round_trip() {
+        let mut buf = [0u16; 6];
+        let mut writer = WordWriter::new(&mut buf);
+        writer.put_u16(0x1234).unwrap();
+        writer.put_u32(0xAABB_CCDD).unwrap();
+        writer.put_bytes_le(b"abc").unwrap();
+        assert_eq!(writer.put_bytes_be(b"xyz"), Err(SerdesError::BufferTooShort { expected: 7, actual: 6 }));
+        writer.put_bytes_be(b"z").unwrap();
+        assert_eq!(writer.written(), [0x1234, 0xCCDD, 0xAABB, 0x6261, 0x0063, 0x7A00]);
+
+        let mut reader = WordReader::new(&buf);
+        assert_eq!(reader.get_u16(), Ok(0x1234));
+        assert_eq!(reader.get_u32(), Ok(0xAABB_CCDD));
+        let mut abc = [0u8; 3];
+        reader.get_bytes_le(&mut abc).unwrap();
+        assert_eq!(&abc, b"abc");
+        let mut z = [0u8; 1];
+        reader.get_bytes_be(&mut z).unwrap();
+        assert_eq!(&z, b"z");
+        assert_eq!(reader.remaining(), 0);
+        assert_eq!(reader.get_u16(), Err(SerdesError::BufferTooShort { expected: 7, actual: 6 }));
+    }
+
+    #[test]
+    fn wide_values_are_low_word_first() {
+        assert_eq!(u32_to_words(0x1234_5678), [0x5678, 0x1234]);
+        assert_eq!(words_to_u32([0x5678, 0x1234]), 0x1234_5678);
+        assert_eq!(u64_to_words(0x0123_4567_89AB_CDEF), [0xCDEF, 0x89AB, 0x4567, 0x0123]);
+        assert_eq!(words_to_u64(u64_to_words(u64::MAX - 1)), u64::MAX - 1);
+    }
+
+    #[test]
+    fn bytes_ser_des() {
+        let blob = [0x00u8, 0xFF, 0x10, 0x80, 0x7F];
+        let mut ser = BytesSer::<4>::new();
+        assert_eq!(ser.encode(&[0xEE; 6]).unwrap(), &[6, 0xEEEE, 0xEEEE, 0xEEEE]);
+        // shorter data does not leave stale words behind
+        assert_eq!(ser.encode(&blob).unwrap(), &[5, 0xFF00, 0x8010, 0x007F]);
+        assert_eq!(ser.encode(&[0; 7]), Err(SerdesError::BytesLenTooBig));
+
+        let mut des = BytesDes::<4, 6>::new();
+        assert_eq!(des.decode_u16(&[5, 0xFF00, 0x8010, 0x007F]), Ok(&blob[..]));
+        assert_eq!(des.decode_u16(&[7, 0, 0, 0]), Err(SerdesError::BytesLenTooBig));
+        let mut small = BytesDes::<4, 4>::new();
+        assert_eq!(small.decode_u16(&[5, 0, 0, 0]), Err(SerdesError::BytesLenTooBig));
+    }
+
+    #[test]
+    fn views_match_owned_decode() {
+        let conf = Ipv4Conf {
+            dhcp: DhcpState::Bound,
+            mac: [2, 3, 4, 5, 6, 7],
+            addr: [10, 0, 0, 2],
+            gtwy: [10, 0, 0, 1],
+            mask: [255, 255, 255, 0],
+            dns1: [9, 9, 9, 9],
+            dns2: [1, 0, 0, 1],
+        };
+        let words = conf.encode_u16();
+        let view = Ipv4ConfView(&words);
+        assert_eq!(view.dhcp(), DhcpState::Bound);
+        assert_eq!((view.mac(), view.addr(), view.gtwy()), (conf.mac, conf.addr, conf.gtwy));
+        assert_eq!((view.mask(), view.dns1(), view.dns2()), (conf.mask, conf.dns1, conf.dns2));
+
+        let mut page = EventPage { count: 2, pending: 9, events: [EventPage::EMPTY_EVENT; EVENT_PAGE_LEN] };
+        page.events[1] = ComEvent { source: InterruptFlags::CHG_INSERTED, timestamp_ms: 77, arg: 1 };
+        let words = page.encode_u16();
+        let view = EventPageView(&words);
+        assert_eq!((view.count(), view.pending()), (2, 9));
+        assert_eq!(view.get(1), Some(page.events[1]));
+        assert_eq!(view.get(2), None);
+        assert_eq!(view.iter().count(), 2);
+    }
+
+    #[test]
+    fn versioned_ipv4_conf() {
+        let conf = Ipv4Conf { dhcp: DhcpState::Bound, addr: [10, 0, 0, 2], ..Default::default() };
+        let legacy = conf.encode_u16();
+        let tagged = conf.encode_versioned();
+        assert_eq!(tagged[0], 0xFA01);
+        assert_eq!(split_format_tag(&legacy), (0, &legacy[..]));
+        assert_eq!(Ipv4Conf::decode_versioned(&legacy).unwrap().encode_u16(), legacy);
+        assert_eq!(Ipv4Conf::decode_versioned(&tagged).unwrap().encode_u16(), legacy);
+
+        // a newer peer appends fields; this revision decodes the prefix it knows
+        let mut newer = [0u16; IPV4_CONF_V1_WORDS + 2];
+        newer[..IPV4_CONF_V1_WORDS].copy_from_slice(&tagged);
+        newer[0] = format_tag(2);
+        assert_eq!(Ipv4Conf::decode_versioned(&newer).unwrap().encode_u16(), legacy);
+        assert_eq!(
+            Ipv4Conf::decode_versioned(&tagged[..8]).err(),
+            Some(SerdesError::BufferTooShort { expected: 14, actual: 7 })
+        );
+    }
+
+    #[test]
+    fn decode_errors_carry_context() {
+        let mut words = LogRecord::new(LogSeverity::Warn, 5, "hi").encode_u16();
+        assert!(LogRecord::decode_u16(&words).is_ok());
+        words[0] = 9;
+        assert_eq!(
+            LogRecord::decode_u16(&words),
+            Err(SerdesError::InvalidEnumValue { field: "LogRecord.severity", value: 9 })
+        );
+        assert_eq!(need(&words[..3], 4), Err(SerdesError::BufferTooShort { expected: 4, actual: 3 }));
+
+        // the catch-all variants are values too; only words no variant encodes to are refused
+        assert_eq!(known_enum_value("ConnectResult", ConnectResult::Pending as u16), Ok(ConnectResult::Pending));
+        assert_eq!(known_enum_value("Security", 0xFF), Ok(Security::Unknown));
+        assert_eq!(known_enum_value::<DhcpState>("DhcpState", 8), Err(SerdesError::InvalidEnumValue { field: "DhcpState", value: 8 }));
+        let mut conf = [0u16; ComState::WLAN_GET_IPV4_CONF.r_words as usize];
+        conf[0] = 8;
+        assert_eq!(Ipv4Conf::decode_versioned(&conf).err(), Some(SerdesError::InvalidEnumValue { field: "Ipv4Conf.dhcp", value: 8 }));
+    }
+
+    #[test]
+    fn string_ser_fmt_write() {
+        use core::fmt::Write;
+        let mut ser = StringSer::<STR_32_WORDS>::new();
+        write!(ser, "rssi {} ch {}", -61, 11).unwrap();
+        let mut expected = StringSer::<STR_32_WORDS>::new();
+        assert_eq!(ser.as_u16_slice(), expected.encode("rssi -61 ch 11").unwrap());
+        assert_eq!(ser.len(), 14);
+
+        // a write that does not fit leaves the string as it was
+        assert!(ser.write_str("0123456789abcdefghij").is_err());
+        assert_eq!(ser.len(), 14);
+        ser.write_str(" ok").unwrap();
+        let mut des = StringDes::<STR_32_WORDS, STR_32_U8_SIZE>::new();
+        assert_eq!(des.decode_u16(ser.as_u16_slice()), Ok("rssi -61 ch 11 ok"));
+        ser.clear();
+        assert!(ser.is_empty());
+    }
+
+    #[test]
+    fn string_des_lossy() {
+        let mut ser = StringSer::<STR_32_WORDS>::new();
+        let mut words = *ser.encode("status: ok").unwrap();
+        let mut des = StringDes::<STR_32_WORDS, STR_32_U8_SIZE>::new();
+        assert_eq!(des.decode_u16_lossy(&words), ("status: ok", 0));
+
+        // one corrupted word
+        words[2] = 0xFFC3;
+        assert_eq!(des.decode_u16(&words), Err(SerdesError::Utf8Decode));
+        assert_eq!(des.decode_u16_lossy(&words), ("st\u{FFFD}\u{FFFD}us: ok", 2));
+
+        // a corrupted length word
+        words[0] = 0x0100;
+        assert_eq!(des.decode_u16_lossy(&words).1, 3);
+    }
+
+    #[cfg(feature = "heapless")]
+    #[test]
+    fn heapless_conversions() {
+        let mut status = heapless::String::<32>::new();
+        status.push_str("link up").unwrap();
+        let mut ser = StringSer::<STR_32_WORDS>::new();
+        let mut des = StringDes::<STR_32_WORDS, STR_32_U8_SIZE>::new();
+        des.decode_u16(ser.encode_from(&status).unwrap()).unwrap();
+        assert_eq!(des.to_heapless::<32>(), Ok(status));
+        assert_eq!(des.to_heapless::<4>(), Err(SerdesError::StrLenTooBig));
+
+        let blob: heapless::Vec<u8, 8> = heapless::Vec::from_slice(&[1, 2, 3]).unwrap();
+        let mut ser = BytesSer::<4>::new();
+        let mut des = BytesDes::<4, 6>::new();
+        des.decode_u16(ser.encode_from(&blob).unwrap()).unwrap();
+        assert_eq!(des.to_heapless::<8>(), Ok(blob));
+        assert_eq!(des.to_heapless::<2>(), Err(SerdesError::BytesLenTooBig));
+    }
+
+    #[test]
+    fn scan_result_pages() {
+        let result = ScanResult::new(&[0xFF, b'x'], [2, 4, 6, 8, 10, 12], Rssi::from_dbm(-71), 11, Security::Wpa2Psk);
+        let page = ScanResultPage::from_results(&[ScanResult::EMPTY, result], 1);
+        assert_eq!((page.total, page.count, page.flags), (2, 1, Flags16::empty()));
+        assert!(ScanResultPage::from_results(&[result; 5], 0).flags.contains(SCAN_PAGE_FLAG_MORE));
+        let decoded = ScanResultPage::decode_u16(&page.encode_u16()).unwrap();
+        assert_eq!(decoded, page);
+        assert_eq!(decoded.results(), [result]);
+        assert_eq!(decoded.results()[0].ssid_bytes(), [0xFF, b'x']);
+        assert_eq!(decoded.results()[0].ssid(), Err(SerdesError::Utf8Decode));
+
+        let mut words = result.encode_u16();
+        words[0] = 33;
+        assert_eq!(ScanResult::decode_u16(&words), Err(SerdesError::StrLenTooBig));
+    }
+
+    #[test]
+    fn scan_config() {
+        let config = ScanConfig { channels: 0b100_0010_0001, dwell_ms: 120, mode: ScanMode::Passive, max_results: 16 };
+        assert_eq!(config.validate(), Ok(()));
+        assert!(config.has_channel(1) && config.has_channel(6) && config.has_channel(11));
+        assert!(!config.has_channel(2) && !config.has_channel(0) && !config.has_channel(15));
+        assert_eq!(ScanConfig::decode_u16(&config.encode_u16()), Ok(config));
+        assert_eq!(
+            ScanConfig::decode_u16(&[1, 0, 2, 0]),
+            Err(SerdesError::InvalidEnumValue { field: "ScanConfig.mode", value: 2 })
+        );
+        assert_eq!(ScanConfig { channels: 0, ..ScanConfig::DEFAULT }.validate(), Err(ScanConfigError::NoChannels));
+        assert_eq!(ScanConfig { channels: 0x4000, ..ScanConfig::DEFAULT }.validate(), Err(ScanConfigError::InvalidChannel));
+    }
+
+    #[test]
+    fn wlan_bin_status_versions() {
+        let ipv4 = Ipv4Conf { dhcp: DhcpState::Bound, addr: [10, 0, 0, 2], ..Default::default() };
+        let rcpi = Rcpi::from_rssi(Rssi::from_dbm(-60));
+        let status = WlanBinStatus::new(rcpi, LinkState::Connected, ipv4, b"precursor", Security::Wpa2Psk);
+        let words = status.encode_versioned();
+        let decoded = WlanBinStatus::decode_versioned(&words).unwrap();
+        let expected = (Rcpi(100), LinkState::Connected, Security::Wpa2Psk);
+        assert_eq!((decoded.rcpi, decoded.link_state, decoded.security), expected);
+        assert_eq!(decoded.ssid(), Ok("precursor"));
+        assert_eq!(decoded.ipv4.encode_u16(), ipv4.encode_u16());
+
+        // WLAN_BIN_STATUS sends the untagged layout without the security word
+        assert_eq!(words[1..34], status.encode_u16());
+        let legacy = WlanBinStatus::decode_versioned(&status.encode_u16()).unwrap();
+        assert_eq!(legacy.security, Security::Unknown);
+        assert_eq!(legacy.ssid_bytes(), b"precursor");
+        assert!(!legacy.security.is_supported());
+        assert!(Security::Open.is_open() && Security::Open.is_supported());
+        assert_eq!(Security::decode_u16(Security::Enterprise as u16), Security::Enterprise);
+    }
+
+    #[test]
+    fn mac_address() {
+        let mac = MacAddress([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(mac.encode_u16(), [0x1102, 0x3322, 0x5544]);
+        assert_eq!(MacAddress::decode_u16(&mac.encode_u16()), mac);
+        assert!(mac.is_valid() && mac.is_locally_administered());
+        assert!(!MacAddress::default().is_valid());
+        assert!(!MacAddress([0x01, 0, 0x5E, 0, 0, 1]).is_valid());
+        let random = MacAddress::random_local([0xFF; 6]);
+        assert!(random.is_valid() && random.is_locally_administered());
+        assert_eq!(random.0[0], 0xFE);
+
+        let mut ser = StringSer::<STR_32_WORDS>::new();
+        core::fmt::write(&mut ser, format_args!("{}", mac)).unwrap();
+        let mut des = StringDes::<STR_32_WORDS, STR_32_U8_SIZE>::new();
+        assert_eq!(des.decode_u16(ser.as_u16_slice()), Ok("02:11:22:33:44:55"));
+
+        let config = MacConfig::with_address(random);
+        assert_eq!(MacConfig::decode_u16(&config.encode_u16()), config);
+        assert!(!config.is_factory() && MacConfig::FACTORY.is_factory());
+    }
+
+    #[test]
+    fn known_networks() {
+        let flags = Flags16::empty().with(KNOWN_NET_FLAG_AUTOJOIN);
+        let net = KnownNetwork::new(3, "precursor", "hunter2", flags).unwrap();
+        let words = net.encode_u16();
+        assert_eq!(&words[..3], &[3, 1, 9]);
+        assert_eq!(words[19], 7);
+        let decoded = KnownNetwork::decode_u16(&words).unwrap();
+        assert_eq!(decoded, net);
+        assert_eq!(decoded.pass(), Ok("hunter2"));
+        assert_eq!(
+            KnownNetwork::new(0, "0123456789abcdef0123456789abcdef!", "", flags),
+            Err(SerdesError::StrLenTooBig)
+        );
+
+        let info = KnownNetworkInfo::decode_u16(&net.info().encode_u16()).unwrap();
+        assert_eq!(info.ssid(), Ok("precursor"));
+        assert!(info.flags.contains(KNOWN_NET_FLAG_AUTOJOIN) && !info.is_empty());
+        assert!(KnownNetworkInfo::decode_u16(&KnownNetworkInfo::EMPTY.encode_u16()).unwrap().is_empty());
+
+        let joined = AutojoinResult { slot: 3, result: ConnectResult::AuthFail };
+        assert_eq!(joined.encode_u16(), 0x0403);
+        assert_eq!(AutojoinResult::decode_u16(0x0403), joined);
+    }
+
+    #[test]
+    fn roam_config() {
+        assert!(!RoamConfig::default().is_enabled());
+        let config = RoamConfig { trigger_rssi: Rssi::from_dbm(-75), hysteresis_db: 6, scan_interval_s: 30 };
+        assert!(config.is_enabled());
+        assert_eq!(config.encode_u16(), [0xB500, 6, 30]);
+        assert_eq!(RoamConfig::decode_u16(&config.encode_u16()), config);
+    }
+
+    #[test]
+    fn eap_payloads() {
+        let config = EapConfig {
+            slot: 1,
+            method: EapMethod::Peap,
+            flags: Flags16::empty().with(EAP_FLAG_VERIFY_SERVER),
+            identity_len: 17,
+            password_len: 7,
+            ca_cert_len: 257,
+        };
+        assert_eq!(EapConfig::decode_u16(&config.encode_u16()), Ok(config));
+        assert_eq!(config.fragments_of(EapItem::CaCert), 3);
+        assert_eq!(config.fragments_of(EapItem::Password), 1);
+        assert_eq!(
+            EapConfig::decode_u16(&[1, 2, 0, 0, 0, 0]),
+            Err(SerdesError::InvalidEnumValue { field: "EapConfig.method", value: 2 })
+        );
+
+        let fragment = EapFragment::new(EapItem::Password, 0, b"hunter2");
+        let words = fragment.encode_u16();
+        assert_eq!(&words[..4], &[1, 0, 7, 0x7568]);
+        assert_eq!(EapFragment::decode_u16(&words), Ok(fragment));
+        assert_eq!(EapFragment::new(EapItem::CaCert, 0, &[0; 200]).bytes().len(), EAP_FRAGMENT_LEN);
+        let mut bad = words;
+        bad[0] = 3;
+        assert_eq!(EapFragment::decode_u16(&bad), Err(SerdesError::InvalidEnumValue { field: "EapItem", value: 3 }));
+        assert_eq!(EapCommitStatus::decode_u16(5), Ok(EapCommitStatus::BadCert));
+    }
+
+    #[test]
+    fn wps_pins() {
+        assert_eq!(WpsPin::from_digits(1234567), WpsPin(12345670));
+        assert!(WpsPin(12345670).is_valid());
+        assert!(!WpsPin(12345671).is_valid());
+        assert!(!WpsPin(123456700).is_valid());
+        assert!(WpsPin::from_digits(42).is_valid());
+        let mut ser = StringSer::<STR_32_WORDS>::new();
+        core::fmt::write(&mut ser, format_args!("{}", WpsPin::from_digits(42))).unwrap();
+        let mut des = StringDes::<STR_32_WORDS, STR_32_U8_SIZE>::new();
+        assert_eq!(des.decode_u16(ser.as_u16_slice()), Ok("00000420"));
+
+        let start = WpsPinStart { slot: 2, pin: WpsPin(12345670) };
+        assert_eq!(WpsPinStart::decode_u16(&start.encode_u16()), start);
+        let result = WpsResult { slot: 2, status: WpsStatus::Overlap };
+        assert_eq!(WpsResult::decode_u16(result.encode_u16()), result);
+        assert_eq!(WpsResult::decode_u16(0x0902).status, WpsStatus::Failed);
+    }
+
+    #[test]
+    fn soft_ap() {
+        let mut config = ApConfig::new("precursor-setup", "correct horse", 6).unwrap();
+        config.flags = Flags16::empty().with(AP_FLAG_ISOLATE);
+        assert_eq!(config.validate(), Ok(()));
+        assert!(!config.is_open());
+        let decoded = ApConfig::decode_u16(&config.encode_u16()).unwrap();
+        assert_eq!(decoded, config);
+        assert_eq!(decoded.pass(), Ok("correct horse"));
+        assert_eq!(ApConfig::new("ap", "short", 6).unwrap().validate(), Err(ApConfigError::PassLength));
+        assert_eq!(ApConfig::new("ap", "", 15).unwrap().validate(), Err(ApConfigError::InvalidChannel));
+        assert_eq!(ApConfig { max_clients: 9, ..config }.validate(), Err(ApConfigError::InvalidMaxClients));
+
+        let stations: [ApStation; 5] = core::array::from_fn(|i| ApStation {
+            mac: MacAddress([2, 0, 0, 0, 0, i as u8]),
+            rssi: Rssi::from_dbm(-60),
+        });
+        let page = ApStationPage::from_stations(&stations, 4);
+        assert_eq!((page.total, page.count), (5, 1));
+        let decoded = ApStationPage::decode_u16(&page.encode_u16());
+        assert_eq!(decoded.stations(), &stations[4..]);
+    }
+
+    #[test]
+    fn country_codes() {
+        let ch = CountryCode::new("ch").unwrap();
+        assert_eq!(ch.as_str(), "CH");
+        assert_eq!(ch.encode_u16(), [0x4843]);
+        assert_eq!(CountryCode::decode_u16(&ch.encode_u16()), Ok(ch));
+        assert_eq!(CountryCode::new("00"), Some(CountryCode::WORLD));
+        assert_eq!(CountryCode::new("C"), None);
+        assert_eq!(CountryCode::new("C1"), None);
+        assert_eq!(CountryCode::new("CHE"), None);
+        // only the canonical upper-case form is accepted on the wire
+        assert_eq!(
+            CountryCode::decode_u16(&[0x6863]),
+            Err(SerdesError::InvalidEnumValue { field: "CountryCode", value: 0x6863 })
+        );
+    }
+
+    #[test]
+    fn tx_power() {
+        let power = TxPower::from_dbm(10).unwrap();
+        assert_eq!(power.quarter_dbm(), 40);
+        assert_eq!(power.to_dbm(), I8F8::from_int(10));
+        assert_eq!(TxPower::new(41).unwrap().to_dbm().to_milli(), 10250);
+        assert_eq!(TxPower::decode_u16(&power.encode_u16()), Ok(power));
+        assert_eq!(TxPower::from_dbm(21), None);
+        assert_eq!(TxPower::new(-1), None);
+        assert_eq!(TxPower::decode_u16(&[0xFFFF]), Err(SerdesError::OutOfRange { field: "TxPower", value: 0xFFFF }));
+    }
+
+    #[test]
+    fn channel_list() {
+        // channels 1-11 at 17 dBm, as in the US
+        let mut max_power = [TxPower::MIN; 14];
+        max_power[..11].fill(TxPower::from_dbm(17).unwrap());
+        let list = ChannelList { channels: 0x07FF, max_power };
+        assert!(list.is_allowed(11) && !list.is_allowed(12) && !list.is_allowed(0));
+        assert_eq!(list.max_power(6), TxPower::from_dbm(17));
+        assert_eq!(list.max_power(13), None);
+        assert_eq!(list.iter().count(), 11);
+        assert!(!list.covers(ScanConfig::DEFAULT.channels));
+        assert!(list.covers(ScanConfig { channels: 0b100_0010_0001, ..ScanConfig::DEFAULT }.channels));
+        assert!(!list.is_allowed(ApConfig::new("ap", "", 13).unwrap().channel));
+
+        let words = list.encode_u16();
+        assert_eq!(&words[10..13], &[68, 68, 0]);
+        assert_eq!(ChannelList::decode_u16(&words), Ok(list));
+        let mut bad = words;
+        bad[0] = 0x8000;
+        assert_eq!(
+            ChannelList::decode_u16(&bad),
+            Err(SerdesError::OutOfRange { field: "ChannelList.channels", value: 0x8000 })
+        );
+    }
+
+    #[test]
+    fn multicast_filter() {
+        let mdns = MacAddress::from_ipv4_multicast([224, 0, 0, 251]);
+        assert_eq!(mdns, MacAddress([0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]));
+        let ssdp = MacAddress::from_ipv4_multicast([239, 255, 255, 250]);
+        assert_eq!(ssdp, MacAddress([0x01, 0x00, 0x5E, 0x7F, 0xFF, 0xFA]));
+
+        let mut table = McastFilterTable::new();
+        assert!(table.insert(mdns) && table.insert(ssdp) && table.insert(mdns));
+        assert!(!table.insert(MacAddress([0x02, 0, 0, 0, 0, 1])));
+        assert_eq!(table.entries(), &[mdns, ssdp]);
+        assert_eq!(McastFilterTable::decode_u16(&table.encode_u16()), table);
+        table.remove(&mdns);
+        assert_eq!(table.entries(), &[ssdp]);
+        assert!(!table.contains(&mdns));
+        for i in 0..MCAST_FILTER_LEN as u8 {
+            table.insert(MacAddress::from_ipv4_multicast([239, 0, 0, i]));
+        }
+        assert_eq!(table.entries().len(), MCAST_FILTER_LEN);
+        assert!(!table.insert(mdns));
+    }
+
+    #[test]
+    fn monitor_config() {
+        let config = MonitorConfig { channel: 6, flags: Flags16::empty().with(MONITOR_FLAG_MGMT) };
+        assert!(config.is_valid());
+        assert!(!MonitorConfig { channel: 0, ..config }.is_valid());
+        assert_eq!(MonitorConfig::decode_u16(&config.encode_u16()), config);
+        assert!(is_audited(ComState::WLAN_MONITOR_START.verb));
+    }
+
+    #[test]
+    fn power_save_config() {
+        assert!(!PowerSaveConfig::default().is_enabled());
+        let config = PowerSaveConfig { mode: PowerSaveMode::Max, listen_interval: 3, dtim_skip: 1 };
+        assert!(config.is_enabled());
+        assert_eq!(PowerSaveConfig::decode_u16(&config.encode_u16()), Ok(config));
+        assert_eq!(
+            PowerSaveConfig::decode_u16(&[3, 0, 0]),
+            Err(SerdesError::InvalidEnumValue { field: "PowerSaveConfig.mode", value: 3 })
+        );
+    }
+
+    #[test]
+    fn phy_status() {
+        assert!(PhyMode::Gn.allows(PhyStandard::G) && !PhyMode::Gn.allows(PhyStandard::B));
+        assert!(PhyMode::ALL.iter().all(|mode| PhyMode::decode_u16(&mode.encode_u16()) == Ok(*mode)));
+        assert_eq!(PhyRate::B5_5Mbps.kbps(), 5500);
+        assert_eq!(PhyRate::Mcs7.kbps(), 65000);
+        assert!(PhyRate::decode_u16(4).is_err());
+
+        let mut rates = RateSet::default();
+        assert_eq!(rates.standard(), None);
+        rates.insert(PhyRate::G54Mbps);
+        rates.insert(PhyRate::B11Mbps);
+        assert_eq!(rates.standard(), Some(PhyStandard::G));
+        assert!(rates.iter().eq([PhyRate::B11Mbps, PhyRate::G54Mbps].iter().copied()));
+
+        let status = PhyStatus { mode: PhyMode::Bg, tx_rate: None, rates };
+        assert_eq!(status.encode_u16()[1], PHY_RATE_NONE);
+        assert_eq!(PhyStatus::decode_u16(&status.encode_u16()), Ok(status));
+    }
+
+    #[test]
+    fn mtu() {
+        assert_eq!(Mtu::default().max_frame_len(), 1514);
+        assert_eq!(Mtu::MAX.get(), 2023);
+        assert_eq!(Mtu::DEFAULT.max_verb_len(Flags16::empty()), 1514);
+        assert_eq!(Mtu::MAX.max_verb_len(frame::NET_FRAME_FLAGS_ALL), NET_FRAME_MAX_LEN);
+        // fetched frames carry RxMeta and a fragment header, sent ones a Priority word and one
+        let fetch = frame::NET_FRAME_FLAG_RX_META.with(frame::NET_FRAME_FLAG_FRAGMENTS);
+        assert!(ComState::net_frame_fetch(Mtu::MAX.max_verb_len(fetch)).is_ok());
+        let send = frame::NET_FRAME_FLAG_TX_PRIORITY.with(frame::NET_FRAME_FLAG_FRAGMENTS);
+        assert_eq!(Mtu::MAX.max_verb_len(send), NET_FRAME_MAX_LEN - 6);
+        assert_eq!(Mtu::new(1279), None);
+        assert_eq!(Mtu::decode_u16(&Mtu::DEFAULT.encode_u16()), Ok(Mtu::DEFAULT));
+        assert_eq!(Mtu::decode_u16(&[0x0800]), Err(SerdesError::OutOfRange { field: "Mtu", value: 0x0800 }));
+    }
+
+    #[test]
+    fn multicast_groups() {
+        let mdns = MulticastGroup::new([224, 0, 0, 251]).unwrap();
+        assert!(mdns.is_link_local());
+        assert_eq!(mdns.mac(), MacAddress([0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]));
+        let ssdp = MulticastGroup::new([239, 255, 255, 250]).unwrap();
+        assert!(!ssdp.is_link_local());
+        assert_eq!(MulticastGroup::decode_u16(&ssdp.encode_u16()), Ok(ssdp));
+        assert_eq!(MulticastGroup::new(MulticastGroup::ALL_HOSTS), None);
+        assert_eq!(MulticastGroup::new([192, 168, 0, 1]), None);
+        assert!(MulticastGroup::decode_u16(&[0x0A0A, 0]).is_err());
+    }
+
+    #[test]
+    fn arp_offload() {
+        assert!(!ArpOffloadConfig::default().is_enabled());
+        let config = ArpOffloadConfig::enabled([192, 168, 1, 20]);
+        assert!(config.is_enabled());
+        assert_eq!(ArpOffloadConfig::decode_u16(&config.encode_u16()), config);
+        let status = ArpOffloadStatus { config, answered: 0x0001_0002 };
+        assert_eq!(ArpOffloadStatus::decode_u16(&status.encode_u16()), status);
+    }
+
+    #[test]
+    fn wake_patterns() {
+        let mut table = WakePatternTable::default();
+        table.patterns[0] = Some(WakePattern::MagicPacket);
+        table.patterns[3] = Some(WakePattern::TcpSyn { port: 22 });
+        table.patterns[7] = Some(WakePattern::SourceIp([10, 0, 0, 1]));
+        assert_eq!(WakePatternTable::decode_u16(&table.encode_u16()), Ok(table));
+        assert_eq!(table.find(&WakePattern::TcpSyn { port: 22 }), Some(3));
+        assert_eq!(table.find(&WakePattern::TcpSyn { port: 80 }), None);
+
+        let entry = table.entries().nth(7).unwrap();
+        assert_eq!(entry.encode_u16(), [7, 3, 0x000A, 0x0100]);
+        assert_eq!(WakePatternEntry::decode_u16(&entry.encode_u16()), Ok(entry));
+        assert_eq!(table.entries().filter(|e| e.pattern.is_none()).count(), 5);
+        assert_eq!(
+            WakePatternEntry::decode_u16(&[8, 1, 0, 0]),
+            Err(SerdesError::OutOfRange { field: "WakePatternEntry.slot", value: 8 })
+        );
+        assert!(WakePatternEntry::decode_u16(&[0, 4, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn keepalive_templates() {
+        let tuple = TcpTuple { local_port: 49152, remote_addr: [203, 0, 113, 7], remote_port: 5222 };
+        let template = KeepaliveTemplate::new(1, tuple, 45, 0x1234_5678, 0x9ABC_DEF0, 0x7210);
+        let template = template.with_payload(b" ").unwrap();
+        assert_eq!(template.payload(), b" ");
+        assert_eq!(KeepaliveTemplate::decode_u16(&template.encode_u16()), Ok(template));
+        assert!(KeepaliveTemplate::clear(1).is_clear());
+        assert_eq!(template.with_payload(&[0; KEEPALIVE_PAYLOAD_LEN + 1]), Err(SerdesError::BytesLenTooBig));
+        let mut words = template.encode_u16();
+        words[0] = KEEPALIVE_SLOTS as u16;
+        assert!(KeepaliveTemplate::decode_u16(&words).is_err());
+
+        let status = KeepaliveStatus { slot: 1, state: KeepaliveState::Active, sent: 10, acked: 9 };
+        assert_eq!(KeepaliveStatus::decode_u16(&status.encode_u16()), Ok(status));
+        assert!(KeepaliveStatus::decode_u16(&[1, 4, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn mdns_services() {
+        let service = MdnsService::new(0, "_http._tcp", "precursor", 80).unwrap().with_txt(b"\x06path=/").unwrap();
+        assert_eq!(service.service(), Ok("_http._tcp"));
+        assert_eq!(service.instance(), Ok("precursor"));
+        assert_eq!(service.txt(), b"\x06path=/");
+        assert_eq!(MdnsService::decode_u16(&service.encode_u16()), Ok(service));
+        assert!(MdnsService::clear(0).is_clear() && !service.is_clear());
+        assert_eq!(MdnsService::new(0, "_a-much-too-long-service-type._tcp", "", 1), Err(SerdesError::StrLenTooBig));
+        let mut words = service.encode_u16();
+        words[0] = MDNS_SERVICE_SLOTS as u16;
+        assert!(MdnsService::decode_u16(&words).is_err());
+
+        let stats = MdnsStats { queries: 70000, answered: 12 };
+        assert_eq!(MdnsStats::decode_u16(&stats.encode_u16()), stats);
+    }
+
+    #[test]
+    fn traffic_stats() {
+        let stats = TrafficStats { tx_bytes: 0x0001_0000, rx_bytes: 3, tx_retries: 7, ..Default::default() };
+        assert_eq!(TrafficStats::decode_u16(&stats.encode_u16()), stats);
+        let earlier = TrafficStats { tx_bytes: 0xFFFF_FFF0, rx_bytes: 1, ..Default::default() };
+        let delta = stats.since(&earlier);
+        assert_eq!((delta.tx_bytes, delta.rx_bytes, delta.tx_retries), (0x0001_0010, 2, 7));
+    }
+
+    #[test]
+    fn conn_diagnostics() {
+        assert_eq!(DisconnectReason::from_code(0), None);
+        for code in 1..=64 {
+            assert_eq!(DisconnectReason::from_code(code).map(|r| r.code()), Some(code));
+        }
+        assert_eq!(DisconnectReason::from_code(15), Some(DisconnectReason::HandshakeTimeout));
+        assert!(DisconnectReason::HandshakeTimeout.is_auth_failure());
+        assert!(!DisconnectReason::Other(99).is_auth_failure());
+
+        let diag = ConnDiagnostics {
+            reason: Some(DisconnectReason::PoorChannel),
+            flags: Flags16::empty().with(DIAG_FLAG_DISASSOC),
+            beacon_misses: 0x0002_0001,
+            retry_permille: 275,
+        };
+        assert_eq!(ConnDiagnostics::decode_u16(&diag.encode_u16()), diag);
+        assert_eq!(ConnDiagnostics::decode_u16(&[0; 5]), ConnDiagnostics::default());
+    }
+
+    #[test]
+    fn rssi_history() {
+        assert_eq!(RssiHistoryConfig::new(1000, 100).depth, RSSI_HISTORY_MAX as u16);
+        assert!(!RssiHistoryConfig::DISABLED.is_enabled());
+        let config = RssiHistoryConfig::new(500, 16);
+        assert_eq!(RssiHistoryConfig::decode_u16(&config.encode_u16()), Ok(config));
+        assert!(RssiHistoryConfig::decode_u16(&[500, 33]).is_err());
+
+        let readings: [Rssi; 40] = core::array::from_fn(|i| Rssi::from_dbm(-40 - i as i8));
+        let history = RssiHistory::from_samples(500, &readings);
+        assert_eq!(history.samples(), &readings[8..]);
+        assert_eq!(history.latest(), Some(Rssi::from_dbm(-79)));
+        assert_eq!(RssiHistory::decode_u16(&history.encode_u16()), history);
+
+        let short = RssiHistory::from_samples(0, &readings[..3]);
+        assert_eq!(RssiHistory::decode_u16(&short.encode_u16()).samples(), &readings[..3]);
+        assert_eq!(RssiHistory::from_samples(0, &[]).latest(), None);
+    }
+
+    #[test]
+    fn rssi_filter() {
+        let config = RssiFilterConfig::default();
+        assert_eq!(RssiFilterConfig::decode_u16(&config.encode_u16()), Ok(config));
+        assert!(RssiFilterConfig::decode_u16(&[9, 0x0300]).is_err());
+        assert!(RssiFilterConfig::decode_u16(&[3, I8F8::from_int(-1).encode_u16()]).is_err());
+
+        let avg = config.filter(Rssi::from_dbm(-60), Rssi::from_dbm(-76));
+        assert_eq!(avg, Rssi::from_dbm(-62));
+        assert!(!config.should_report(Rssi::from_dbm(-60), avg));
+        assert!(config.should_report(Rssi::from_dbm(-60), Rssi::from_dbm(-63)));
+        assert!(config.should_report(Rssi::from_dbm(-60), Rssi::from_dbm(-57)));
+        let silent = RssiFilterConfig { min_delta: I8F8::ZERO, ..config };
+        assert!(!silent.should_report(Rssi::from_dbm(-60), Rssi::from_dbm(-90)));
+    }
+
+    #[test]
+    fn wlan_err_counts() {
+        let counts = WlanErrCounts { tx_errors: 3, rx_drops: 12, driver_resets: 1, alloc_fails: 0 };
+        assert_eq!(counts.encode_u16(), [3, 12, 1, 0]);
+        assert_eq!(WlanErrCounts::decode_u16(&counts.encode_u16()), counts);
+        assert!(WlanErrCounts::decode_u16(&[0; 4]).is_clean());
+        let later = WlanErrCounts { tx_errors: 5, ..counts };
+        assert_eq!(later.since(&counts), WlanErrCounts { tx_errors: 2, ..Default::default() });
+    }
+
+    #[test]
+    fn rssi_quality() {
+        assert_eq!(Rssi::decode_u16(0xC400), Rssi::from_dbm(-60));
+        assert_eq!(Rssi::from_dbm(-60).encode_u16(), 0xC400);
+        let fine = Rssi::decode_u16(I8F8::from_milli(-61_500).encode_u16());
+        assert_eq!((fine.dbm(), fine.encode_u16()), (-61, 0xC280));
+
+        // legacy RCPI: 0.5 dB steps from -110 dBm
+        assert_eq!(Rcpi::from_rssi(fine), Rcpi(97));
+        assert_eq!(Rcpi(97).rssi(), Some(fine));
+        assert_eq!(Rcpi(0).rssi(), Some(Rssi::from_dbm(-110)));
+        assert_eq!(Rcpi(220).rssi(), Some(Rssi::from_dbm(0)));
+        assert_eq!(Rcpi::UNAVAILABLE.rssi(), None);
+        assert_eq!(Rcpi::from_rssi(Rssi::from_dbm(-120)), Rcpi(0));
+        assert_eq!(Rcpi::from_rssi(Rssi::from_dbm(10)), Rcpi(220));
+        assert_eq!(Rcpi::decode_u16(0x0064), Ok(Rcpi(100)));
+        assert!(Rcpi::decode_u16(0xFFC4).is_err());
+
+        assert_eq!(Rssi::from_dbm(-40).quality(), SignalQuality::Excellent);
+        assert_eq!(Rssi::from_dbm(-67).quality(), SignalQuality::Good);
+        assert_eq!(Rssi::from_dbm(-70).quality(), SignalQuality::Fair);
+        assert_eq!(Rssi::from_dbm(-85).quality().bars(), 1);
+        assert_eq!(Rssi::from_dbm(-90).quality().bars(), 0);
+    }
+
+    #[test]
+    fn connect_status() {
+        let status = ConnectStatus { result: ConnectResult::AuthFail, detail: ConnectDetail::WrongPassword };
+        assert_eq!(status.encode_u16(), [4, 1]);
+        assert_eq!(ConnectStatus::decode_u16(&status.encode_u16()), Ok(status));
+        assert_eq!(
+            ConnectStatus::decode_u16(&[3, 0x1234]),
+            Err(SerdesError::InvalidEnumValue { field: "ConnectStatus.detail", value: 0x1234 })
+        );
+        // the legacy one-word form still decodes on its own
+        assert_eq!(ConnectResult::decode_u16(status.encode_u16()[0]), ConnectResult::AuthFail);
+        let joined = AutojoinResult::decode_u16(0x0002);
+        assert_eq!(ConnectStatus::from_result(joined.result).detail, ConnectDetail::None);
     }
 }
@@ -0,0 +1,291 @@
+#![forbid(unsafe_code)]
+
+// Incremental decoding of large responses.
+//
+// Some responses (EC_LOG_FETCH, AUDIT_LOG_FETCH, LINK_EVENT_FETCH, NET_TX_STATUS, SSID_SCAN_FETCH,
+// WLAN_GET_RSSI_HISTORY) are a short header followed by a long run of fixed-size records. Rather than buffering
+// the whole response before decoding it, the host can push each word into an `IncrementalDecoder` as it drains
+// the FIFO, and handle records as they complete.
+
+use crate::serdes::{
+    known_enum_value, AuditRecord, AuditResult, ComEvent, Flags16, LogRecord, Rssi, ScanResult, SerdesError,
+    TxCompletion, SCAN_RESULT_WORDS,
+};
+
+/// A decoder that consumes a response one word at a time
+pub trait IncrementalDecoder {
+    type Item;
+    /// Feed the next word of the response; returns an item each time one is complete
+    fn push_word(&mut self, word: u16) -> Option<Self::Item>;
+    /// Prepare to decode a new response
+    fn reset(&mut self);
+}
+
+/// A fixed-size record that can appear in a paged response
+pub trait WireRecord: Sized {
+    /// number of words per record
+    const WORDS: usize;
+    /// Decode one record from exactly `WORDS` words
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError>;
+}
+
+impl WireRecord for LogRecord {
+    const WORDS: usize = LogRecord::WORDS;
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        let mut data = [0u16; LogRecord::WORDS];
+        data.copy_from_slice(words);
+        LogRecord::decode_u16(&data)
+    }
+}
+
+impl WireRecord for ComEvent {
+    const WORDS: usize = 4;
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        Ok(ComEvent::decode_u16(&[words[0], words[1], words[2], words[3]]))
+    }
+}
+
+impl WireRecord for AuditRecord {
+    const WORDS: usize = 4;
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        known_enum_value::<AuditResult>("AuditRecord.result", words[3])?;
+        Ok(AuditRecord::decode_u16(&[words[0], words[1], words[2], words[3]]))
+    }
+}
+
+impl WireRecord for TxCompletion {
+    const WORDS: usize = 2;
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        Ok(TxCompletion::decode_u16(&[words[0], words[1]]))
+    }
+}
+
+impl WireRecord for ScanResult {
+    const WORDS: usize = SCAN_RESULT_WORDS;
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        let mut data = [0u16; SCAN_RESULT_WORDS];
+        data.copy_from_slice(words);
+        ScanResult::decode_u16(&data)
+    }
+}
+
+impl WireRecord for Rssi {
+    const WORDS: usize = 1;
+    fn decode_words(words: &[u16]) -> Result<Self, SerdesError> {
+        Ok(Rssi::decode_u16(words[0]))
+    }
+}
+
+/// The words of a paged response that precede its records
+pub trait PageHeader: Copy {
+    /// number of words in the header
+    const WORDS: usize;
+    /// Decode the header from exactly `WORDS` words
+    fn decode_words(words: &[u16]) -> Self;
+    /// Number of valid records that follow the header
+    fn count(&self) -> usize;
+}
+
+/// Header of EC_LOG_FETCH, AUDIT_LOG_FETCH, LINK_EVENT_FETCH and NET_TX_STATUS: one word, the number of valid
+/// records in bits 0-7 and the number still pending on the EC in bits 8-15
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CountPendingHeader {
+    pub count: u8,
+    pub pending: u8,
+}
+impl PageHeader for CountPendingHeader {
+    const WORDS: usize = 1;
+    fn decode_words(words: &[u16]) -> Self {
+        CountPendingHeader { count: words[0] as u8, pending: (words[0] >> 8) as u8 }
+    }
+    fn count(&self) -> usize {
+        self.count as usize
+    }
+}
+
+/// Header of SSID_SCAN_FETCH, the first 4 words of serdes::ScanResultPage
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ScanPageHeader {
+    pub total: u16,
+    pub count: u16,
+    pub flags: Flags16,
+    pub scan_id: u16,
+}
+impl PageHeader for ScanPageHeader {
+    const WORDS: usize = 4;
+    fn decode_words(words: &[u16]) -> Self {
+        ScanPageHeader { total: words[0], count: words[1], flags: Flags16::decode_u16(words[2]), scan_id: words[3] }
+    }
+    fn count(&self) -> usize {
+        self.count as usize
+    }
+}
+
+/// Header of WLAN_GET_RSSI_HISTORY, the first 2 words of serdes::RssiHistory
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RssiHistoryHeader {
+    pub interval_ms: u16,
+    pub count: u16,
+}
+impl PageHeader for RssiHistoryHeader {
+    const WORDS: usize = 2;
+    fn decode_words(words: &[u16]) -> Self {
+        RssiHistoryHeader { interval_ms: words[0], count: words[1] }
+    }
+    fn count(&self) -> usize {
+        self.count as usize
+    }
+}
+
+/// Decoder for paged responses made of a header `H` followed by records.
+///
+/// The header announces the number of valid records; padding records past the valid count are
+/// consumed silently. `MAX_WORDS` is the size of the word buffer and must be at least `T::WORDS`
+/// and `H::WORDS`.
+pub struct PageDecoder<T: WireRecord, const MAX_WORDS: usize, H: PageHeader = CountPendingHeader> {
+    buf: [u16; MAX_WORDS],
+    fill: usize,
+    header: Option<H>,
+    decoded: usize,
+    _record: core::marker::PhantomData<T>,
+}
+impl<T: WireRecord, const MAX_WORDS: usize, H: PageHeader> PageDecoder<T, MAX_WORDS, H> {
+    pub fn new() -> Self {
+        assert!(MAX_WORDS >= T::WORDS, "PageDecoder buffer is smaller than one record");
+        assert!(MAX_WORDS >= H::WORDS, "PageDecoder buffer is smaller than the page header");
+        PageDecoder {
+            buf: [0; MAX_WORDS],
+            fill: 0,
+            header: None,
+            decoded: 0,
+            _record: core::marker::PhantomData,
+        }
+    }
+    /// The page header, once it has been received
+    pub fn header(&self) -> Option<&H> {
+        self.header.as_ref()
+    }
+    /// Number of valid records announced by the header, once it has been received
+    pub fn count(&self) -> Option<usize> {
+        self.header.map(|h| h.count())
+    }
+}
+impl<T: WireRecord, const MAX_WORDS: usize> PageDecoder<T, MAX_WORDS, CountPendingHeader> {
+    /// Number of records still pending on the EC after this page, once the header has been received
+    pub fn pending(&self) -> Option<usize> {
+        self.header.map(|h| h.pending as usize)
+    }
+}
+impl<T: WireRecord, const MAX_WORDS: usize, H: PageHeader> Default for PageDecoder<T, MAX_WORDS, H> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl<T: WireRecord, const MAX_WORDS: usize, H: PageHeader> IncrementalDecoder for PageDecoder<T, MAX_WORDS, H> {
+    type Item = Result<T, SerdesError>;
+    fn push_word(&mut self, word: u16) -> Option<Self::Item> {
+        let count = match self.count() {
+            None => {
+                self.buf[self.fill] = word;
+                self.fill += 1;
+                if self.fill == H::WORDS {
+                    self.header = Some(H::decode_words(&self.buf[..H::WORDS]));
+                    self.fill = 0;
+                }
+                return None;
+            }
+            Some(count) => count,
+        };
+        self.buf[self.fill] = word;
+        self.fill += 1;
+        if self.fill < T::WORDS {
+            return None;
+        }
+        self.fill = 0;
+        if self.decoded >= count {
+            return None;
+        }
+        self.decoded += 1;
+        Some(T::decode_words(&self.buf[..T::WORDS]))
+    }
+    fn reset(&mut self) {
+        self.fill = 0;
+        self.header = None;
+        self.decoded = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serdes::{EventPage, LogSeverity, RssiHistory, ScanResultPage, Security, EC_LOG_PAGE_LEN, EVENT_PAGE_LEN};
+    use crate::{ComState, InterruptFlags};
+
+    #[test]
+    fn decode_log_page_word_by_word() {
+        let mut response = [0u16; ComState::EC_LOG_FETCH.r_words as usize];
+        response[0] = 2 | 5 << 8;
+        let first = LogRecord::new(LogSeverity::Warn, 1000, "battery getting warm");
+        let second = LogRecord::new(LogSeverity::Error, 1001, "wfx hif error");
+        response[1..1 + LogRecord::WORDS].copy_from_slice(&first.encode_u16());
+        response[1 + LogRecord::WORDS..1 + 2 * LogRecord::WORDS].copy_from_slice(&second.encode_u16());
+
+        let mut decoder = PageDecoder::<LogRecord, { LogRecord::WORDS }>::new();
+        let mut records = 0;
+        for word in response.iter() {
+            if let Some(record) = decoder.push_word(*word) {
+                let record = record.unwrap();
+                assert_eq!(record, if records == 0 { first } else { second });
+                records += 1;
+            }
+        }
+        assert_eq!(records, 2);
+        assert_eq!(decoder.pending(), Some(5));
+        assert_eq!(EC_LOG_PAGE_LEN * LogRecord::WORDS + 1, response.len());
+        assert_eq!(second.message().unwrap(), "wfx hif error");
+    }
+
+    #[test]
+    fn decode_event_page_word_by_word() {
+        let mut page = EventPage { count: 1, pending: 0, events: [EventPage::EMPTY_EVENT; EVENT_PAGE_LEN] };
+        page.events[0] = ComEvent { source: InterruptFlags::CHG_REMOVED, timestamp_ms: 7, arg: 0 };
+        let mut decoder = PageDecoder::<ComEvent, 4>::new();
+        let events: usize = page.encode_u16().iter().filter_map(|w| decoder.push_word(*w)).count();
+        assert_eq!(events, 1);
+    }
+
+    #[test]
+    fn decode_scan_page_word_by_word() {
+        let results: [ScanResult; 3] = core::array::from_fn(|i| {
+            ScanResult::new(b"ap", [2, 0, 0, 0, 0, i as u8], Rssi::from_dbm(-50), 6, Security::Open)
+        });
+        let page = ScanResultPage { scan_id: 9, ..ScanResultPage::from_results(&results, 0) };
+        let mut decoder = PageDecoder::<ScanResult, SCAN_RESULT_WORDS, ScanPageHeader>::new();
+        let mut decoded = 0;
+        for record in page.encode_u16().iter().filter_map(|w| decoder.push_word(*w)) {
+            assert_eq!(record, Ok(results[decoded]));
+            decoded += 1;
+        }
+        assert_eq!(decoded, results.len());
+        assert_eq!(decoder.header().map(|h| (h.total, h.scan_id)), Some((3, 9)));
+
+        decoder.reset();
+        let mut words = page.encode_u16();
+        words[4 + 21] = 0x7F06; // unknown security on the first record
+        assert!(words.iter().filter_map(|w| decoder.push_word(*w)).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn decode_rssi_history_word_by_word() {
+        let samples = [Rssi::from_dbm(-60), Rssi::from_dbm(-62), Rssi::from_dbm(-65)];
+        let history = RssiHistory::from_samples(1000, &samples);
+        let mut decoder = PageDecoder::<Rssi, 2, RssiHistoryHeader>::new();
+        let mut decoded = 0;
+        for sample in history.encode_u16().iter().filter_map(|w| decoder.push_word(*w)) {
+            assert_eq!(sample, Ok(samples[decoded]));
+            decoded += 1;
+        }
+        assert_eq!(decoded, samples.len());
+        assert_eq!(decoder.header(), Some(&RssiHistoryHeader { interval_ms: 1000, count: 3 }));
+    }
+}
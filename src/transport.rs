@@ -0,0 +1,299 @@
+#![forbid(unsafe_code)]
+
+// Verb-level transport abstraction for hosts of the COM bus.
+//
+// A host driver (the Xous com server, test rigs, factory tools) implements the three raw
+// operations of `ComTransport` for its hardware. The provided methods then implement the FIFO
+// discipline once, against `ComSpec`: the verb goes out first, followed by exactly `w_words`
+// of payload, and exactly `r_words` are read back. Getting a count wrong desyncs the FIFO
+// until the next LINK_SYNC, so the provided methods refuse mismatched buffers before anything
+// touches the bus.
+
+use crate::ComSpec;
+
+#[cfg(feature = "std")]
+pub mod mock;
+#[cfg(feature = "std")]
+pub mod trace;
+#[cfg(feature = "spi")]
+pub mod spi;
+
+/// Errors from the provided `ComTransport` methods
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TransportError<E> {
+    /// the underlying bus reported an error
+    Bus(E),
+    /// the payload buffer does not match the verb's w_words
+    WriteLength { verb: u16, expected: u16, actual: usize },
+    /// the response buffer does not match the verb's r_words
+    ReadLength { verb: u16, expected: u16, actual: usize },
+}
+
+fn check_lengths<E>(spec: &ComSpec, tx: &[u16], rx: &[u16]) -> Result<(), TransportError<E>> {
+    if tx.len() != spec.w_words as usize {
+        return Err(TransportError::WriteLength { verb: spec.verb, expected: spec.w_words, actual: tx.len() });
+    }
+    if rx.len() != spec.r_words as usize {
+        return Err(TransportError::ReadLength { verb: spec.verb, expected: spec.r_words, actual: rx.len() });
+    }
+    Ok(())
+}
+
+/// Blocking access to the COM bus, one verb at a time
+pub trait ComTransport {
+    type Error;
+
+    /// Send a verb word
+    fn send_verb(&mut self, verb: u16) -> Result<(), Self::Error>;
+    /// Send payload words following a verb
+    fn write_words(&mut self, words: &[u16]) -> Result<(), Self::Error>;
+    /// Read response words. The implementation generates the dummy exchanges (LINK_READ)
+    /// that pump the EC's FIFO, one per word read.
+    fn read_words(&mut self, words: &mut [u16]) -> Result<(), Self::Error>;
+
+    /// Issue `spec` with payload `tx` and read its response into `rx`, checking both lengths first.
+    fn exchange(&mut self, spec: &ComSpec, tx: &[u16], rx: &mut [u16]) -> Result<(), TransportError<Self::Error>> {
+        check_lengths(spec, tx, rx)?;
+        self.send_verb(spec.verb).map_err(TransportError::Bus)?;
+        if !tx.is_empty() {
+            self.write_words(tx).map_err(TransportError::Bus)?;
+        }
+        if !rx.is_empty() {
+            self.read_words(rx).map_err(TransportError::Bus)?;
+        }
+        Ok(())
+    }
+
+    /// Issue a verb that returns nothing
+    fn command(&mut self, spec: &ComSpec, tx: &[u16]) -> Result<(), TransportError<Self::Error>> {
+        self.exchange(spec, tx, &mut [])
+    }
+
+    /// Issue a verb that takes no payload and read its response
+    fn query(&mut self, spec: &ComSpec, rx: &mut [u16]) -> Result<(), TransportError<Self::Error>> {
+        self.exchange(spec, &[], rx)
+    }
+}
+
+/// Instrumentation callbacks around every exchange, for plugging in log, defmt or metrics sinks.
+/// All methods default to doing nothing; `()` is the no-op implementation.
+pub trait Hooks {
+    /// Called before the verb is sent, with the payload about to be written
+    fn on_verb_start(&mut self, _spec: &ComSpec, _tx: &[u16]) {}
+    /// Called after the response has been read in full
+    fn on_verb_end(&mut self, _spec: &ComSpec, _rx: &[u16]) {}
+    /// Called instead of `on_verb_end` when the exchange fails
+    fn on_error(&mut self, _spec: &ComSpec, _error: &dyn core::fmt::Debug) {}
+}
+impl Hooks for () {}
+
+/// A ComTransport that reports every exchange to a set of Hooks
+pub struct Instrumented<T, H> {
+    inner: T,
+    hooks: H,
+}
+impl<T: ComTransport, H: Hooks> Instrumented<T, H> {
+    pub fn new(inner: T, hooks: H) -> Self {
+        Instrumented { inner, hooks }
+    }
+    pub fn hooks(&self) -> &H {
+        &self.hooks
+    }
+    pub fn hooks_mut(&mut self) -> &mut H {
+        &mut self.hooks
+    }
+    pub fn into_inner(self) -> (T, H) {
+        (self.inner, self.hooks)
+    }
+}
+impl<T, H> ComTransport for Instrumented<T, H>
+where
+    T: ComTransport,
+    T::Error: core::fmt::Debug,
+    H: Hooks,
+{
+    type Error = T::Error;
+
+    fn send_verb(&mut self, verb: u16) -> Result<(), Self::Error> {
+        self.inner.send_verb(verb)
+    }
+    fn write_words(&mut self, words: &[u16]) -> Result<(), Self::Error> {
+        self.inner.write_words(words)
+    }
+    fn read_words(&mut self, words: &mut [u16]) -> Result<(), Self::Error> {
+        self.inner.read_words(words)
+    }
+
+    fn exchange(&mut self, spec: &ComSpec, tx: &[u16], rx: &mut [u16]) -> Result<(), TransportError<Self::Error>> {
+        self.hooks.on_verb_start(spec, tx);
+        match self.inner.exchange(spec, tx, rx) {
+            Ok(()) => {
+                self.hooks.on_verb_end(spec, rx);
+                Ok(())
+            }
+            Err(e) => {
+                self.hooks.on_error(spec, &e);
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Async access to the COM bus, for hosts that should not block while waiting on FIFO readiness.
+///
+/// Mirrors `ComTransport`: implement the three raw operations, and the provided `exchange`,
+/// `command` and `query` enforce the same length rules before the bus is touched.
+#[cfg(feature = "async")]
+#[allow(async_fn_in_trait)]
+pub trait AsyncComTransport {
+    type Error;
+
+    /// Send a verb word
+    async fn send_verb(&mut self, verb: u16) -> Result<(), Self::Error>;
+    /// Send payload words following a verb
+    async fn write_words(&mut self, words: &[u16]) -> Result<(), Self::Error>;
+    /// Read response words, pumping the EC's FIFO with LINK_READ as for `ComTransport::read_words`.
+    async fn read_words(&mut self, words: &mut [u16]) -> Result<(), Self::Error>;
+
+    /// Issue `spec` with payload `tx` and read its response into `rx`, checking both lengths first.
+    async fn exchange(&mut self, spec: &ComSpec, tx: &[u16], rx: &mut [u16]) -> Result<(), TransportError<Self::Error>> {
+        check_lengths(spec, tx, rx)?;
+        self.send_verb(spec.verb).await.map_err(TransportError::Bus)?;
+        if !tx.is_empty() {
+            self.write_words(tx).await.map_err(TransportError::Bus)?;
+        }
+        if !rx.is_empty() {
+            self.read_words(rx).await.map_err(TransportError::Bus)?;
+        }
+        Ok(())
+    }
+
+    /// Issue a verb that returns nothing
+    async fn command(&mut self, spec: &ComSpec, tx: &[u16]) -> Result<(), TransportError<Self::Error>> {
+        self.exchange(spec, tx, &mut []).await
+    }
+
+    /// Issue a verb that takes no payload and read its response
+    async fn query(&mut self, spec: &ComSpec, rx: &mut [u16]) -> Result<(), TransportError<Self::Error>> {
+        self.exchange(spec, &[], rx).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ComState;
+
+    struct Recorder {
+        sent: [u16; 8],
+        len: usize,
+    }
+    impl ComTransport for Recorder {
+        type Error = ();
+        fn send_verb(&mut self, verb: u16) -> Result<(), ()> {
+            self.write_words(&[verb])
+        }
+        fn write_words(&mut self, words: &[u16]) -> Result<(), ()> {
+            self.sent[self.len..self.len + words.len()].copy_from_slice(words);
+            self.len += words.len();
+            Ok(())
+        }
+        fn read_words(&mut self, words: &mut [u16]) -> Result<(), ()> {
+            words.iter_mut().for_each(|w| *w = 0xAAAA);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn lengths_are_checked_before_the_bus() {
+        let mut bus = Recorder { sent: [0; 8], len: 0 };
+        assert_eq!(
+            bus.command(&ComState::FLASH_ERASE, &[0; 3]),
+            Err(TransportError::WriteLength { verb: ComState::FLASH_ERASE.verb, expected: 4, actual: 3 })
+        );
+        assert_eq!(bus.len, 0);
+        bus.command(&ComState::FLASH_ERASE, &[1, 2, 3, 4]).unwrap();
+        assert_eq!(bus.sent[..bus.len], [ComState::FLASH_ERASE.verb, 1, 2, 3, 4]);
+
+        let mut uptime = [0u16; 4];
+        bus.query(&ComState::UPTIME, &mut uptime).unwrap();
+        assert_eq!(uptime, [0xAAAA; 4]);
+        assert!(matches!(bus.query(&ComState::UPTIME, &mut [0; 2]), Err(TransportError::ReadLength { .. })));
+    }
+
+    #[derive(Default)]
+    struct Counter {
+        started: u16,
+        ended: u16,
+        errors: u16,
+    }
+    impl Hooks for Counter {
+        fn on_verb_start(&mut self, _spec: &ComSpec, _tx: &[u16]) {
+            self.started += 1;
+        }
+        fn on_verb_end(&mut self, _spec: &ComSpec, _rx: &[u16]) {
+            self.ended += 1;
+        }
+        fn on_error(&mut self, _spec: &ComSpec, _error: &dyn core::fmt::Debug) {
+            self.errors += 1;
+        }
+    }
+
+    #[test]
+    fn hooks_see_every_exchange() {
+        let mut bus = Instrumented::new(Recorder { sent: [0; 8], len: 0 }, Counter::default());
+        bus.query(&ComState::UPTIME, &mut [0; 4]).unwrap();
+        assert!(bus.command(&ComState::FLASH_ERASE, &[0; 2]).is_err());
+        let counter = bus.hooks();
+        assert_eq!((counter.started, counter.ended, counter.errors), (2, 1, 1));
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod async_tests {
+    use super::*;
+    use crate::ComState;
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    // a bus that is never busy, so every future completes on the first poll
+    struct Loopback {
+        last: u16,
+    }
+    impl AsyncComTransport for Loopback {
+        type Error = ();
+        async fn send_verb(&mut self, verb: u16) -> Result<(), ()> {
+            self.last = verb;
+            Ok(())
+        }
+        async fn write_words(&mut self, words: &[u16]) -> Result<(), ()> {
+            self.last = words[words.len() - 1];
+            Ok(())
+        }
+        async fn read_words(&mut self, words: &mut [u16]) -> Result<(), ()> {
+            words.iter_mut().for_each(|w| *w = !self.last);
+            Ok(())
+        }
+    }
+
+    fn poll_once<F: Future>(fut: F) -> F::Output {
+        let mut cx = Context::from_waker(Waker::noop());
+        match pin!(fut).poll(&mut cx) {
+            Poll::Ready(out) => out,
+            Poll::Pending => panic!("loopback future did not complete"),
+        }
+    }
+
+    #[test]
+    fn async_exchange() {
+        let mut bus = Loopback { last: 0 };
+        let mut rx = [0u16; 2];
+        poll_once(bus.exchange(&ComState::LINK_PING, &[0x1234], &mut rx)).unwrap();
+        assert_eq!(rx, [!0x1234; 2]);
+        assert!(matches!(
+            poll_once(bus.query(&ComState::UPTIME, &mut rx)),
+            Err(TransportError::ReadLength { expected: 4, actual: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,174 @@
+#![forbid(unsafe_code)]
+
+// Scripted ComTransport for host-side unit tests of COM consumers.
+//
+// The test pre-loads the verbs it expects the code under test to issue, in order, together with
+// the canned responses the EC would give. MockCom then plays the EC: it serves the responses,
+// records every exchange into a transcript, and panics on anything a real EC would turn into a
+// FIFO desync -- an unexpected verb, a payload of the wrong length, a read before the payload
+// is complete, or a new verb before the previous response was fully read.
+
+use super::ComTransport;
+use crate::ComSpec;
+use core::convert::Infallible;
+use std::collections::VecDeque;
+use std::vec::Vec;
+
+/// One completed exchange, as recorded in the transcript
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Exchange {
+    pub verb: u16,
+    pub tx: Vec<u16>,
+    pub rx: Vec<u16>,
+}
+
+struct Expectation {
+    spec: ComSpec,
+    /// None accepts any payload
+    tx: Option<Vec<u16>>,
+    rx: Vec<u16>,
+}
+
+struct InFlight {
+    expectation: Expectation,
+    tx: Vec<u16>,
+    read: usize,
+}
+impl InFlight {
+    fn is_complete(&self) -> bool {
+        self.tx.len() == self.expectation.spec.w_words as usize && self.read == self.expectation.rx.len()
+    }
+}
+
+/// Mock EC implementing ComTransport. See the module comment for the rules it enforces.
+#[derive(Default)]
+pub struct MockCom {
+    expected: VecDeque<Expectation>,
+    in_flight: Option<InFlight>,
+    transcript: Vec<Exchange>,
+}
+impl MockCom {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect `spec` with exactly the payload `tx`, and answer it with `rx`.
+    pub fn expect(&mut self, spec: &ComSpec, tx: &[u16], rx: &[u16]) -> &mut Self {
+        assert_eq!(tx.len(), spec.w_words as usize, "expected payload for verb {:#06x} has the wrong length", spec.verb);
+        self.push(spec, Some(tx.to_vec()), rx)
+    }
+
+    /// Expect `spec` with any payload, and answer it with `rx`.
+    pub fn expect_verb(&mut self, spec: &ComSpec, rx: &[u16]) -> &mut Self {
+        self.push(spec, None, rx)
+    }
+
+    fn push(&mut self, spec: &ComSpec, tx: Option<Vec<u16>>, rx: &[u16]) -> &mut Self {
+        assert_eq!(rx.len(), spec.r_words as usize, "canned response for verb {:#06x} has the wrong length", spec.verb);
+        self.expected.push_back(Expectation { spec: *spec, tx, rx: rx.to_vec() });
+        self
+    }
+
+    /// Every exchange completed so far, in order
+    pub fn transcript(&self) -> &[Exchange] {
+        &self.transcript
+    }
+
+    /// Panic unless every expected exchange has happened and completed.
+    pub fn finish(&self) {
+        assert!(self.in_flight.is_none(), "exchange still in flight at finish");
+        assert!(self.expected.is_empty(), "{} expected exchanges never happened", self.expected.len());
+    }
+
+    fn retire_if_complete(&mut self) {
+        if self.in_flight.as_ref().is_some_and(InFlight::is_complete) {
+            let done = self.in_flight.take().unwrap();
+            self.transcript.push(Exchange { verb: done.expectation.spec.verb, tx: done.tx, rx: done.expectation.rx });
+        }
+    }
+}
+
+impl ComTransport for MockCom {
+    type Error = Infallible;
+
+    fn send_verb(&mut self, verb: u16) -> Result<(), Infallible> {
+        if let Some(flight) = &self.in_flight {
+            panic!("verb {:#06x} sent while verb {:#06x} is incomplete", verb, flight.expectation.spec.verb);
+        }
+        let expectation = match self.expected.pop_front() {
+            Some(expectation) => expectation,
+            None => panic!("unexpected verb {:#06x}: no more exchanges expected", verb),
+        };
+        assert_eq!(verb, expectation.spec.verb, "unexpected verb");
+        self.in_flight = Some(InFlight { expectation, tx: Vec::new(), read: 0 });
+        self.retire_if_complete();
+        Ok(())
+    }
+
+    fn write_words(&mut self, words: &[u16]) -> Result<(), Infallible> {
+        let flight = self.in_flight.as_mut().expect("payload written without a verb");
+        let spec = flight.expectation.spec;
+        assert!(
+            flight.tx.len() + words.len() <= spec.w_words as usize,
+            "payload for verb {:#06x} overruns w_words ({})",
+            spec.verb,
+            spec.w_words
+        );
+        flight.tx.extend_from_slice(words);
+        if flight.tx.len() == spec.w_words as usize {
+            if let Some(expected) = &flight.expectation.tx {
+                assert_eq!(&flight.tx, expected, "payload mismatch for verb {:#06x}", spec.verb);
+            }
+        }
+        self.retire_if_complete();
+        Ok(())
+    }
+
+    fn read_words(&mut self, words: &mut [u16]) -> Result<(), Infallible> {
+        let flight = self.in_flight.as_mut().expect("response read without a verb");
+        let spec = flight.expectation.spec;
+        assert_eq!(flight.tx.len(), spec.w_words as usize, "response read before payload of verb {:#06x} completed", spec.verb);
+        let end = flight.read + words.len();
+        assert!(end <= flight.expectation.rx.len(), "read for verb {:#06x} overruns r_words ({})", spec.verb, spec.r_words);
+        words.copy_from_slice(&flight.expectation.rx[flight.read..end]);
+        flight.read = end;
+        self.retire_if_complete();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ComState;
+
+    #[test]
+    fn scripted_exchanges() {
+        let mut com = MockCom::new();
+        com.expect(&ComState::LINK_PING, &[0x1234], &[!0x1234, 7]).expect_verb(&ComState::WLAN_SET_SSID, &[]);
+        let mut rx = [0u16; 2];
+        com.exchange(&ComState::LINK_PING, &[0x1234], &mut rx).unwrap();
+        assert_eq!(rx, [!0x1234, 7]);
+        com.command(&ComState::WLAN_SET_SSID, &[0; 17]).unwrap();
+        com.finish();
+        assert_eq!(com.transcript().len(), 2);
+        assert_eq!(com.transcript()[0].tx, [0x1234]);
+    }
+
+    #[test]
+    #[should_panic(expected = "is incomplete")]
+    fn new_verb_before_response_is_read() {
+        let mut com = MockCom::new();
+        com.expect_verb(&ComState::UPTIME, &[0; 4]).expect_verb(&ComState::WLAN_ON, &[]);
+        com.send_verb(ComState::UPTIME.verb).unwrap();
+        com.send_verb(ComState::WLAN_ON.verb).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "payload mismatch")]
+    fn wrong_payload() {
+        let mut com = MockCom::new();
+        com.expect(&ComState::LINK_PING, &[1], &[0, 0]);
+        com.exchange(&ComState::LINK_PING, &[2], &mut [0; 2]).unwrap();
+    }
+}
@@ -0,0 +1,170 @@
+#![forbid(unsafe_code)]
+
+// Reference ComTransport over an embedded-hal SPI device.
+//
+// The SoC is the SPI controller and every transfer exchanges exactly one 16-bit word with the
+// EC's FIFOs. The EC drives a hold line that is asserted (high) while it cannot accept a
+// transfer, e.g. while it is still processing the previous verb; no word may be clocked while
+// hold is asserted. Words the EC returns while the SoC is writing are meaningless and are
+// discarded. To read a response, the SoC clocks out one LINK_READ per word, and the word
+// clocked in with each LINK_READ is the next response word.
+
+use super::ComTransport;
+use crate::ComState;
+use embedded_hal::digital::InputPin;
+use embedded_hal::spi::SpiDevice;
+
+/// Default number of hold polls before a transfer is abandoned
+pub const DEFAULT_HOLD_SPINS: u32 = 100_000;
+
+/// Errors from SpiTransport
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SpiTransportError<S, P> {
+    /// the SPI device reported an error
+    Spi(S),
+    /// the hold pin could not be read
+    Hold(P),
+    /// hold stayed asserted for more than the configured number of polls
+    HoldTimeout,
+}
+
+/// ComTransport over a 16-bit SPI device and the EC's hold line
+pub struct SpiTransport<SPI, HOLD> {
+    spi: SPI,
+    hold: HOLD,
+    hold_spins: u32,
+}
+impl<SPI, HOLD> SpiTransport<SPI, HOLD>
+where
+    SPI: SpiDevice<u16>,
+    HOLD: InputPin,
+{
+    pub fn new(spi: SPI, hold: HOLD) -> Self {
+        SpiTransport { spi, hold, hold_spins: DEFAULT_HOLD_SPINS }
+    }
+    /// Set the number of times hold is polled before giving up with HoldTimeout
+    pub fn with_hold_spins(mut self, hold_spins: u32) -> Self {
+        self.hold_spins = hold_spins;
+        self
+    }
+    /// Release the SPI device and hold pin
+    pub fn release(self) -> (SPI, HOLD) {
+        (self.spi, self.hold)
+    }
+
+    fn wait_hold(&mut self) -> Result<(), SpiTransportError<SPI::Error, HOLD::Error>> {
+        for _ in 0..self.hold_spins {
+            if self.hold.is_low().map_err(SpiTransportError::Hold)? {
+                return Ok(());
+            }
+        }
+        Err(SpiTransportError::HoldTimeout)
+    }
+
+    /// Clock one word out and return the word clocked in.
+    fn exchange_word(&mut self, word: u16) -> Result<u16, SpiTransportError<SPI::Error, HOLD::Error>> {
+        self.wait_hold()?;
+        let mut buf = [word];
+        self.spi.transfer_in_place(&mut buf).map_err(SpiTransportError::Spi)?;
+        Ok(buf[0])
+    }
+}
+impl<SPI, HOLD> ComTransport for SpiTransport<SPI, HOLD>
+where
+    SPI: SpiDevice<u16>,
+    HOLD: InputPin,
+{
+    type Error = SpiTransportError<SPI::Error, HOLD::Error>;
+
+    fn send_verb(&mut self, verb: u16) -> Result<(), Self::Error> {
+        self.exchange_word(verb).map(|_| ())
+    }
+    fn write_words(&mut self, words: &[u16]) -> Result<(), Self::Error> {
+        for &word in words {
+            self.exchange_word(word)?;
+        }
+        Ok(())
+    }
+    fn read_words(&mut self, words: &mut [u16]) -> Result<(), Self::Error> {
+        for word in words.iter_mut() {
+            *word = self.exchange_word(ComState::LINK_READ.verb)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::convert::Infallible;
+    use embedded_hal::spi::{ErrorType, Operation};
+
+    // An EC that answers LINK_READ with a counter and records what it was sent.
+    struct FakeEc {
+        sent: [u16; 8],
+        len: usize,
+        next: u16,
+    }
+    impl ErrorType for FakeEc {
+        type Error = Infallible;
+    }
+    impl SpiDevice<u16> for FakeEc {
+        fn transaction(&mut self, operations: &mut [Operation<'_, u16>]) -> Result<(), Infallible> {
+            for op in operations {
+                if let Operation::TransferInPlace(words) = op {
+                    for word in words.iter_mut() {
+                        self.sent[self.len] = *word;
+                        self.len += 1;
+                        if *word == ComState::LINK_READ.verb {
+                            *word = self.next;
+                            self.next += 1;
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    // a hold line that is asserted for the first `busy` polls
+    struct Hold {
+        busy: u32,
+    }
+    impl embedded_hal::digital::ErrorType for Hold {
+        type Error = Infallible;
+    }
+    impl InputPin for Hold {
+        fn is_high(&mut self) -> Result<bool, Infallible> {
+            if self.busy > 0 {
+                self.busy -= 1;
+                Ok(true)
+            } else {
+                Ok(false)
+            }
+        }
+        fn is_low(&mut self) -> Result<bool, Infallible> {
+            self.is_high().map(|high| !high)
+        }
+    }
+
+    #[test]
+    fn reads_pump_link_read() {
+        let ec = FakeEc { sent: [0; 8], len: 0, next: 0x100 };
+        let mut bus = SpiTransport::new(ec, Hold { busy: 3 });
+        let mut rx = [0u16; 2];
+        bus.exchange(&ComState::LINK_PING, &[0x5555], &mut rx).unwrap();
+        assert_eq!(rx, [0x100, 0x101]);
+        let (ec, _) = bus.release();
+        assert_eq!(ec.sent[..ec.len], [ComState::LINK_PING.verb, 0x5555, ComState::LINK_READ.verb, ComState::LINK_READ.verb]);
+    }
+
+    #[test]
+    fn stuck_hold_times_out() {
+        let ec = FakeEc { sent: [0; 8], len: 0, next: 0 };
+        let mut bus = SpiTransport::new(ec, Hold { busy: 10 }).with_hold_spins(5);
+        assert_eq!(
+            bus.command(&ComState::WLAN_ON, &[]),
+            Err(crate::transport::TransportError::Bus(SpiTransportError::HoldTimeout))
+        );
+    }
+}
@@ -0,0 +1,202 @@
+#![forbid(unsafe_code)]
+
+// Transcript recording and replay.
+//
+// A `Trace` is a `Hooks` sink: wrap the production transport in `Instrumented` with a Trace and
+// every completed exchange is recorded as (verb, tx words, rx words, timestamp). The trace
+// serializes to a compact binary blob that can be attached to a field bug report, and on the
+// bench `Trace::to_mock` turns it back into a `MockCom` that plays the EC's side of the
+// conversation, so the host code can be re-run against exactly what the EC said.
+//
+// Binary format, all fields little-endian:
+// - header: b"CTRC", format version (u16), COM_ABI_VERSION of the recorder (u16), record count (u32)
+// - each record: verb (u16), tx word count (u16), rx word count (u16), microseconds since the
+//   trace started (u32, saturating), then the tx words, then the rx words
+
+use super::mock::MockCom;
+use super::Hooks;
+use crate::{ComSpec, ComState, DangerLevel, COM_ABI_VERSION};
+use std::time::Instant;
+use std::vec::Vec;
+
+const TRACE_MAGIC: &[u8; 4] = b"CTRC";
+/// Version of the binary trace format
+pub const TRACE_FORMAT_VERSION: u16 = 1;
+
+/// One recorded exchange
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceRecord {
+    pub verb: u16,
+    pub tx: Vec<u16>,
+    pub rx: Vec<u16>,
+    /// microseconds since the trace started
+    pub timestamp_us: u32,
+}
+
+/// Errors from parsing a serialized trace
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum TraceError {
+    /// the blob does not start with the trace magic
+    BadMagic,
+    /// the blob uses a trace format this revision does not understand
+    UnsupportedVersion(u16),
+    /// the blob ends in the middle of a record
+    Truncated,
+}
+
+/// A recorded sequence of exchanges
+pub struct Trace {
+    start: Instant,
+    /// ABI version of the recorder, for traces parsed from bytes
+    abi_version: u16,
+    pending: Option<(u16, Vec<u16>, u32)>,
+    records: Vec<TraceRecord>,
+}
+impl Trace {
+    pub fn new() -> Self {
+        Trace { start: Instant::now(), abi_version: COM_ABI_VERSION, pending: None, records: Vec::new() }
+    }
+    pub fn records(&self) -> &[TraceRecord] {
+        &self.records
+    }
+    /// COM_ABI_VERSION of the firmware that recorded the trace
+    pub fn abi_version(&self) -> u16 {
+        self.abi_version
+    }
+
+    fn elapsed_us(&self) -> u32 {
+        self.start.elapsed().as_micros().min(u32::MAX as u128) as u32
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(TRACE_MAGIC);
+        out.extend_from_slice(&TRACE_FORMAT_VERSION.to_le_bytes());
+        out.extend_from_slice(&self.abi_version.to_le_bytes());
+        out.extend_from_slice(&(self.records.len() as u32).to_le_bytes());
+        for record in &self.records {
+            out.extend_from_slice(&record.verb.to_le_bytes());
+            out.extend_from_slice(&(record.tx.len() as u16).to_le_bytes());
+            out.extend_from_slice(&(record.rx.len() as u16).to_le_bytes());
+            out.extend_from_slice(&record.timestamp_us.to_le_bytes());
+            for word in record.tx.iter().chain(record.rx.iter()) {
+                out.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, TraceError> {
+        let mut reader = ByteReader { bytes };
+        if reader.take(4)? != TRACE_MAGIC {
+            return Err(TraceError::BadMagic);
+        }
+        let version = reader.u16()?;
+        if version != TRACE_FORMAT_VERSION {
+            return Err(TraceError::UnsupportedVersion(version));
+        }
+        let abi_version = reader.u16()?;
+        let count = reader.u32()?;
+        let mut records = Vec::new();
+        for _ in 0..count {
+            let verb = reader.u16()?;
+            let tx_len = reader.u16()? as usize;
+            let rx_len = reader.u16()? as usize;
+            let timestamp_us = reader.u32()?;
+            let tx = reader.words(tx_len)?;
+            let rx = reader.words(rx_len)?;
+            records.push(TraceRecord { verb, tx, rx, timestamp_us });
+        }
+        Ok(Trace { start: Instant::now(), abi_version, pending: None, records })
+    }
+
+    /// A MockCom that expects the recorded exchanges in order, with the recorded payloads, and
+    /// answers them with the recorded responses.
+    pub fn to_mock(&self) -> MockCom {
+        let mut mock = MockCom::new();
+        for record in &self.records {
+            // the recorded lengths are what actually crossed the bus, so they define the spec
+            // not marked deprecated: a recorded exchange happened, whatever this build thinks of the verb
+            let spec = ComSpec::new(record.verb, record.tx.len() as u16, record.rx.len() as u16, [0; 4])
+                .with_danger(ComState::lookup(record.verb).map_or(DangerLevel::Safe, |spec| spec.danger));
+            mock.expect(&spec, &record.tx, &record.rx);
+        }
+        mock
+    }
+}
+impl Default for Trace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Hooks for Trace {
+    fn on_verb_start(&mut self, spec: &ComSpec, tx: &[u16]) {
+        self.pending = Some((spec.verb, tx.to_vec(), self.elapsed_us()));
+    }
+    fn on_verb_end(&mut self, _spec: &ComSpec, rx: &[u16]) {
+        if let Some((verb, tx, timestamp_us)) = self.pending.take() {
+            self.records.push(TraceRecord { verb, tx, rx: rx.to_vec(), timestamp_us });
+        }
+    }
+    fn on_error(&mut self, _spec: &ComSpec, _error: &dyn core::fmt::Debug) {
+        // failed exchanges have no response to replay
+        self.pending = None;
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> ByteReader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], TraceError> {
+        if self.bytes.len() < n {
+            return Err(TraceError::Truncated);
+        }
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+    fn u16(&mut self) -> Result<u16, TraceError> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+    fn u32(&mut self) -> Result<u32, TraceError> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+    fn words(&mut self, n: usize) -> Result<Vec<u16>, TraceError> {
+        (0..n).map(|_| self.u16()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{ComTransport, Instrumented};
+
+    #[test]
+    fn record_serialize_replay() {
+        let mut ec = MockCom::new();
+        ec.expect(&ComState::LINK_PING, &[0x1234], &[!0x1234, 1]).expect_verb(&ComState::WLAN_GET_RSSI, &[0x0064]);
+        let mut bus = Instrumented::new(ec, Trace::new());
+        bus.exchange(&ComState::LINK_PING, &[0x1234], &mut [0; 2]).unwrap();
+        assert!(bus.query(&ComState::WLAN_GET_RSSI, &mut [0; 2]).is_err());
+        bus.query(&ComState::WLAN_GET_RSSI, &mut [0; 1]).unwrap();
+        let (_, trace) = bus.into_inner();
+        assert_eq!(trace.records().len(), 2);
+
+        let bytes = trace.to_bytes();
+        assert_eq!(&bytes[..4], b"CTRC");
+        let parsed = Trace::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed.records(), trace.records());
+        assert_eq!(Trace::from_bytes(&bytes[..bytes.len() - 1]).err(), Some(TraceError::Truncated));
+
+        // the replayed EC answers exactly as the recorded one did
+        let mut replay = parsed.to_mock();
+        let mut pong = [0u16; 2];
+        replay.exchange(&ComState::LINK_PING, &[0x1234], &mut pong).unwrap();
+        assert_eq!(pong, [!0x1234, 1]);
+        let mut rssi = [0u16; 1];
+        replay.query(&ComState::WLAN_GET_RSSI, &mut rssi).unwrap();
+        assert_eq!(rssi, [0x0064]);
+        replay.finish();
+    }
+}
@@ -0,0 +1,165 @@
+#![forbid(unsafe_code)]
+
+// Vendor verb range.
+//
+// Verbs VENDOR_VERB_MIN..=VENDOR_VERB_MAX are reserved for downstream hardware forks and will
+// never be allocated upstream. A fork describes its board-specific commands as `VendorSpec`s
+// and registers them in a `VendorRegistry` on both sides of the link.
+//
+// A vendor verb may be marked experimental. Experimental verbs are refused by the registry's
+// guard unless the peer advertises PROTO_FEATURE_VENDOR_EXPERIMENTAL, so a host built with
+// experimental commands cannot issue them to production EC firmware by accident.
+
+use crate::serdes::PROTO_FEATURE_VENDOR_EXPERIMENTAL;
+use crate::{ComSpec, DangerLevel};
+
+/// First verb of the vendor range
+pub const VENDOR_VERB_MIN: u16 = 0xE000;
+/// Last verb of the vendor range
+pub const VENDOR_VERB_MAX: u16 = 0xEFFF;
+
+/// True if `verb` is in the vendor range
+pub const fn is_vendor_verb(verb: u16) -> bool {
+    verb >= VENDOR_VERB_MIN && verb <= VENDOR_VERB_MAX
+}
+
+/// Errors from VendorRegistry
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VendorError {
+    /// the verb is outside the vendor range
+    OutOfRange(u16),
+    /// a verb with this value is already registered
+    Duplicate(u16),
+    /// the registry has no free slots
+    Full,
+    /// the verb is not registered
+    Unknown(u16),
+    /// the verb is experimental and the peer does not advertise PROTO_FEATURE_VENDOR_EXPERIMENTAL
+    ExperimentalDisabled(u16),
+}
+
+/// A verb in the vendor range, with its word counts. Vendor verbs are Safe unless marked
+/// otherwise with `with_danger`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VendorSpec {
+    spec: ComSpec,
+    experimental: bool,
+}
+impl VendorSpec {
+    /// In a const context, a verb outside the vendor range fails to compile; `try_new` is the
+    /// runtime equivalent.
+    pub const fn new(verb: u16, w_words: u16, r_words: u16, apilevel: [u8; 4]) -> Self {
+        assert!(is_vendor_verb(verb), "vendor verbs must be in VENDOR_VERB_MIN..=VENDOR_VERB_MAX");
+        VendorSpec {
+            spec: ComSpec::new(verb, w_words, r_words, apilevel),
+            experimental: false,
+        }
+    }
+    pub const fn try_new(verb: u16, w_words: u16, r_words: u16, apilevel: [u8; 4]) -> Result<Self, VendorError> {
+        if is_vendor_verb(verb) {
+            Ok(VendorSpec::new(verb, w_words, r_words, apilevel))
+        } else {
+            Err(VendorError::OutOfRange(verb))
+        }
+    }
+    /// Set the danger level of this verb
+    pub const fn with_danger(mut self, danger: DangerLevel) -> Self {
+        self.spec.danger = danger;
+        self
+    }
+    /// Mark this verb experimental
+    pub const fn experimental(mut self) -> Self {
+        self.experimental = true;
+        self
+    }
+    pub const fn spec(&self) -> &ComSpec {
+        &self.spec
+    }
+    pub const fn is_experimental(&self) -> bool {
+        self.experimental
+    }
+}
+
+/// The vendor verbs known to one side of the link, up to N of them
+pub struct VendorRegistry<const N: usize> {
+    entries: [Option<VendorSpec>; N],
+}
+impl<const N: usize> VendorRegistry<N> {
+    pub const fn new() -> Self {
+        VendorRegistry { entries: [None; N] }
+    }
+
+    pub fn register(&mut self, vendor: VendorSpec) -> Result<(), VendorError> {
+        let verb = vendor.spec.verb;
+        if self.find(verb).is_some() {
+            return Err(VendorError::Duplicate(verb));
+        }
+        match self.entries.iter_mut().find(|e| e.is_none()) {
+            Some(slot) => {
+                *slot = Some(vendor);
+                Ok(())
+            }
+            None => Err(VendorError::Full),
+        }
+    }
+
+    /// The registered spec for `verb`, without the experimental guard
+    pub fn find(&self, verb: u16) -> Option<&VendorSpec> {
+        self.entries.iter().flatten().find(|v| v.spec.verb == verb)
+    }
+
+    /// Runtime guard: the spec for `verb`, if it is registered and usable with a peer that
+    /// advertises `features` (ProtocolVersion::features).
+    pub fn check(&self, verb: u16, features: u32) -> Result<&ComSpec, VendorError> {
+        if !is_vendor_verb(verb) {
+            return Err(VendorError::OutOfRange(verb));
+        }
+        let vendor = self.find(verb).ok_or(VendorError::Unknown(verb))?;
+        if vendor.experimental && features & PROTO_FEATURE_VENDOR_EXPERIMENTAL == 0 {
+            return Err(VendorError::ExperimentalDisabled(verb));
+        }
+        Ok(&vendor.spec)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &VendorSpec> {
+        self.entries.iter().flatten()
+    }
+}
+impl<const N: usize> Default for VendorRegistry<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verb::VerbClass;
+    use crate::ComState;
+
+    const BOARD_LED: VendorSpec = VendorSpec::new(0xE001, 1, 0, [0, 9, 9, 0]);
+    const BOARD_PROBE: VendorSpec = VendorSpec::new(0xE002, 0, 2, [0, 9, 9, 0]).experimental();
+
+    #[test]
+    fn upstream_verbs_are_outside_the_vendor_range() {
+        assert!(ComState::ALL.iter().all(|spec| !is_vendor_verb(spec.verb)));
+        let join = ComState::WLAN_JOIN.verb;
+        assert_eq!(VendorSpec::try_new(join, 0, 0, [0, 9, 9, 0]), Err(VendorError::OutOfRange(join)));
+        assert_eq!(VendorSpec::try_new(0xF000, 0, 0, [0, 9, 9, 0]), Err(VendorError::OutOfRange(0xF000)));
+        let spec = VendorSpec::try_new(0xEFFF, 2, 1, [0, 9, 9, 0]).unwrap();
+        assert_eq!((spec.spec().verb, spec.spec().category(), spec.spec().danger), (0xEFFF, VerbClass::Vendor, DangerLevel::Safe));
+    }
+
+    #[test]
+    fn experimental_verbs_need_the_feature_bit() {
+        let mut registry = VendorRegistry::<2>::new();
+        registry.register(BOARD_LED).unwrap();
+        registry.register(BOARD_PROBE).unwrap();
+        assert_eq!(registry.register(BOARD_LED), Err(VendorError::Duplicate(0xE001)));
+
+        assert_eq!(registry.check(0xE001, 0), Ok(BOARD_LED.spec()));
+        assert_eq!(registry.check(0xE002, 0), Err(VendorError::ExperimentalDisabled(0xE002)));
+        assert_eq!(registry.check(0xE002, PROTO_FEATURE_VENDOR_EXPERIMENTAL), Ok(BOARD_PROBE.spec()));
+        assert_eq!(registry.check(0xE003, PROTO_FEATURE_VENDOR_EXPERIMENTAL), Err(VendorError::Unknown(0xE003)));
+    }
+}
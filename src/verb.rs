@@ -0,0 +1,212 @@
+#![forbid(unsafe_code)]
+
+// Verb namespace.
+//
+// The 16-bit verb space is partitioned into 4k blocks by subsystem; the top nibble of a verb
+// names its block. The 0x2000 block is shared by the wifi, WLAN, network and raw frame
+// subsystems, split on the second nibble; raw frames take its upper half. Some blocks encode an
+// argument in the low bits of the verb (the backlight level in BL_START..BL_END, the frame
+// length in the NET_FRAME and NET_RAW_SEND verbs), so classification works on ranges rather
+// than on the verb table. New verbs must be allocated inside the block of their subsystem; the
+// build fails if a verb in ComState::ALL lands in an unassigned block.
+
+use core::ops::RangeInclusive;
+
+use crate::vendor::{is_vendor_verb, VENDOR_VERB_MAX, VENDOR_VERB_MIN};
+use crate::{ComSpec, ComState};
+
+/// Subsystem block of the verb space
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VerbClass {
+    /// 0x0000-0x1FFF: not allocated
+    Unassigned,
+    /// 0x2000-0x22FF: SSID scanning and WF200 management
+    Wifi,
+    /// 0x2300-0x23FF: WLAN_* connection management
+    Wlan,
+    /// 0x2400-0x27FF: network interface configuration
+    Net,
+    /// 0x2800-0x2FFF: raw 802.11 frames (NET_RAW_SEND), length encoded in the verb
+    RawFrame,
+    /// 0x3000-0x3FFF: EC flash update
+    Flash,
+    /// 0x4000-0x4FFF: EC metadata, entropy, logs, liveness
+    System,
+    /// 0x5000-0x5FFF: charger control
+    Charger,
+    /// 0x6000-0x6FFF: backlight, level encoded in the verb
+    Backlight,
+    /// 0x7000-0x7FFF: gas gauge
+    GasGauge,
+    /// 0x8000-0x8FFF: charger status
+    ChargerStatus,
+    /// 0x9000-0x9FFF: power state
+    Power,
+    /// 0xA000-0xAFFF: gyro and IMU
+    Imu,
+    /// 0xB000-0xBFFF: USB CC controller
+    UsbCc,
+    /// 0xC000-0xCFFF: network frames, length encoded in the verb
+    NetFrame,
+    /// 0xD000-0xDFFF: error codes (ERROR) and diagnostics
+    Diagnostic,
+    /// 0xE000-0xEFFF: reserved for downstream forks, see the vendor module
+    Vendor,
+    /// 0xF000-0xFFFF: link protocol overhead
+    Link,
+}
+
+impl VerbClass {
+    /// Every class, in verb order
+    pub const ALL: [VerbClass; 18] = [
+        VerbClass::Unassigned,
+        VerbClass::Wifi,
+        VerbClass::Wlan,
+        VerbClass::Net,
+        VerbClass::RawFrame,
+        VerbClass::Flash,
+        VerbClass::System,
+        VerbClass::Charger,
+        VerbClass::Backlight,
+        VerbClass::GasGauge,
+        VerbClass::ChargerStatus,
+        VerbClass::Power,
+        VerbClass::Imu,
+        VerbClass::UsbCc,
+        VerbClass::NetFrame,
+        VerbClass::Diagnostic,
+        VerbClass::Vendor,
+        VerbClass::Link,
+    ];
+
+    /// The verbs of this class
+    pub const fn range(&self) -> RangeInclusive<u16> {
+        match self {
+            VerbClass::Unassigned => 0x0000..=0x1FFF,
+            VerbClass::Wifi => 0x2000..=0x22FF,
+            VerbClass::Wlan => 0x2300..=0x23FF,
+            VerbClass::Net => 0x2400..=0x27FF,
+            VerbClass::RawFrame => 0x2800..=0x2FFF,
+            VerbClass::Flash => 0x3000..=0x3FFF,
+            VerbClass::System => 0x4000..=0x4FFF,
+            VerbClass::Charger => 0x5000..=0x5FFF,
+            VerbClass::Backlight => 0x6000..=0x6FFF,
+            VerbClass::GasGauge => 0x7000..=0x7FFF,
+            VerbClass::ChargerStatus => 0x8000..=0x8FFF,
+            VerbClass::Power => 0x9000..=0x9FFF,
+            VerbClass::Imu => 0xA000..=0xAFFF,
+            VerbClass::UsbCc => 0xB000..=0xBFFF,
+            VerbClass::NetFrame => 0xC000..=0xCFFF,
+            VerbClass::Diagnostic => 0xD000..=0xDFFF,
+            VerbClass::Vendor => VENDOR_VERB_MIN..=VENDOR_VERB_MAX,
+            VerbClass::Link => 0xF000..=0xFFFF,
+        }
+    }
+}
+
+/// The class a verb belongs to
+pub const fn classify(verb: u16) -> VerbClass {
+    match verb >> 12 {
+        0x0 | 0x1 => VerbClass::Unassigned,
+        0x2 => match (verb >> 8) & 0xF {
+            0x0..=0x2 => VerbClass::Wifi,
+            0x3 => VerbClass::Wlan,
+            0x4..=0x7 => VerbClass::Net,
+            _ => VerbClass::RawFrame,
+        },
+        0x3 => VerbClass::Flash,
+        0x4 => VerbClass::System,
+        0x5 => VerbClass::Charger,
+        0x6 => VerbClass::Backlight,
+        0x7 => VerbClass::GasGauge,
+        0x8 => VerbClass::ChargerStatus,
+        0x9 => VerbClass::Power,
+        0xA => VerbClass::Imu,
+        0xB => VerbClass::UsbCc,
+        0xC => VerbClass::NetFrame,
+        0xD => VerbClass::Diagnostic,
+        0xE => VerbClass::Vendor,
+        _ => VerbClass::Link,
+    }
+}
+
+/// Result of looking a verb value up across the whole verb space
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum VerbLookup {
+    /// an upstream verb from ComState::ALL
+    Upstream(&'static ComSpec),
+    /// a vendor verb: valid, but opaque to upstream code. Its word counts are known only to
+    /// the fork that registers it as a VendorSpec.
+    Custom(u16),
+    /// not a verb this crate knows about
+    Unknown,
+}
+impl VerbLookup {
+    /// True for upstream and vendor verbs
+    pub fn is_valid(&self) -> bool {
+        !matches!(self, VerbLookup::Unknown)
+    }
+}
+
+/// Look up any verb value, treating the vendor range as valid-but-opaque
+pub fn lookup(verb: u16) -> VerbLookup {
+    if let Some(spec) = ComState::lookup(verb) {
+        VerbLookup::Upstream(spec)
+    } else if is_vendor_verb(verb) {
+        VerbLookup::Custom(verb)
+    } else {
+        VerbLookup::Unknown
+    }
+}
+
+/// True if every upstream verb is allocated in an assigned, non-vendor block
+const fn upstream_verbs_in_range() -> bool {
+    let mut i = 0;
+    while i < ComState::ALL.len() {
+        match classify(ComState::ALL[i].verb) {
+            VerbClass::Unassigned | VerbClass::Vendor => return false,
+            _ => {}
+        }
+        i += 1;
+    }
+    true
+}
+const _: () = assert!(upstream_verbs_in_range(), "a ComState verb is outside the assigned verb blocks");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classes_tile_the_verb_space() {
+        let mut next = 0u32;
+        for class in VerbClass::ALL.iter() {
+            let range = class.range();
+            assert_eq!(*range.start() as u32, next);
+            assert_eq!(classify(*range.start()), *class);
+            assert_eq!(classify(*range.end()), *class);
+            next = *range.end() as u32 + 1;
+        }
+        assert_eq!(next, 0x1_0000);
+    }
+
+    #[test]
+    fn known_verbs() {
+        assert_eq!(classify(ComState::SSID_SCAN_ON.verb), VerbClass::Wifi);
+        assert_eq!(classify(ComState::WLAN_JOIN.verb), VerbClass::Wlan);
+        assert_eq!(classify(ComState::NET_FRAME_CONFIG.verb), VerbClass::Net);
+        assert_eq!(classify(ComState::NET_RAW_SEND_7FF.verb), VerbClass::RawFrame);
+        assert_eq!(classify(ComState::BL_START.verb | 0x155), VerbClass::Backlight);
+        assert_eq!(classify(ComState::NET_FRAME_FETCH_7FF.verb), VerbClass::NetFrame);
+        assert_eq!(classify(ComState::ERROR.verb), VerbClass::Diagnostic);
+        assert_eq!(classify(ComState::LINK_SYNC.verb), VerbClass::Link);
+    }
+
+    #[test]
+    fn vendor_verbs_are_opaque_but_valid() {
+        assert_eq!(lookup(ComState::WLAN_JOIN.verb), VerbLookup::Upstream(&ComState::WLAN_JOIN));
+        assert_eq!(lookup(0xE123), VerbLookup::Custom(0xE123));
+        assert!(lookup(0xE123).is_valid());
+        assert!(!lookup(0x1234).is_valid());
+    }
+}
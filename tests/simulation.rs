@@ -0,0 +1,237 @@
+//! Dual-ended protocol simulation.
+//!
+//! A model EC and a model host talk through a pair of in-memory FIFOs, one word per clock, the
+//! same way the two firmwares talk over SPI. The EC side is driven entirely by `ComState::ALL`
+//! and the serdes structs: it buffers exactly `w_words` of payload for every verb, and asserts
+//! that each handler produces exactly `r_words`. The host side pumps responses out with
+//! LINK_READ. A protocol change in this crate that would desync the two firmwares shows up here
+//! as a failing `cargo test`, before either firmware is touched.
+
+use com_rs::provision::Provisioner;
+use com_rs::serdes::*;
+use com_rs::*;
+use std::collections::VecDeque;
+use std::convert::TryInto;
+
+/// Word clocked back to the host when the EC has nothing queued
+const EMPTY: u16 = ComState::ERROR.verb;
+
+/// The EC's state, as far as the simulated verbs are concerned
+#[derive(Default)]
+struct EcState {
+    intmask: u32,
+    ping_count: u16,
+    ssid: String,
+    cred_map: CredMap,
+    wom: Option<ImuWomConfig>,
+    ipv4: Ipv4Conf,
+}
+
+struct ModelEc {
+    rx: VecDeque<u16>,
+    tx: VecDeque<u16>,
+    state: EcState,
+    /// verbs that were not recognized, in arrival order
+    unknown: Vec<u16>,
+}
+impl ModelEc {
+    fn new() -> Self {
+        ModelEc { rx: VecDeque::new(), tx: VecDeque::new(), state: EcState::default(), unknown: Vec::new() }
+    }
+
+    /// One SPI clock: take a word from the host and return a word. LINK_READ pops the next
+    /// queued response word; any other word is received, and what it clocks back is meaningless.
+    fn clock(&mut self, word: u16) -> u16 {
+        if word == ComState::LINK_READ.verb {
+            return self.tx.pop_front().unwrap_or(EMPTY);
+        }
+        self.rx.push_back(word);
+        self.service();
+        EMPTY
+    }
+
+    /// Dispatch the verb at the head of the receive FIFO once its whole payload has arrived.
+    fn service(&mut self) {
+        let verb = match self.rx.front() {
+            Some(&verb) => verb,
+            None => return,
+        };
+        if verb == ComState::LINK_SYNC.verb {
+            self.rx.clear();
+            self.tx.clear();
+            return;
+        }
+        let spec = match ComState::lookup(verb) {
+            Some(spec) => *spec,
+            None => {
+                self.unknown.push(verb);
+                self.rx.pop_front();
+                return;
+            }
+        };
+        if self.rx.len() < 1 + spec.w_words as usize {
+            return;
+        }
+        self.rx.pop_front();
+        let payload: Vec<u16> = self.rx.drain(..spec.w_words as usize).collect();
+        let response = self.handle(&spec, &payload);
+        assert_eq!(response.len(), spec.r_words as usize, "EC handler for {:#06x} produced the wrong number of words", verb);
+        self.tx.extend(response);
+    }
+
+    fn handle(&mut self, spec: &ComSpec, payload: &[u16]) -> Vec<u16> {
+        let state = &mut self.state;
+        match *spec {
+            ComState::LINK_PING => {
+                state.ping_count = state.ping_count.wrapping_add(1);
+                vec![!payload[0], state.ping_count]
+            }
+            ComState::LINK_SET_INTMASK => {
+                state.intmask = (state.intmask & !INT32_LEGACY_MASK) | int16_to_int32(payload[0]);
+                vec![]
+            }
+            ComState::LINK_SET_INTMASK32 => {
+                state.intmask = words_to_u32([payload[0], payload[1]]);
+                vec![]
+            }
+            ComState::LINK_GET_INTMASK32 => InterruptFlags::from_bits(state.intmask).encode_u16().to_vec(),
+            ComState::LINK_PROTOCOL_VERSION => ProtocolVersion::CURRENT.encode_u16().to_vec(),
+            ComState::LINK_LIST_VERBS => VerbListPage::from_table(ComState::ALL, payload[0]).encode_u16().to_vec(),
+            ComState::WLAN_SET_SSID => {
+                let mut des = StringDes::<STR_32_WORDS, STR_32_U8_SIZE>::new();
+                state.ssid = des.decode_u16(payload.try_into().unwrap()).unwrap().to_string();
+                vec![]
+            }
+            ComState::WLAN_GET_IPV4_CONF => state.ipv4.encode_u16().to_vec(),
+            ComState::WLAN_CRED_MAP_SET => {
+                state.cred_map = CredMap::decode_u16(payload.try_into().unwrap());
+                vec![]
+            }
+            ComState::WLAN_CRED_MAP_GET => state.cred_map.encode_u16().to_vec(),
+            ComState::IMU_WOM_CONFIG => {
+                let config = ImuWomConfig::decode_u16(payload.try_into().unwrap());
+                state.wom = config.validate().ok().map(|_| config).filter(ImuWomConfig::is_enabled);
+                vec![]
+            }
+            // everything else is accepted and answered with zeros of the right length
+            _ => vec![0; spec.r_words as usize],
+        }
+    }
+}
+
+struct ModelHost {
+    ec: ModelEc,
+}
+impl ModelHost {
+    fn exchange(&mut self, spec: &ComSpec, tx: &[u16], rx: &mut [u16]) {
+        assert_eq!(tx.len(), spec.w_words as usize);
+        assert_eq!(rx.len(), spec.r_words as usize);
+        self.ec.clock(spec.verb);
+        for &word in tx {
+            self.ec.clock(word);
+        }
+        for word in rx.iter_mut() {
+            *word = self.ec.clock(ComState::LINK_READ.verb);
+        }
+    }
+}
+
+fn sim() -> ModelHost {
+    ModelHost { ec: ModelEc::new() }
+}
+
+#[test]
+fn every_verb_keeps_the_fifos_in_sync() {
+    let mut host = sim();
+    // the model is a current EC, which does not implement the deprecated verbs
+    for spec in ComState::ALL.iter().filter(|spec| !spec.response && !spec.deprecated && spec.verb != ComState::LINK_SYNC.verb) {
+        let tx = vec![0u16; spec.w_words as usize];
+        let mut rx = vec![0u16; spec.r_words as usize];
+        host.exchange(spec, &tx, &mut rx);
+        // after every verb, a ping must round-trip; a desync would return stale or missing words
+        let mut pong = [0u16; 2];
+        host.exchange(&ComState::LINK_PING, &[0x5A5A], &mut pong);
+        assert_eq!(pong[0], !0x5A5A, "FIFO desync after verb {:#06x}", spec.verb);
+    }
+    assert!(host.ec.rx.is_empty() && host.ec.tx.is_empty());
+    assert!(host.ec.unknown.is_empty());
+}
+
+#[test]
+fn host_and_ec_agree_on_the_verb_table() {
+    let mut host = sim();
+    let mut version = [0u16; ComState::LINK_PROTOCOL_VERSION.r_words as usize];
+    host.exchange(&ComState::LINK_PROTOCOL_VERSION, &[], &mut version);
+    assert!(ProtocolVersion::decode_u16(&version).is_compatible_with(&ProtocolVersion::CURRENT));
+
+    let mut remote = Vec::new();
+    let mut page = 0;
+    loop {
+        let mut words = [0u16; ComState::LINK_LIST_VERBS.r_words as usize];
+        host.exchange(&ComState::LINK_LIST_VERBS, &[page], &mut words);
+        let list = VerbListPage::decode_u16(&words);
+        remote.extend(list.verbs().copied());
+        page += 1;
+        if page >= list.page_count() {
+            break;
+        }
+    }
+    let local: Vec<VerbEntry> = ComState::ALL.iter().map(VerbEntry::from).collect();
+    assert_eq!(remote, local);
+}
+
+#[test]
+fn provisioning_replay_restores_ec_state() {
+    let mut script = Provisioner::<128>::new();
+    let mut ssid = StringSer::<STR_32_WORDS>::new();
+    script.record(&ComState::WLAN_SET_SSID, ssid.encode("precursor").unwrap()).unwrap();
+    script.record(&ComState::IMU_WOM_CONFIG, &ImuWomConfig::new(250, 1).unwrap().encode_u16()).unwrap();
+    script.record(&ComState::LINK_SET_INTMASK, &[INT_WLAN_RX_READY]).unwrap();
+
+    // a fresh EC, as after an epoch change
+    let mut host = sim();
+    script
+        .replay(|verb, payload| -> Result<(), ()> {
+            let spec = ComState::lookup(verb).unwrap();
+            host.exchange(spec, payload, &mut []);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(host.ec.state.ssid, "precursor");
+    assert_eq!(host.ec.state.wom, Some(ImuWomConfig { threshold_mg: 250, duration: 1 }));
+
+    let mut mask = [0u16; 2];
+    host.exchange(&ComState::LINK_GET_INTMASK32, &[], &mut mask);
+    assert_eq!(InterruptFlags::decode_u16(&mask), InterruptFlags::WLAN_RX_READY);
+}
+
+#[test]
+fn cred_map_round_trip() {
+    let mut host = sim();
+    let mut map = CredMap::new();
+    map.insert("precursor", 2, 0);
+    host.exchange(&ComState::WLAN_CRED_MAP_SET, &map.encode_u16(), &mut []);
+    let mut words = [0u16; ComState::WLAN_CRED_MAP_GET.r_words as usize];
+    host.exchange(&ComState::WLAN_CRED_MAP_GET, &[], &mut words);
+    assert_eq!(CredMap::decode_u16(&words).lookup("precursor"), Some(2));
+}
+
+#[test]
+fn link_sync_recovers_from_a_short_payload() {
+    let mut host = sim();
+    // a buggy host sends FLASH_ERASE with only half its payload...
+    host.ec.clock(ComState::FLASH_ERASE.verb);
+    host.ec.clock(0);
+    host.ec.clock(0);
+    // ...so the EC is still waiting for payload, and would eat the next verb as data
+    host.ec.clock(ComState::LINK_SYNC.verb);
+    let mut pong = [0u16; 2];
+    host.exchange(&ComState::LINK_PING, &[1], &mut pong);
+    assert_ne!(pong[0], !1);
+    assert_eq!(host.ec.unknown, [1]);
+
+    // once the EC is back at a verb boundary, LINK_SYNC clears both FIFOs and the link works again
+    host.ec.clock(ComState::LINK_SYNC.verb);
+    host.exchange(&ComState::LINK_PING, &[1], &mut pong);
+    assert_eq!(pong[0], !1);
+}
@@ -0,0 +1,101 @@
+//! Checks over the verb table in `ComState::ALL`: lookup, names and the per-verb metadata.
+
+use com_rs::verb::VerbClass;
+use com_rs::*;
+
+#[test]
+fn lookup_finds_every_verb() {
+    assert!(verbs_unique(ComState::ALL));
+    for spec in ComState::ALL {
+        if spec.deprecated && !cfg!(feature = "legacy-verbs") {
+            assert_eq!(ComState::lookup(spec.verb), None);
+        } else {
+            assert_eq!(ComState::lookup(spec.verb), Some(spec));
+        }
+    }
+    assert_eq!(ComState::lookup(0x0000), None);
+}
+
+#[test]
+fn categories() {
+    assert_eq!(ComState::WLAN_JOIN.category(), VerbClass::Wlan);
+    assert_eq!(ComState::SSID_SCAN_ON.category(), VerbClass::Wifi);
+    assert_eq!(ComState::CHG_BOOST_ON.category(), VerbClass::Charger);
+    assert!(ComState::in_category(VerbClass::Flash).all(|spec| spec.verb & 0xF000 == 0x3000));
+    assert!(ComState::in_category(VerbClass::Vendor).next().is_none());
+    let grouped: usize = VerbClass::ALL.iter().map(|&c| ComState::in_category(c).count()).sum();
+    assert_eq!(grouped, ComState::ALL.len());
+    // every constant lands in exactly one assigned, non-vendor class
+    for spec in ComState::ALL.iter() {
+        let mut classes = VerbClass::ALL.iter().filter(|class| class.range().contains(&spec.verb));
+        assert_eq!(classes.next(), Some(&spec.category()));
+        assert!(classes.next().is_none());
+        assert!(!matches!(spec.category(), VerbClass::Unassigned | VerbClass::Vendor), "{:#06x}", spec.verb);
+    }
+}
+
+#[test]
+fn danger_levels() {
+    assert!(ComState::FLASH_ERASE.danger.requires_confirmation());
+    assert!(ComState::POWER_SHIPMODE.danger.requires_confirmation());
+    assert!(!ComState::WLAN_JOIN.danger.requires_confirmation());
+    assert!(ComState::WLAN_MONITOR_START.danger.requires_confirmation());
+    // everything the EC audits is at least disruptive
+    for spec in serdes::AUDITED_VERBS {
+        assert!(spec.danger >= DangerLevel::Disruptive, "{:#06x} is audited but marked Safe", spec.verb);
+    }
+}
+
+#[test]
+fn names_match_constants() {
+    assert_eq!(ComState::name_of(0x2304), Some("WLAN_JOIN"));
+    assert_eq!(ComState::name_of(0x1234), None);
+    // every verb has exactly one name
+    for (i, spec) in ComState::ALL.iter().enumerate() {
+        let name = ComState::name_of(spec.verb).unwrap();
+        assert!(ComState::ALL[i + 1..].iter().all(|other| ComState::name_of(other.verb) != Some(name)), "{}", name);
+    }
+}
+
+#[test]
+fn protocol_revisions() {
+    use com_rs::serdes::ProtocolVersion;
+    assert_eq!(ComState::WLAN_JOIN.introduced_in(), ProtocolVersion::V1_0);
+    assert!(ComState::WLAN_JOIN.supported_by(&ProtocolVersion::V1_0));
+    assert_eq!(ComState::LINK_PROTOCOL_VERSION.introduced_in(), ProtocolVersion::V1_1);
+    assert!(!ComState::LINK_PROTOCOL_VERSION.supported_by(&ProtocolVersion::V1_0));
+    assert_eq!(ComState::NET_TX_CREDITS.introduced_in(), ProtocolVersion::V1_2);
+    assert_eq!(ComState::WLAN_ROAM_CONFIG.introduced_in(), ProtocolVersion::V1_3);
+    assert!(!ComState::WLAN_ROAM_CONFIG.supported_by(&ProtocolVersion::V1_2));
+    assert_eq!(ComState::WLAN_BIN_STATUS_TAGGED.introduced_in(), ProtocolVersion::V1_4);
+    assert!(!ComState::WLAN_BIN_STATUS_TAGGED.supported_by(&ProtocolVersion::V1_3));
+    assert_eq!(ProtocolVersion::CURRENT, ProtocolVersion::V1_4);
+    assert!(ComState::ALL.iter().all(|spec| spec.supported_by(&ProtocolVersion::CURRENT)));
+}
+
+#[test]
+fn net_frame_verbs() {
+    let fetch = ComState::net_frame_fetch(3).unwrap();
+    assert_eq!((fetch.verb, fetch.w_words, fetch.r_words), (0xC803, 0, 3));
+    let send = ComState::net_frame_send(0x7FF).unwrap();
+    assert_eq!((send.verb, send.w_words, send.r_words), (0xC7FF, 0x400, 0));
+    assert_eq!(ComState::net_frame_send(0x800), Err(ComError::FrameTooLong(0x800)));
+    assert_eq!(ComState::net_frame_fetch(0xFFFF), Err(ComError::FrameTooLong(0xFFFF)));
+
+    // raw injection is refused unless the EC build offers it
+    assert_eq!(ComState::net_raw_send(24, 0), Err(ComError::RawTxDisabled));
+    let raw = ComState::net_raw_send(25, serdes::PROTO_FEATURE_RAW_TX).unwrap();
+    assert_eq!((raw.verb, raw.w_words, raw.danger), (0x2819, 13, DangerLevel::Destructive));
+    assert_eq!(ComState::net_raw_send(0x800, serdes::PROTO_FEATURE_RAW_TX), Err(ComError::FrameTooLong(0x800)));
+    assert!(ComState::is_net_raw_send(raw.verb) && serdes::is_audited(raw.verb));
+    assert!(raw.danger.requires_confirmation());
+    assert!(!ComState::is_net_raw_send(ComState::NET_FRAME_SEND_1.verb));
+}
+
+#[test]
+fn spec_builder() {
+    let spec = ComSpec::new(0x2400, 1, 0, [0, 9, 9, 1]);
+    assert_eq!(spec, ComState::NET_FRAME_CONFIG);
+    let spec = spec.with_danger(DangerLevel::Disruptive).deprecated();
+    assert_eq!((spec.danger, spec.category(), spec.deprecated), (DangerLevel::Disruptive, VerbClass::Net, true));
+}
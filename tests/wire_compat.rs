@@ -0,0 +1,526 @@
+//! Wire compatibility harness.
+//!
+//! Serializes the payload structs shared by the EC and the SoC and compares the result against
+//! golden byte images (little-endian words, in FIFO order) committed here. A failure means a
+//! change would break compatibility between EC and SoC firmware built from different revisions
+//! of this crate: either revert the layout change, or bump `COM_ABI_VERSION` and update the
+//! golden image in the same commit.
+
+use com_rs::serdes::*;
+use com_rs::*;
+
+const GOLDEN_ABI_VERSION: u16 = 3;
+
+fn to_bytes(words: &[u16]) -> Vec<u8> {
+    words.iter().flat_map(|w| w.to_le_bytes()).collect()
+}
+
+fn check(name: &str, words: &[u16], golden: &[u8]) {
+    assert_eq!(
+        to_bytes(words),
+        golden,
+        "wire layout of {} changed: bump COM_ABI_VERSION and update its golden image",
+        name
+    );
+}
+
+#[test]
+fn abi_version_matches_golden_set() {
+    assert_eq!(COM_ABI_VERSION, GOLDEN_ABI_VERSION, "golden images were not regenerated for this ABI version");
+}
+
+#[test]
+fn ipv4_conf() {
+    let conf = Ipv4Conf {
+        dhcp: DhcpState::Bound,
+        mac: [0x02, 0x11, 0x22, 0x33, 0x44, 0x55],
+        addr: [192, 168, 1, 42],
+        gtwy: [192, 168, 1, 1],
+        mask: [255, 255, 255, 0],
+        dns1: [1, 1, 1, 1],
+        dns2: [8, 8, 4, 4],
+    };
+    check("Ipv4Conf", &conf.encode_u16(), &[
+        0x04, 0x00, 0x02, 0x11, 0x22, 0x33, 0x44, 0x55,
+        0xc0, 0xa8, 0x01, 0x2a, 0xc0, 0xa8, 0x01, 0x01,
+        0xff, 0xff, 0xff, 0x00, 0x01, 0x01, 0x01, 0x01,
+        0x08, 0x08, 0x04, 0x04,
+    ]);
+}
+
+#[test]
+fn string_ser() {
+    let mut ser = StringSer::<STR_32_WORDS>::new();
+    check("StringSer", ser.encode("precursor").unwrap(), &[
+        0x09, 0x00, b'p', b'r', b'e', b'c', b'u', b'r', b's', b'o', b'r', 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ]);
+}
+
+#[test]
+fn crc_trailer() {
+    let payload = [0x0000, 0x0001, 0x1000, 0x0000];
+    let trailer = crc::frame_crc(ComState::FLASH_ERASE.verb, &payload);
+    check("crc trailer", &[trailer], &[0x9f, 0xd5]);
+}
+
+#[test]
+fn subscription() {
+    let sub = Subscription {
+        source: SubscriptionSource::Rssi,
+        trigger: SubscriptionTrigger::Above,
+        threshold: 80,
+    };
+    check("Subscription", &sub.encode_u16(), &[0x01, 0x02, 0x50, 0x00]);
+}
+
+#[test]
+fn trng_stream() {
+    let mut words = [0u16; TRNG_STREAM_WORDS];
+    words[0] = 0xBEEF;
+    let stream = TrngStream { health: TRNG_HEALTH_NOT_READY, valid: 1, words };
+    let mut golden = vec![0x01, 0x80, 0xef, 0xbe];
+    golden.resize(2 * (1 + TRNG_STREAM_WORDS), 0);
+    check("TrngStream", &stream.encode_u16(), &golden);
+}
+
+#[test]
+fn link_stats() {
+    let stats = LinkStats {
+        crc_errors: 0x0001_0002,
+        dropped_words: 3,
+        desyncs: 4,
+        spurious_reads: 0xFFFF_0000,
+    };
+    check("LinkStats", &stats.encode_u16(), &[
+        0x02, 0x00, 0x01, 0x00, 0x03, 0x00, 0x00, 0x00,
+        0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff,
+    ]);
+    assert_eq!(stats, LinkStats::decode_u16(&stats.encode_u16()));
+}
+
+#[test]
+fn protocol_version() {
+    check("ProtocolVersion", &ProtocolVersion::V1_1.encode_u16(), &[
+        0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00,
+    ]);
+    check("ProtocolVersion", &ProtocolVersion::V1_4.encode_u16(), &[
+        0x01, 0x00, 0x04, 0x00, 0x00, 0x00, 0xff, 0x02, 0x00, 0x00,
+    ]);
+}
+
+#[test]
+fn verb_list_page() {
+    let table = [ComState::LINK_PING, ComState::FLASH_ERASE];
+    let mut golden = vec![0x02, 0x00, 0x05, 0x40, 0x01, 0x00, 0x02, 0x00, 0x00, 0x32, 0x04, 0x00, 0x00, 0x00];
+    golden.resize(2 * ComState::LINK_LIST_VERBS.r_words as usize, 0);
+    check("VerbListPage", &VerbListPage::from_table(&table, 0).encode_u16(), &golden);
+}
+
+#[test]
+fn interrupt_bit_migration() {
+    assert_eq!(int16_to_int32(INT_WLAN_RX_READY | INT_INVALID), 0x8000_0001);
+    assert_eq!(int16_to_int32(INT_LINK_SHUTDOWN), 0x0000_0800);
+    assert_eq!(int32_to_int16(0x8001_0002), INT_INVALID | INT_WLAN_IPCONF_UPDATE);
+}
+
+#[test]
+fn i8f8() {
+    check("I8F8", &[I8F8::from_milli(-61_250).encode_u16()], &[0xc0, 0xc2]);
+    assert_eq!(I8F8::from_milli(-61_250).to_string(), "-61.25");
+}
+
+#[test]
+fn p2p() {
+    let config = P2pConfig { go_intent: 7, channel: 6, flags: P2P_FLAG_PERSISTENT };
+    check("P2pConfig", &config.encode_u16(), &[0x07, 0x06, 0x01, 0x00]);
+    let status = P2pStatus {
+        state: P2pState::Connected,
+        role: P2pRole::Client,
+        channel: 11,
+        peer: [0x02, 0x11, 0x22, 0x33, 0x44, 0x55],
+    };
+    check("P2pStatus", &status.encode_u16(), &[0x02, 0x02, 0x0b, 0x00, 0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+    assert_eq!(status, P2pStatus::decode_u16(&status.encode_u16()));
+}
+
+#[test]
+fn interrupt_flags() {
+    let flags = InterruptFlags::from_u16(INT_WLAN_RX_READY | INT_WLAN_DISCONNECT | INT_INVALID);
+    check("InterruptFlags", &flags.encode_u16(), &[0x41, 0x00, 0x00, 0x80]);
+    assert_eq!(flags.to_string(), "WLAN_RX_READY|WLAN_DISCONNECT|INVALID");
+    assert_eq!(flags.iter().count(), 3);
+    assert!(flags.contains(InterruptFlags::WLAN_RX_READY | InterruptFlags::INVALID));
+    assert_eq!((flags - InterruptFlags::INVALID).to_u16(), INT_WLAN_RX_READY | INT_WLAN_DISCONNECT);
+    assert_eq!(InterruptFlags::from_bits(1 << 30).to_string(), "0x40000000");
+}
+
+#[test]
+fn log_escalation() {
+    let policy = LogEscalation { min_severity: LogSeverity::Error, max_per_sec: 4 };
+    check("LogEscalation", &policy.encode_u16(), &[0x04, 0x00, 0x04, 0x00]);
+}
+
+#[test]
+fn com_event() {
+    let event = ComEvent { source: InterruptFlags::WLAN_DISCONNECT, timestamp_ms: 0x0001_0203, arg: 9 };
+    check("ComEvent", &event.encode_u16(), &[0x06, 0x00, 0x03, 0x02, 0x01, 0x00, 0x09, 0x00]);
+}
+
+#[test]
+fn power_interrupt_bits() {
+    let flags = InterruptFlags::CHG_INSERTED | InterruptFlags::BATTERY_LOW | InterruptFlags::BATTERY_CRITICAL;
+    check("power interrupt bits", &flags.encode_u16(), &[0x08, 0x00, 0x0a, 0x00]);
+    assert_eq!(flags.to_string(), "BATTERY_CRITICAL|CHG_INSERTED|BATTERY_LOW");
+}
+
+#[test]
+fn log_record() {
+    let record = LogRecord::new(LogSeverity::Warn, 0x0002_0001, "hot");
+    let mut golden = vec![0x03, 0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, b'h', b'o', b't', 0x00];
+    golden.resize(2 * LogRecord::WORDS, 0);
+    check("LogRecord", &record.encode_u16(), &golden);
+}
+
+#[test]
+fn coalesce_config() {
+    let config = CoalesceConfig { source: InterruptFlags::WLAN_RX_READY, min_events: 8, max_delay_ms: 10, min_interval_ms: 1 };
+    check("CoalesceConfig", &config.encode_u16(), &[0x00, 0x00, 0x08, 0x00, 0x0a, 0x00, 0x01, 0x00]);
+}
+
+#[test]
+fn audit_record() {
+    let record = AuditRecord { verb: ComState::FLASH_ERASE.verb, uptime_ms: 0x0001_0000, result: AuditResult::Rejected };
+    check("AuditRecord", &record.encode_u16(), &[0x00, 0x32, 0x00, 0x00, 0x01, 0x00, 0x01, 0x00]);
+    assert!(is_audited(ComState::CHG_BOOST_ON.verb));
+    assert!(!is_audited(ComState::WLAN_JOIN.verb));
+}
+
+#[test]
+fn interrupt_status() {
+    let status = InterruptStatus { flags: InterruptFlags::WLAN_RX_READY | InterruptFlags::CHG_INSERTED, rx_len_bytes: 0x5EA };
+    check("InterruptStatus", &status.encode_u16(), &[0x01, 0x00, 0xea, 0x05]);
+    check("InterruptStatus32", &status.encode32_u16(), &[0x01, 0x00, 0x02, 0x00, 0xea, 0x05]);
+}
+
+#[test]
+fn cred_map() {
+    assert_eq!(ssid_hash("precursor"), 0xADCF_B474);
+    let mut map = CredMap::new();
+    map.insert("precursor", 3, 1);
+    let mut golden = vec![0x74, 0xb4, 0xcf, 0xad, 0x03, 0x01];
+    for _ in 1..CRED_MAP_LEN {
+        golden.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0xff, 0x00]);
+    }
+    check("CredMap", &map.encode_u16(), &golden);
+}
+
+#[test]
+fn imu_wom_config() {
+    let config = ImuWomConfig::new(500, 1).unwrap();
+    check("ImuWomConfig", &config.encode_u16(), &[0xf4, 0x01, 0x01, 0x00]);
+    assert_eq!(InterruptFlags::IMU_MOTION.to_string(), "IMU_MOTION");
+    check("IMU_MOTION bit", &InterruptFlags::IMU_MOTION.encode_u16(), &[0x00, 0x00, 0x10, 0x00]);
+}
+
+#[test]
+fn scan_summary() {
+    let summary = ScanSummary { total_seen: 40, available: 32, flags: SCAN_FLAG_TRUNCATED, duration_ms: 2500 };
+    check("ScanSummary", &summary.encode_u16(), &[0x28, 0x00, 0x20, 0x00, 0x01, 0x00, 0xc4, 0x09]);
+    assert!(!summary.is_complete());
+    assert_eq!(summary, ScanSummary::decode_u16(&summary.encode_u16()));
+}
+
+#[test]
+fn frag_header() {
+    use com_rs::frame::FragHeader;
+    check("FragHeader", &[FragHeader { more: true, index: 2 }.encode_u16()], &[0x02, 0x80]);
+}
+
+#[test]
+fn rx_meta() {
+    let meta = RxMeta { rssi: Rssi::from_dbm(-60), channel: 11, timestamp_us: 0x0102_0304 };
+    check("RxMeta", &meta.encode_u16(), &[0x00, 0xC4, 0x0B, 0x00, 0x04, 0x03, 0x02, 0x01]);
+}
+
+#[test]
+fn tx_completion() {
+    let record = TxCompletion { handle: 0x1234, status: TxStatus::Expired, retries: 3 };
+    check("TxCompletion", &record.encode_u16(), &[0x34, 0x12, 0x02, 0x03]);
+}
+
+#[test]
+fn bytes_ser() {
+    let mut ser = BytesSer::<4>::new();
+    check("BytesSer", ser.encode(&[0xDE, 0xAD, 0xBE]).unwrap(), &[0x03, 0x00, 0xDE, 0xAD, 0xBE, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn flash_range_with_checksum() {
+    let mut words = [0u16; 5];
+    crc::encode_with_checksum(&FlashRange { addr: 0x0008_0000, len: 0x1000 }, &mut words).unwrap();
+    check("FlashRange", &words, &[0x00, 0x00, 0x08, 0x00, 0x00, 0x10, 0x00, 0x00, 0x1F, 0x7F]);
+}
+
+#[test]
+fn scan_result() {
+    let bssid = [0x02, 0x11, 0x22, 0x33, 0x44, 0x55];
+    let result = ScanResult::new(b"abc", bssid, Rssi::from_dbm(-60), 6, Security::Wpa3Sae);
+    let mut golden = vec![0x03, 0x00, 0x61, 0x62, 0x63, 0x00];
+    golden.resize(34, 0);
+    golden.extend_from_slice(&[0x02, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0xC4, 0x06, 0x04]);
+    check("ScanResult", &result.encode_u16(), &golden);
+}
+
+#[test]
+fn scan_config() {
+    let config = ScanConfig { channels: 0x0421, dwell_ms: 120, mode: ScanMode::Passive, max_results: 16 };
+    check("ScanConfig", &config.encode_u16(), &[0x21, 0x04, 0x78, 0x00, 0x01, 0x00, 0x10, 0x00]);
+}
+
+#[test]
+fn wlan_bin_status() {
+    let ipv4 = Ipv4Conf { dhcp: DhcpState::Bound, addr: [10, 0, 0, 2], ..Default::default() };
+    let status = WlanBinStatus::new(Rcpi::from_rssi(Rssi::from_dbm(-60)), LinkState::Connected, ipv4, b"ab", Security::Wpa3Sae);
+    let mut golden = vec![0x01, 0xFA, 0x64, 0x00, 0x06, 0x00, 0x04, 0x00];
+    golden.extend_from_slice(&[0; 6]);
+    golden.extend_from_slice(&[0x0A, 0x00, 0x00, 0x02]);
+    golden.resize(34, 0);
+    golden.extend_from_slice(&[0x02, 0x00, 0x61, 0x62]);
+    golden.resize(68, 0);
+    golden.extend_from_slice(&[0x04, 0x00]);
+    check("WlanBinStatus", &status.encode_versioned(), &golden);
+    // WLAN_BIN_STATUS: the same without the format and security words
+    check("WlanBinStatus untagged", &status.encode_u16(), &golden[2..68]);
+}
+
+#[test]
+fn mac_address() {
+    let mac = MacAddress([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+    check("MacAddress", &mac.encode_u16(), &[0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+    check("MacConfig", &MacConfig::FACTORY.encode_u16(), &[0x01, 0x00, 0, 0, 0, 0, 0, 0]);
+}
+
+#[test]
+fn known_network() {
+    let net = KnownNetwork::new(2, "ab", "xyz", Flags16::empty().with(KNOWN_NET_FLAG_HIDDEN)).unwrap();
+    let mut golden = vec![0x02, 0x00, 0x02, 0x00, 0x02, 0x00, 0x61, 0x62];
+    golden.resize(38, 0);
+    golden.extend_from_slice(&[0x03, 0x00, 0x78, 0x79, 0x7A, 0x00]);
+    golden.resize(104, 0);
+    check("KnownNetwork", &net.encode_u16(), &golden);
+    check("AutojoinResult", &[AutojoinResult { slot: 2, result: ConnectResult::Success }.encode_u16()], &[0x02, 0x00]);
+}
+
+#[test]
+fn roam_config() {
+    let config = RoamConfig { trigger_rssi: Rssi::from_dbm(-70), hysteresis_db: 8, scan_interval_s: 60 };
+    check("RoamConfig", &config.encode_u16(), &[0x00, 0xBA, 0x08, 0x00, 0x3C, 0x00]);
+}
+
+#[test]
+fn eap_upload() {
+    let config = EapConfig {
+        slot: 1,
+        method: EapMethod::Ttls,
+        flags: Flags16::empty().with(EAP_FLAG_VERIFY_SERVER),
+        identity_len: 5,
+        password_len: 7,
+        ca_cert_len: 0x0300,
+    };
+    check("EapConfig", &config.encode_u16(), &[0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x05, 0x00, 0x07, 0x00, 0x00, 0x03]);
+    let mut golden = vec![0x00, 0x00, 0x02, 0x00, 0x03, 0x00, 0x61, 0x62, 0x63, 0x00];
+    golden.resize(134, 0);
+    check("EapFragment", &EapFragment::new(EapItem::Identity, 2, b"abc").encode_u16(), &golden);
+}
+
+#[test]
+fn wps() {
+    let start = WpsPinStart { slot: 1, pin: WpsPin(12345670) };
+    check("WpsPinStart", &start.encode_u16(), &[0x01, 0x00, 0x46, 0x61, 0xBC, 0x00]);
+    check("WpsResult", &[WpsResult { slot: 1, status: WpsStatus::PinRejected }.encode_u16()], &[0x01, 0x03]);
+}
+
+#[test]
+fn soft_ap() {
+    let mut config = ApConfig::new("ab", "", 11).unwrap();
+    config.max_clients = 4;
+    config.flags = Flags16::empty().with(AP_FLAG_HIDDEN);
+    let mut golden = vec![0x02, 0x00, 0x61, 0x62];
+    golden.resize(100, 0);
+    golden.extend_from_slice(&[0x0B, 0x04, 0x01, 0x00]);
+    check("ApConfig", &config.encode_u16(), &golden);
+
+    let station = ApStation { mac: MacAddress([0x02, 0x11, 0x22, 0x33, 0x44, 0x55]), rssi: Rssi::from_dbm(-50) };
+    let mut golden = vec![0x01, 0x00, 0x01, 0x00, 0x02, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00, 0xCE];
+    golden.resize(36, 0);
+    check("ApStationPage", &ApStationPage::from_stations(&[station], 0).encode_u16(), &golden);
+}
+
+#[test]
+fn country_code() {
+    check("CountryCode", &CountryCode::new("US").unwrap().encode_u16(), b"US");
+    check("CountryCode", &CountryCode::WORLD.encode_u16(), b"00");
+}
+
+#[test]
+fn tx_power() {
+    check("TxPower", &TxPower::from_dbm(17).unwrap().encode_u16(), &[0x44, 0x00]);
+}
+
+#[test]
+fn channel_list() {
+    let mut max_power = [TxPower::MIN; 14];
+    max_power[0] = TxPower::from_dbm(10).unwrap();
+    let list = ChannelList { channels: 0x0001, max_power };
+    let mut golden = vec![0x01, 0x00, 0x28, 0x00];
+    golden.resize(30, 0);
+    check("ChannelList", &list.encode_u16(), &golden);
+}
+
+#[test]
+fn mcast_filter_table() {
+    let mut table = McastFilterTable::new();
+    table.insert(MacAddress::from_ipv4_multicast([224, 0, 0, 251]));
+    let mut golden = vec![0x01, 0x00, 0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB];
+    golden.resize(50, 0);
+    check("McastFilterTable", &table.encode_u16(), &golden);
+}
+
+#[test]
+fn monitor_config() {
+    let config = MonitorConfig { channel: 11, flags: Flags16::empty().with(MONITOR_FLAG_MGMT) };
+    check("MonitorConfig", &config.encode_u16(), &[0x0B, 0x00, 0x01, 0x00]);
+}
+
+#[test]
+fn power_save_config() {
+    let config = PowerSaveConfig { mode: PowerSaveMode::Fast, listen_interval: 0, dtim_skip: 2 };
+    check("PowerSaveConfig", &config.encode_u16(), &[0x01, 0x00, 0x00, 0x00, 0x02, 0x00]);
+}
+
+#[test]
+fn phy_status() {
+    check("PhyMode", &PhyMode::Gn.encode_u16(), &[0x04, 0x00]);
+    let mut rates = RateSet::default();
+    rates.insert(PhyRate::G6Mbps);
+    rates.insert(PhyRate::Mcs7);
+    let status = PhyStatus { mode: PhyMode::Bgn, tx_rate: Some(PhyRate::Mcs7), rates };
+    check("PhyStatus", &status.encode_u16(), &[0x00, 0x00, 0x15, 0x00, 0x40, 0x00, 0x20, 0x00]);
+}
+
+#[test]
+fn mtu() {
+    check("Mtu", &Mtu::DEFAULT.encode_u16(), &[0xDC, 0x05]);
+}
+
+#[test]
+fn tx_priority() {
+    check("Priority", &[Priority::Video.encode_u16()], &[0x02, 0x00]);
+}
+
+#[test]
+fn multicast_group() {
+    let group = MulticastGroup::new([239, 255, 255, 250]).unwrap();
+    check("MulticastGroup", &group.encode_u16(), &[0xEF, 0xFF, 0xFF, 0xFA]);
+}
+
+#[test]
+fn arp_offload() {
+    let status = ArpOffloadStatus { config: ArpOffloadConfig::enabled([10, 0, 0, 2]), answered: 3 };
+    check("ArpOffloadStatus", &status.encode_u16(), &[0x01, 0x00, 0x0A, 0x00, 0x00, 0x02, 0x03, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn wake_patterns() {
+    let entry = WakePatternEntry { slot: 1, pattern: Some(WakePattern::TcpSyn { port: 443 }) };
+    check("WakePatternEntry", &entry.encode_u16(), &[0x01, 0x00, 0x02, 0x00, 0xBB, 0x01, 0x00, 0x00]);
+    let mut table = WakePatternTable::default();
+    table.patterns[0] = Some(WakePattern::MagicPacket);
+    let mut golden = vec![0x01, 0x00];
+    golden.resize(48, 0);
+    check("WakePatternTable", &table.encode_u16(), &golden);
+}
+
+#[test]
+fn keepalive() {
+    let tuple = TcpTuple { local_port: 0x1234, remote_addr: [10, 0, 0, 1], remote_port: 443 };
+    let template = KeepaliveTemplate::new(0, tuple, 30, 0x0001_0002, 0x0003_0004, 0x0800);
+    let mut golden = vec![
+        0x00, 0x00, 0x1E, 0x00, 0x34, 0x12, 0x0A, 0x00, 0x00, 0x01, 0xBB, 0x01, 0x02, 0x00, 0x01, 0x00, 0x04, 0x00, 0x03,
+        0x00, 0x00, 0x08,
+    ];
+    golden.resize(56, 0);
+    check("KeepaliveTemplate", &template.encode_u16(), &golden);
+    let status = KeepaliveStatus { slot: 2, state: KeepaliveState::Timeout, sent: 5, acked: 2 };
+    let golden = [0x02, 0x00, 0x03, 0x00, 0x05, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00];
+    check("KeepaliveStatus", &status.encode_u16(), &golden);
+}
+
+#[test]
+fn mdns() {
+    let service = MdnsService::new(1, "_ssh._tcp", "p", 22).unwrap();
+    let mut golden = vec![0x01, 0x00, 0x16, 0x00, 0x09, 0x00];
+    golden.extend_from_slice(b"_ssh._tcp\0");
+    golden.resize(2 * (2 + 17), 0);
+    golden.extend_from_slice(&[0x01, 0x00, b'p', 0x00]);
+    golden.resize(2 * (2 + 17 + 33 + 33), 0);
+    check("MdnsService", &service.encode_u16(), &golden);
+    check("MdnsStats", &MdnsStats { queries: 4, answered: 3 }.encode_u16(), &[4, 0, 0, 0, 3, 0, 0, 0]);
+}
+
+#[test]
+fn traffic_stats() {
+    let stats = TrafficStats { tx_packets: 2, rx_dropped: 0x0003_0000, ..Default::default() };
+    let mut golden = vec![0; 28];
+    golden[4] = 0x02;
+    golden[22] = 0x03;
+    check("TrafficStats", &stats.encode_u16(), &golden);
+}
+
+#[test]
+fn conn_diagnostics() {
+    let diag = ConnDiagnostics {
+        reason: Some(DisconnectReason::DeauthLeaving),
+        flags: Flags16::empty(),
+        beacon_misses: 12,
+        retry_permille: 50,
+    };
+    check("ConnDiagnostics", &diag.encode_u16(), &[0x03, 0x00, 0x00, 0x00, 0x0C, 0x00, 0x00, 0x00, 0x32, 0x00]);
+}
+
+#[test]
+fn rssi_history() {
+    check("RssiHistoryConfig", &RssiHistoryConfig::new(1000, 8).encode_u16(), &[0xE8, 0x03, 0x08, 0x00]);
+    let history = RssiHistory::from_samples(1000, &[Rssi::from_dbm(-60), Rssi::from_i8f8(I8F8::from_milli(-61_500))]);
+    let mut bytes = [0u8; 68];
+    bytes[..12].copy_from_slice(&[0xE8, 0x03, 0x02, 0x00, 0x00, 0xC4, 0x80, 0xC2, 0, 0, 0, 0]);
+    check("RssiHistory", &history.encode_u16(), &bytes);
+}
+
+#[test]
+fn rssi_filter() {
+    check("RssiFilterConfig", &RssiFilterConfig::DEFAULT.encode_u16(), &[0x03, 0x00, 0x00, 0x03]);
+    assert_eq!(InterruptFlags::WLAN_RSSI_UPDATE.bits(), 0x0400_0000);
+}
+
+#[test]
+fn wlan_err_counts() {
+    let counts = WlanErrCounts { tx_errors: 1, rx_drops: 0x0102, driver_resets: 2, alloc_fails: 0xFFFF };
+    check("WlanErrCounts", &counts.encode_u16(), &[0x01, 0x00, 0x02, 0x01, 0x02, 0x00, 0xFF, 0xFF]);
+}
+
+#[test]
+fn rssi() {
+    let rssi = Rssi::from_i8f8(I8F8::from_milli(-61_500));
+    check("Rssi", &[rssi.encode_u16()], &[0x80, 0xC2]);
+    check("Rcpi", &[Rcpi::from_rssi(rssi).encode_u16()], &[0x61, 0x00]);
+    check("Rcpi unavailable", &[Rcpi::UNAVAILABLE.encode_u16()], &[0xFF, 0x00]);
+}
+
+#[test]
+fn connect_status() {
+    let status = ConnectStatus { result: ConnectResult::Reject, detail: ConnectDetail::ApFull };
+    check("ConnectStatus", &status.encode_u16(), &[0x03, 0x00, 0x04, 0x00]);
+}